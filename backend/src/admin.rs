@@ -0,0 +1,247 @@
+use axum::{
+    extract::{rejection::QueryRejection, FromRequestParts, Query, State},
+    http::{header, request::Parts, HeaderMap},
+    response::Json,
+};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{IntoParams, Modify, ToSchema};
+
+use crate::error::{ApiError, ErrorBody};
+use crate::experiments::{self, ExperimentReport, Variant};
+use crate::redis_client::{PageId, PageStats};
+use crate::store::PageStatsStore;
+use crate::user_agent::ClientBreakdown;
+use crate::{AppState, CurrentSite};
+
+/// Days of history in an export when none are asked for
+const DEFAULT_EXPORT_DAYS: u32 = 14;
+
+/// Most days of history an export covers
+const MAX_EXPORT_DAYS: u32 = 90;
+
+/// Name of the bearer token scheme in the OpenAPI document
+const SECURITY_SCHEME: &str = "admin_token";
+
+/// Guard of the admin endpoints: the request carries `Authorization: Bearer <ADMIN_TOKEN>`.
+/// Without a configured token every admin request is refused.
+pub struct Admin;
+
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        match state.admin_token.as_deref() {
+            Some(token) if is_authorized(&parts.headers, token) => Ok(Admin),
+            _ => Err(ApiError::Unauthorized),
+        }
+    }
+}
+
+/// Whether the headers carry the bearer token, compared in constant time
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(given) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Adds the admin bearer token scheme to the OpenAPI document
+pub struct AdminSecurity;
+
+impl Modify for AdminSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                SECURITY_SCHEME,
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// Days of daily views to include, ending today (default 14, at most 90)
+    days: Option<u32>,
+}
+
+/// Stats of every page with their recent daily views
+#[derive(Serialize, ToSchema)]
+pub struct StatsExport {
+    /// Days covered by `daily_views`, oldest first (UTC, `YYYY-MM-DD`)
+    pub days: Vec<String>,
+    /// Views of the whole site per device, browser and OS
+    pub clients: ClientBreakdown,
+    pub pages: Vec<PageExport>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PageExport {
+    pub stats: PageStats,
+    /// Views on each of the export's days
+    pub daily_views: Vec<u64>,
+    /// Views per device, browser and OS
+    pub clients: ClientBreakdown,
+}
+
+/// The `days` days ending today, oldest first
+fn export_days(days: u32, today: NaiveDate) -> Vec<NaiveDate> {
+    (0..days.clamp(1, MAX_EXPORT_DAYS))
+        .rev()
+        .filter_map(|ago| today.checked_sub_days(Days::new(ago.into())))
+        .collect()
+}
+
+/// Export the stats of all pages with their daily views and clients (admin only)
+#[utoipa::path(
+    get,
+    path = "/admin/export",
+    tag = "admin",
+    params(ExportQuery),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Stats, daily views and clients of every page", body = StatsExport),
+        (status = 400, description = "Invalid query", body = ErrorBody),
+        (status = 401, description = "Missing or wrong admin token", body = ErrorBody),
+        (status = 404, description = "Unknown site", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all)]
+pub async fn export_stats(
+    _: Admin,
+    CurrentSite(site): CurrentSite,
+    query: Result<Query<ExportQuery>, QueryRejection>,
+) -> Result<Json<StatsExport>, ApiError> {
+    let Query(query) = query?;
+    let days = export_days(
+        query.days.unwrap_or(DEFAULT_EXPORT_DAYS),
+        Utc::now().date_naive(),
+    );
+    info!("Exporting stats with {} days of history", days.len());
+
+    let unavailable = |e| {
+        warn!("Failed to export stats: {}", e);
+        ApiError::StoreUnavailable
+    };
+    let all_stats = site.store.get_all_page_stats().await.map_err(unavailable)?;
+    let pages: Vec<PageId> = all_stats.iter().map(PageStats::id).collect();
+    let daily_views = site
+        .store
+        .get_daily_views(&pages, &days)
+        .await
+        .map_err(unavailable)?;
+    let clients = site.store.get_clients(&pages).await.map_err(unavailable)?;
+    let site_clients = site.store.get_site_clients().await.map_err(unavailable)?;
+
+    Ok(Json(StatsExport {
+        days: days.iter().map(|day| day.to_string()).collect(),
+        clients: site_clients,
+        pages: all_stats
+            .into_iter()
+            .zip(daily_views)
+            .zip(clients)
+            .map(|((stats, daily_views), clients)| PageExport {
+                stats,
+                daily_views,
+                clients,
+            })
+            .collect(),
+    }))
+}
+
+/// Click-through of every variant of the running experiments (admin only)
+#[utoipa::path(
+    get,
+    path = "/admin/experiments",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Experiments with the click-through of each variant", body = Vec<ExperimentReport>),
+        (status = 401, description = "Missing or wrong admin token", body = ErrorBody),
+        (status = 404, description = "Unknown site", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all)]
+pub async fn experiments_report(
+    _: Admin,
+    State(state): State<AppState>,
+    CurrentSite(site): CurrentSite,
+) -> Result<Json<Vec<ExperimentReport>>, ApiError> {
+    let experiments: Vec<(String, Vec<Variant>)> = {
+        let experiments = state.experiments.read().expect("experiments lock poisoned");
+        experiments
+            .slugs()
+            .into_iter()
+            .filter(|slug| site.config.allows_slug(slug))
+            .filter_map(|slug| Some((slug.to_string(), experiments.get(slug)?.to_vec())))
+            .collect()
+    };
+    info!("Reporting {} experiments", experiments.len());
+
+    let pages: Vec<PageId> = experiments
+        .iter()
+        .map(|(slug, _)| PageId::post(slug))
+        .collect();
+    let counts = site.store.get_experiments(&pages).await.map_err(|e| {
+        warn!("Failed to get experiment counts: {}", e);
+        ApiError::StoreUnavailable
+    })?;
+
+    Ok(Json(
+        experiments
+            .iter()
+            .zip(counts)
+            .map(|((slug, variants), counts)| experiments::report(slug, variants, &counts))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_is_authorized() {
+        let headers = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_static(value));
+            headers
+        };
+
+        assert!(is_authorized(&headers("Bearer s3cret"), "s3cret"));
+        assert!(!is_authorized(&headers("Bearer s3cre"), "s3cret"));
+        assert!(!is_authorized(&headers("Bearer s3creT"), "s3cret"));
+        assert!(!is_authorized(&headers("s3cret"), "s3cret"));
+        assert!(!is_authorized(&HeaderMap::new(), "s3cret"));
+    }
+
+    #[test]
+    fn test_export_days() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        let days: Vec<String> = export_days(3, today)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(days, ["2024-02-29", "2024-03-01", "2024-03-02"]);
+
+        assert_eq!(export_days(0, today).len(), 1);
+        assert_eq!(export_days(1000, today).len(), MAX_EXPORT_DAYS as usize);
+    }
+}
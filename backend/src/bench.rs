@@ -0,0 +1,129 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::coalesce::{CoalesceConfig, CoalescingStore};
+use crate::redis_client::{PageId, RedisPageStatsClient};
+use crate::store::PageStatsStore;
+
+/// Options of the `bench` subcommand
+#[derive(clap::Args, Debug, Clone)]
+pub struct BenchArgs {
+    /// Number of view increments per run
+    #[arg(long, default_value = "20000")]
+    pub requests: usize,
+
+    /// Concurrent clients
+    #[arg(long, default_value = "64")]
+    pub concurrency: usize,
+
+    /// Number of pages the views are spread over, few pages means hot pages
+    #[arg(long, default_value = "10")]
+    pub pages: usize,
+}
+
+/// Result of one benchmark run
+struct Run {
+    elapsed: Duration,
+    stored_views: u64,
+}
+
+/// Count `requests` views against Redis once directly and once through the coalescing
+/// layer, and print the throughput of both. Keys live under `<app_env>-bench` and are
+/// removed afterwards.
+pub async fn run(
+    redis_url: &str,
+    app_env: &str,
+    coalesce: CoalesceConfig,
+    args: BenchArgs,
+) -> anyhow::Result<()> {
+    let env_prefix = format!("{}-bench", app_env);
+    let client = RedisPageStatsClient::new(redis_url, &env_prefix).await?;
+    let pages: Vec<PageId> = (0..args.pages.max(1))
+        .map(|i| PageId::post(&format!("bench_page_{}", i)))
+        .collect();
+
+    println!(
+        "{} views from {} clients over {} pages",
+        args.requests, args.concurrency, args.pages
+    );
+    for config in [None, Some(coalesce)] {
+        let label = match &config {
+            None => "direct".to_string(),
+            Some(config) => format!(
+                "coalesced ({} ms / {} events)",
+                config.interval.as_millis(),
+                config.max_events
+            ),
+        };
+
+        for page in &pages {
+            client.delete_page_stats(page).await?;
+        }
+        let run = run_once(&client, config, &pages, &args).await?;
+        for page in &pages {
+            client.delete_page_stats(page).await?;
+        }
+
+        println!(
+            "{:<32} {:>8.0} views/s  ({:.2?}, {} views stored)",
+            label,
+            args.requests as f64 / run.elapsed.as_secs_f64(),
+            run.elapsed,
+            run.stored_views
+        );
+    }
+
+    Ok(())
+}
+
+async fn run_once(
+    client: &RedisPageStatsClient,
+    config: Option<CoalesceConfig>,
+    pages: &[PageId],
+    args: &BenchArgs,
+) -> anyhow::Result<Run> {
+    let store = Arc::new(CoalescingStore::new(client.clone(), config));
+    let flusher = tokio::spawn({
+        let store = store.clone();
+        async move { store.run_flusher().await }
+    });
+
+    let concurrency = args.concurrency.max(1);
+    let started = Instant::now();
+    let clients: Vec<_> = (0..concurrency)
+        .map(|client_id| {
+            let store = store.clone();
+            let pages = pages.to_vec();
+            let requests =
+                args.requests / concurrency + usize::from(client_id < args.requests % concurrency);
+            tokio::spawn(async move {
+                for i in 0..requests {
+                    let page = &pages[(client_id + i) % pages.len()];
+                    store.increment_views(page).await?;
+                }
+                anyhow::Ok(())
+            })
+        })
+        .collect();
+
+    for client in clients {
+        client.await??;
+    }
+    // Pending increments only count once they are stored
+    store.flush().await?;
+    let elapsed = started.elapsed();
+    flusher.abort();
+
+    let mut stored_views = 0;
+    for page in pages {
+        stored_views += store
+            .get_page_stats(page)
+            .await?
+            .map_or(0, |stats| stats.views);
+    }
+
+    Ok(Run {
+        elapsed,
+        stored_views,
+    })
+}
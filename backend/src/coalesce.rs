@@ -0,0 +1,611 @@
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Mutex as AsyncMutex, Notify};
+use tracing::{debug, warn};
+
+use crate::events::{Event, EventCount};
+use crate::experiments::{ExperimentAction, VariantCounts};
+use crate::reading_times::ReadingTime;
+use crate::redis_client::{PageId, PageStats};
+use crate::store::{Increment, PageStatsStore, StoreError, StoreResult};
+use crate::user_agent::{Client, ClientBreakdown};
+
+/// Pages whose last stored stats are kept once they have nothing pending
+const MAX_KNOWN_PAGES: usize = 10_000;
+
+/// When coalesced increments are written to the store
+#[derive(Debug, Clone)]
+pub struct CoalesceConfig {
+    /// Flush at least this often
+    pub interval: Duration,
+    /// Flush early once this many increments are waiting
+    pub max_events: usize,
+    /// Maximum time a batch write may take before the flush gives up on it
+    pub timeout: Duration,
+}
+
+#[derive(Default)]
+struct Buffer {
+    /// Stats of each page as last read from or written to the store
+    stored: HashMap<PageId, PageStats>,
+    /// Increments waiting for the next flush
+    pending: HashMap<PageId, Increment>,
+    /// Increments of the flush in progress
+    in_flight: HashMap<PageId, Increment>,
+    /// Number of increments in `pending`
+    events: usize,
+}
+
+impl Buffer {
+    fn add(target: &mut HashMap<PageId, Increment>, increment: &Increment) {
+        let entry = target
+            .entry(increment.page.clone())
+            .or_insert_with(|| Increment {
+                page: increment.page.clone(),
+                views: 0,
+                likes: 0,
+            });
+        entry.views += increment.views;
+        entry.likes += increment.likes;
+    }
+
+    /// Stored stats with the in-flight and pending increments added, as the caller will see
+    /// them once everything is flushed
+    fn current(&self, page: &PageId) -> Option<PageStats> {
+        let increments: Vec<&Increment> = [&self.in_flight, &self.pending]
+            .into_iter()
+            .filter_map(|increments| increments.get(page))
+            .collect();
+        if increments.is_empty() {
+            return self.stored.get(page).cloned();
+        }
+
+        let mut stats = self
+            .stored
+            .get(page)
+            .cloned()
+            .unwrap_or_else(|| PageStats::new(page));
+        for increment in increments {
+            stats.views += increment.views;
+            stats.likes += increment.likes;
+        }
+        Some(stats)
+    }
+
+    /// Remember stats read from the store. While a flush for the page is in progress the
+    /// read may or may not include it, so the read is ignored until the flush reports back.
+    fn update_stored(&mut self, stats: PageStats) {
+        let page = stats.id();
+        if self.in_flight.contains_key(&page) {
+            return;
+        }
+        self.stored.insert(page, stats);
+    }
+
+    fn evict(&mut self) {
+        if self.stored.len() > MAX_KNOWN_PAGES {
+            let Buffer {
+                stored,
+                pending,
+                in_flight,
+                ..
+            } = self;
+            stored.retain(|page, _| pending.contains_key(page) || in_flight.contains_key(page));
+        }
+    }
+}
+
+/// Sums view and like increments per page in memory and writes them to the store in one
+/// batch every `interval` or `max_events` increments. Callers get the stats including their
+/// increment straight away. Without a config every call goes to the store directly.
+pub struct CoalescingStore<S> {
+    inner: S,
+    config: Option<CoalesceConfig>,
+    buffer: Mutex<Buffer>,
+    /// Held while a batch is written, so flushes run one after another
+    flushing: AsyncMutex<()>,
+    flush_requested: Notify,
+    stop_requested: Notify,
+}
+
+impl<S: PageStatsStore> CoalescingStore<S> {
+    pub fn new(inner: S, config: Option<CoalesceConfig>) -> Self {
+        Self {
+            inner,
+            config,
+            buffer: Mutex::new(Buffer::default()),
+            flushing: AsyncMutex::new(()),
+            flush_requested: Notify::new(),
+            stop_requested: Notify::new(),
+        }
+    }
+
+    async fn increment(&self, increment: Increment, max_events: usize) -> StoreResult<PageStats> {
+        let page = increment.page.clone();
+        let known = self
+            .buffer
+            .lock()
+            .expect("buffer lock poisoned")
+            .stored
+            .contains_key(&page);
+
+        // The first increment of a page needs its current stats to answer with
+        if !known {
+            let stats = self
+                .inner
+                .get_page_stats(&page)
+                .await?
+                .unwrap_or_else(|| PageStats::new(&page));
+            self.buffer
+                .lock()
+                .expect("buffer lock poisoned")
+                .stored
+                .entry(page.clone())
+                .or_insert(stats);
+        }
+
+        let (stats, flush_now) = {
+            let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+            Buffer::add(&mut buffer.pending, &increment);
+            buffer.events += 1;
+            let stats = buffer.current(&page).expect("page has a pending increment");
+            (stats, buffer.events >= max_events)
+        };
+
+        if flush_now {
+            self.flush_requested.notify_one();
+        }
+        Ok(stats)
+    }
+
+    /// Write the pending increments to the store, returns the number of pages written.
+    /// A flush already in progress is waited for first. When the batch never reached the store
+    /// the increments are kept for the next flush. After a timeout or any other failure the
+    /// store may have applied it, so the increments are counted as stored and not written again.
+    pub async fn flush(&self) -> StoreResult<usize> {
+        let _flushing = self.flushing.lock().await;
+        let batch: Vec<Increment> = {
+            let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+            buffer.in_flight = std::mem::take(&mut buffer.pending);
+            buffer.events = 0;
+            buffer.in_flight.values().cloned().collect()
+        };
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let timeout = self
+            .config
+            .as_ref()
+            .map_or(Duration::MAX, |config| config.timeout);
+        let result = match tokio::time::timeout(timeout, self.inner.increment_many(&batch)).await {
+            Ok(result) => result,
+            Err(_) => Err(StoreError::Timeout),
+        };
+
+        let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+        let in_flight = std::mem::take(&mut buffer.in_flight);
+        match result {
+            Ok(updated) => {
+                for stats in updated {
+                    buffer.stored.insert(stats.id(), stats);
+                }
+                buffer.evict();
+                debug!("Flushed coalesced increments of {} pages", batch.len());
+                Ok(batch.len())
+            }
+            Err(e) if e.is_unsent() => {
+                for increment in in_flight.values() {
+                    Buffer::add(&mut buffer.pending, increment);
+                    buffer.events += (increment.views + increment.likes) as usize;
+                }
+                Err(e)
+            }
+            Err(e) => {
+                warn!(
+                    "Not retrying increments of {} pages, the store may have applied them: {}",
+                    batch.len(),
+                    e
+                );
+                for increment in in_flight.values() {
+                    if let Some(stats) = buffer.stored.get_mut(&increment.page) {
+                        stats.views += increment.views;
+                        stats.likes += increment.likes;
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Make [`CoalescingStore::run_flusher`] return once the flush it may be running is done
+    pub fn stop_flusher(&self) {
+        self.stop_requested.notify_one();
+    }
+
+    /// Flush every interval, or earlier when `max_events` is reached, until
+    /// [`CoalescingStore::stop_flusher`] is called. Returns right away when coalescing is off.
+    pub async fn run_flusher(&self) {
+        let Some(config) = &self.config else {
+            return;
+        };
+
+        let mut ticker = tokio::time::interval(config.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = self.flush_requested.notified() => {}
+                _ = self.stop_requested.notified() => return,
+            }
+
+            if let Err(e) = self.flush().await {
+                warn!("Failed to flush coalesced increments: {}", e);
+            }
+        }
+    }
+}
+
+impl<S: PageStatsStore> PageStatsStore for CoalescingStore<S> {
+    async fn get_page_stats(&self, page: &PageId) -> StoreResult<Option<PageStats>> {
+        let stats = self.inner.get_page_stats(page).await?;
+        if self.config.is_none() {
+            return Ok(stats);
+        }
+
+        let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+        if let Some(stats) = stats {
+            buffer.update_stored(stats);
+        }
+        Ok(buffer.current(page))
+    }
+
+    async fn increment_views(&self, page: &PageId) -> StoreResult<PageStats> {
+        match &self.config {
+            Some(config) => {
+                let increment = Increment {
+                    page: page.clone(),
+                    views: 1,
+                    likes: 0,
+                };
+                self.increment(increment, config.max_events).await
+            }
+            None => self.inner.increment_views(page).await,
+        }
+    }
+
+    async fn increment_likes(&self, page: &PageId) -> StoreResult<PageStats> {
+        match &self.config {
+            Some(config) => {
+                let increment = Increment {
+                    page: page.clone(),
+                    views: 0,
+                    likes: 1,
+                };
+                self.increment(increment, config.max_events).await
+            }
+            None => self.inner.increment_likes(page).await,
+        }
+    }
+
+    async fn set_reading_time(
+        &self,
+        page: &PageId,
+        reading: &ReadingTime,
+    ) -> StoreResult<PageStats> {
+        let stats = self.inner.set_reading_time(page, reading).await?;
+        if self.config.is_none() {
+            return Ok(stats);
+        }
+
+        let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+        if let Some(stored) = buffer.stored.get_mut(page) {
+            stored.time = stats.time;
+            stored.content_hash = stats.content_hash.clone();
+        } else {
+            buffer.update_stored(stats.clone());
+        }
+        Ok(buffer.current(page).unwrap_or(stats))
+    }
+
+    async fn get_all_page_stats(&self) -> StoreResult<Vec<PageStats>> {
+        let all_stats = self.inner.get_all_page_stats().await?;
+        if self.config.is_none() {
+            return Ok(all_stats);
+        }
+
+        let mut buffer = self.buffer.lock().expect("buffer lock poisoned");
+        let mut pages: Vec<PageId> = all_stats.iter().map(PageStats::id).collect();
+        pages.extend(buffer.pending.keys().cloned());
+        pages.extend(buffer.in_flight.keys().cloned());
+        pages.sort();
+        pages.dedup();
+
+        for stats in all_stats {
+            buffer.update_stored(stats);
+        }
+        Ok(pages
+            .iter()
+            .filter_map(|page| buffer.current(page))
+            .collect())
+    }
+
+    /// Increments still waiting for a flush aren't counted yet
+    async fn get_daily_views(
+        &self,
+        pages: &[PageId],
+        days: &[NaiveDate],
+    ) -> StoreResult<Vec<Vec<u64>>> {
+        self.inner.get_daily_views(pages, days).await
+    }
+
+    /// Not coalesced, country, event, experiment and client counts are a small share of
+    /// the writes
+    async fn increment_country(&self, page: &PageId, country: &str) -> StoreResult<()> {
+        self.inner.increment_country(page, country).await
+    }
+
+    async fn get_countries(&self, page: &PageId) -> StoreResult<BTreeMap<String, u64>> {
+        self.inner.get_countries(page).await
+    }
+
+    async fn increment_event(&self, page: &PageId, event: &Event) -> StoreResult<()> {
+        self.inner.increment_event(page, event).await
+    }
+
+    async fn get_events(&self, page: &PageId) -> StoreResult<BTreeMap<String, EventCount>> {
+        self.inner.get_events(page).await
+    }
+
+    async fn increment_experiment(
+        &self,
+        page: &PageId,
+        variant: usize,
+        action: ExperimentAction,
+    ) -> StoreResult<()> {
+        self.inner.increment_experiment(page, variant, action).await
+    }
+
+    async fn get_experiments(
+        &self,
+        pages: &[PageId],
+    ) -> StoreResult<Vec<BTreeMap<usize, VariantCounts>>> {
+        self.inner.get_experiments(pages).await
+    }
+
+    async fn increment_client(&self, page: &PageId, client: &Client) -> StoreResult<()> {
+        self.inner.increment_client(page, client).await
+    }
+
+    async fn get_clients(&self, pages: &[PageId]) -> StoreResult<Vec<ClientBreakdown>> {
+        self.inner.get_clients(pages).await
+    }
+
+    async fn get_site_clients(&self) -> StoreResult<ClientBreakdown> {
+        self.inner.get_site_clients().await
+    }
+
+    async fn increment_many(&self, increments: &[Increment]) -> StoreResult<Vec<PageStats>> {
+        self.inner.increment_many(increments).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::testing::FakeStore;
+    use std::sync::atomic::Ordering;
+
+    fn coalescing(max_events: usize) -> CoalescingStore<FakeStore> {
+        CoalescingStore::new(
+            FakeStore::default(),
+            Some(CoalesceConfig {
+                interval: Duration::from_secs(60),
+                max_events,
+                timeout: Duration::from_secs(1),
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_increments_are_summed_into_one_batch() {
+        let store = coalescing(100);
+
+        for expected in 1..=5 {
+            assert_eq!(
+                store
+                    .increment_views(&PageId::post("a"))
+                    .await
+                    .unwrap()
+                    .views,
+                expected
+            );
+        }
+        assert_eq!(
+            store
+                .increment_likes(&PageId::post("a"))
+                .await
+                .unwrap()
+                .likes,
+            1
+        );
+        assert_eq!(store.inner.views("a"), 0);
+
+        assert_eq!(store.flush().await.unwrap(), 1);
+        assert_eq!(store.inner.views("a"), 5);
+        // One read for the first increment, one batch write
+        assert_eq!(store.inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reads_include_pending_increments() {
+        let store = coalescing(100);
+        store.increment_views(&PageId::post("a")).await.unwrap();
+        store.increment_views(&PageId::post("b")).await.unwrap();
+
+        assert_eq!(
+            store
+                .get_page_stats(&PageId::post("a"))
+                .await
+                .unwrap()
+                .unwrap()
+                .views,
+            1
+        );
+        let all = store.get_all_page_stats().await.unwrap();
+        assert_eq!(all.iter().map(|stats| stats.views).sum::<u64>(), 2);
+
+        store.flush().await.unwrap();
+        assert_eq!(
+            store
+                .get_page_stats(&PageId::post("a"))
+                .await
+                .unwrap()
+                .unwrap()
+                .views,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reading_time_replaces_the_buffered_one() {
+        let store = coalescing(100);
+        let page = PageId::post("a");
+        store.increment_views(&page).await.unwrap();
+
+        for words in [200, 400] {
+            let reading = ReadingTime::of(&"word ".repeat(words));
+            let stats = store.set_reading_time(&page, &reading).await.unwrap();
+            assert!(!stats.needs_reading_time(&reading));
+            assert_eq!(stats.views, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_keeps_increments() {
+        let store = coalescing(100);
+        store.increment_views(&PageId::post("a")).await.unwrap();
+
+        store.inner.down.store(true, Ordering::SeqCst);
+        assert!(store.flush().await.is_err());
+        assert_eq!(
+            store
+                .increment_views(&PageId::post("a"))
+                .await
+                .unwrap()
+                .views,
+            2
+        );
+
+        store.inner.down.store(false, Ordering::SeqCst);
+        store.flush().await.unwrap();
+        assert_eq!(store.inner.views("a"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_flusher_flushes_at_max_events() {
+        let store = std::sync::Arc::new(coalescing(3));
+        let flusher = tokio::spawn({
+            let store = store.clone();
+            async move { store.run_flusher().await }
+        });
+
+        for _ in 0..3 {
+            store.increment_views(&PageId::post("a")).await.unwrap();
+        }
+        for _ in 0..50 {
+            if store.inner.views("a") == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        flusher.abort();
+
+        assert_eq!(store.inner.views("a"), 3);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_batch_is_not_written_twice() {
+        let store = CoalescingStore::new(
+            FakeStore {
+                answer_delay: Some(Duration::from_millis(200)),
+                ..Default::default()
+            },
+            Some(CoalesceConfig {
+                interval: Duration::from_secs(60),
+                max_events: 100,
+                timeout: Duration::from_millis(50),
+            }),
+        );
+        store.increment_views(&PageId::post("a")).await.unwrap();
+
+        // The batch is applied, only the answer is too late
+        assert!(matches!(store.flush().await, Err(StoreError::Timeout)));
+        assert_eq!(store.inner.views("a"), 1);
+        assert_eq!(store.flush().await.unwrap(), 0);
+        assert_eq!(store.inner.views("a"), 1);
+
+        assert_eq!(
+            store
+                .increment_views(&PageId::post("a"))
+                .await
+                .unwrap()
+                .views,
+            2
+        );
+        assert!(matches!(store.flush().await, Err(StoreError::Timeout)));
+        assert_eq!(store.inner.views("a"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_flush_waits_for_the_one_in_flight() {
+        let store = std::sync::Arc::new(CoalescingStore::new(
+            FakeStore {
+                delay: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+            Some(CoalesceConfig {
+                interval: Duration::from_secs(60),
+                max_events: 100,
+                timeout: Duration::from_secs(1),
+            }),
+        ));
+        store.increment_views(&PageId::post("a")).await.unwrap();
+
+        let first = tokio::spawn({
+            let store = store.clone();
+            async move { store.flush().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        store.increment_views(&PageId::post("a")).await.unwrap();
+
+        // Runs after the first flush instead of skipping, so nothing is left behind
+        assert_eq!(store.flush().await.unwrap(), 1);
+        assert_eq!(first.await.unwrap().unwrap(), 1);
+        assert_eq!(store.inner.views("a"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stopped_flusher_returns() {
+        let store = std::sync::Arc::new(coalescing(100));
+        let flusher = tokio::spawn({
+            let store = store.clone();
+            async move { store.run_flusher().await }
+        });
+
+        store.stop_flusher();
+        tokio::time::timeout(Duration::from_secs(1), flusher)
+            .await
+            .expect("flusher stopped")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_without_config_calls_go_straight_through() {
+        let store = CoalescingStore::new(FakeStore::default(), None);
+        store.increment_views(&PageId::post("a")).await.unwrap();
+        assert_eq!(store.inner.views("a"), 1);
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+pub use content_model::PostFrontmatter;
+
+/// A post read from `content/posts/<slug>.md`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Post {
+    pub slug: String,
+    pub frontmatter: PostFrontmatter,
+    pub body: String, // Raw markdown content
+}
+
+/// Split a markdown file into frontmatter and body. Files without frontmatter (pages) are all
+/// body; files with invalid frontmatter are logged and keep the default frontmatter.
+pub fn parse_post(slug: &str, raw: &str) -> Post {
+    let (frontmatter, body) = match content_model::parse(raw) {
+        Ok(parsed) => parsed,
+        Err(content_model::Error::MissingFrontmatter) => (PostFrontmatter::default(), raw),
+        Err(e) => {
+            warn!("Invalid frontmatter in {}: {}", slug, e);
+            let body = content_model::split(raw).map_or(raw, |(_, body)| body);
+            (PostFrontmatter::default(), body)
+        }
+    };
+
+    Post {
+        slug: slug.to_string(),
+        frontmatter,
+        body: body.to_string(),
+    }
+}
+
+/// Load all posts from the posts directory, files that can't be read are skipped
+pub fn load_posts(posts_dir: &Path) -> io::Result<Vec<Post>> {
+    let mut posts = Vec::new();
+
+    for (slug, path) in content_model::markdown_files(posts_dir)? {
+        match fs::read_to_string(&path) {
+            Ok(raw) => posts.push(parse_post(&slug, &raw)),
+            Err(e) => warn!("Could not read {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(posts)
+}
+
+/// Names, sizes and modification times of the posts, used to detect edits
+fn fingerprint(posts_dir: &Path) -> Vec<(PathBuf, u64, Option<SystemTime>)> {
+    content_model::markdown_files(posts_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, path)| {
+            let metadata = fs::metadata(&path).ok();
+            let len = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+            let modified = metadata.and_then(|m| m.modified().ok());
+            (path, len, modified)
+        })
+        .collect()
+}
+
+/// Poll the posts directory and call `on_change` with the reloaded posts after every edit
+pub fn watch_posts<F>(posts_dir: PathBuf, interval: Duration, on_change: F)
+where
+    F: Fn(Vec<Post>) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut last = fingerprint(&posts_dir);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let current = fingerprint(&posts_dir);
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            match load_posts(&posts_dir) {
+                Ok(posts) => {
+                    info!("Posts changed, reloaded {} posts", posts.len());
+                    on_change(posts);
+                }
+                Err(e) => warn!("Failed to reload posts: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_post_with_frontmatter() {
+        let raw = "---\ntitle: \"Hello: World\"\ndate: \"2024-01-01\"\ntags: rust, wasm\ncategory: code\npublished: false\n---\n\nBody text";
+        let post = parse_post("hello", raw);
+
+        assert_eq!(post.slug, "hello");
+        assert_eq!(post.frontmatter.title, "Hello: World");
+        assert_eq!(post.frontmatter.date_string(), "2024-01-01");
+        assert_eq!(post.frontmatter.tags, vec!["rust", "wasm"]);
+        assert_eq!(post.frontmatter.category, "code");
+        assert!(!post.frontmatter.published);
+        assert_eq!(post.body, "Body text");
+    }
+
+    #[test]
+    fn test_parse_post_multiline_summary() {
+        let raw = "---\ntitle: Multi\nsummary: \"First line\nsecond line\"\ntags: [\"a\", 'b']\n---\nBody";
+        let post = parse_post("multi", raw);
+
+        assert_eq!(post.frontmatter.summary, "First line second line");
+        assert_eq!(post.frontmatter.tags, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_parse_post_with_invalid_frontmatter() {
+        let post = parse_post("broken", "---\ntitle: [unclosed\n---\nStill readable");
+        assert_eq!(post.frontmatter, PostFrontmatter::default());
+        assert_eq!(post.body, "Still readable");
+    }
+
+    #[test]
+    fn test_parse_post_without_frontmatter() {
+        let post = parse_post("plain", "Just content");
+        assert_eq!(post.frontmatter, PostFrontmatter::default());
+        assert_eq!(post.body, "Just content");
+    }
+}
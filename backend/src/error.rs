@@ -0,0 +1,220 @@
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// Errors returned by the API, rendered as a JSON [`ErrorBody`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// Slug is empty, too long or contains characters other than `[A-Za-z0-9_-]`
+    InvalidSlug(String),
+    /// Content kind is not one of "post", "page" or "note"
+    InvalidKind(String),
+    /// `increment_type` is not one of "views" or "likes"
+    InvalidIncrementType(String),
+    /// Event name is not in the allow-list of the site
+    UnknownEvent(String),
+    /// Query string or JSON body could not be parsed
+    InvalidRequest(String),
+    /// No site is configured for the Host header
+    UnknownSite(String),
+    /// The slug isn't in the slug list of the site
+    UnknownPage(String),
+    /// No post with this slug in the content directory
+    UnknownPost(String),
+    /// The post has no title or summary variants
+    UnknownExperiment(String),
+    /// Admin endpoint called without the configured admin token
+    Unauthorized,
+    /// The stats store (Redis) could not be reached or returned an error
+    StoreUnavailable,
+    /// Too many write requests from this client, retry after the given duration
+    RateLimited { retry_after: Duration },
+}
+
+/// JSON body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ErrorBody {
+    /// Machine readable error code, e.g. "invalid_slug"
+    pub code: String,
+    /// Human readable description of the error
+    pub message: String,
+    /// Identifier of the failed request, also found in the server logs
+    pub request_id: String,
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidSlug(_)
+            | ApiError::InvalidKind(_)
+            | ApiError::InvalidIncrementType(_)
+            | ApiError::UnknownEvent(_)
+            | ApiError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::UnknownSite(_)
+            | ApiError::UnknownPage(_)
+            | ApiError::UnknownPost(_)
+            | ApiError::UnknownExperiment(_) => StatusCode::NOT_FOUND,
+            ApiError::StoreUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    /// Stable, machine readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidSlug(_) => "invalid_slug",
+            ApiError::InvalidKind(_) => "invalid_kind",
+            ApiError::InvalidIncrementType(_) => "invalid_increment_type",
+            ApiError::UnknownEvent(_) => "unknown_event",
+            ApiError::InvalidRequest(_) => "invalid_request",
+            ApiError::UnknownSite(_) => "unknown_site",
+            ApiError::UnknownPage(_) => "unknown_page",
+            ApiError::UnknownPost(_) => "unknown_post",
+            ApiError::UnknownExperiment(_) => "unknown_experiment",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::StoreUnavailable => "store_unavailable",
+            ApiError::RateLimited { .. } => "rate_limited",
+        }
+    }
+
+    /// Human readable message, safe to show to readers
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::InvalidSlug(slug) => format!("Invalid slug: '{}'", slug),
+            ApiError::InvalidKind(kind) => format!(
+                "Invalid content kind '{}', expected 'post', 'page' or 'note'",
+                kind
+            ),
+            ApiError::InvalidIncrementType(increment_type) => format!(
+                "Invalid increment type '{}', expected 'views' or 'likes'",
+                increment_type
+            ),
+            ApiError::UnknownEvent(name) => format!("Unknown event: '{}'", name),
+            ApiError::InvalidRequest(reason) => format!("Invalid request: {}", reason),
+            ApiError::UnknownSite(host) => format!("No site is configured for host '{}'", host),
+            ApiError::UnknownPage(slug) => format!("Unknown page: '{}'", slug),
+            ApiError::UnknownPost(slug) => format!("Unknown post: '{}'", slug),
+            ApiError::UnknownExperiment(slug) => {
+                format!("Post '{}' has no title or summary variants", slug)
+            }
+            ApiError::Unauthorized => "A valid admin token is required".to_string(),
+            ApiError::StoreUnavailable => {
+                "Stats are temporarily unavailable, please try again later".to_string()
+            }
+            ApiError::RateLimited { retry_after } => format!(
+                "Too many requests, please retry in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::InvalidRequest(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::InvalidRequest(rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let request_id = crate::telemetry::current_request_id()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        warn!("Request {} failed: {}", request_id, self);
+
+        let body = ErrorBody {
+            code: self.code().to_string(),
+            message: self.message(),
+            request_id,
+        };
+
+        let mut response = (self.status(), Json(body)).into_response();
+        if let ApiError::RateLimited { retry_after } = self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after.as_secs().max(1)),
+            );
+        }
+        response
+    }
+}
+
+/// Maximum length of a slug
+const MAX_SLUG_LENGTH: usize = 128;
+
+/// Check a slug before it ends up in a store key
+pub fn validate_slug(slug: &str) -> Result<(), ApiError> {
+    let valid = !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LENGTH
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::InvalidSlug(
+            slug.chars().take(MAX_SLUG_LENGTH).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_slug() {
+        assert!(validate_slug("240125_rust_on_esp32_2_hardware").is_ok());
+        assert!(validate_slug("home").is_ok());
+        assert!(validate_slug("my-post").is_ok());
+
+        assert!(validate_slug("").is_err());
+        assert!(validate_slug("a:b").is_err());
+        assert!(validate_slug("../etc").is_err());
+        assert!(validate_slug(&"a".repeat(MAX_SLUG_LENGTH + 1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_error_response_body() {
+        let response = ApiError::InvalidIncrementType("reads".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: ErrorBody = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body.code, "invalid_increment_type");
+        assert!(body.message.contains("reads"));
+        assert!(!body.request_id.is_empty());
+    }
+
+    #[test]
+    fn test_rate_limited_sets_retry_after() {
+        let response = ApiError::RateLimited {
+            retry_after: Duration::from_secs(30),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+
+/// Event names counted when neither the site nor `--event-names` lists any
+pub const DEFAULT_EVENT_NAMES: &str = "copy_code,outbound_link,image_open,posts_filter";
+
+/// Most properties counted per event
+const MAX_PROPS: usize = 4;
+
+/// Maximum length of a property name
+const MAX_PROP_NAME_LENGTH: usize = 32;
+
+/// Maximum length of a property value, longer values are cut off
+const MAX_PROP_VALUE_LENGTH: usize = 100;
+
+/// An interaction on a page, checked against the allow-list of the site
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub props: BTreeMap<String, String>,
+}
+
+impl Event {
+    /// Check the name is allowed and the properties fit in a few hash fields
+    pub fn new(
+        name: &str,
+        props: BTreeMap<String, String>,
+        allowed: &HashSet<String>,
+    ) -> Result<Self, ApiError> {
+        if !allowed.contains(name) {
+            return Err(ApiError::UnknownEvent(name.chars().take(32).collect()));
+        }
+        if props.len() > MAX_PROPS {
+            return Err(ApiError::InvalidRequest(format!(
+                "at most {} props per event",
+                MAX_PROPS
+            )));
+        }
+        if let Some(key) = props.keys().find(|key| !valid_prop_name(key)) {
+            return Err(ApiError::InvalidRequest(format!(
+                "invalid prop name '{}'",
+                key.chars().take(MAX_PROP_NAME_LENGTH).collect::<String>()
+            )));
+        }
+
+        let props = props
+            .into_iter()
+            .map(|(key, value)| (key, value.chars().take(MAX_PROP_VALUE_LENGTH).collect()))
+            .collect();
+        Ok(Event {
+            name: name.to_string(),
+            props,
+        })
+    }
+
+    /// Fields counted for this event in a page's events hash: the name, and
+    /// `<name>:<prop>=<value>` for every property
+    pub fn fields(&self) -> Vec<String> {
+        std::iter::once(self.name.clone())
+            .chain(
+                self.props
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}={}", self.name, key, value)),
+            )
+            .collect()
+    }
+}
+
+fn valid_prop_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PROP_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// How often an event happened on a page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EventCount {
+    pub count: u64,
+    /// Counts per property and value, e.g. `{"language": {"rust": 3}}`
+    pub props: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// Counts per event name from the fields of an events hash, see [`Event::fields`]
+pub fn counts_from_fields(fields: HashMap<String, u64>) -> BTreeMap<String, EventCount> {
+    let mut counts: BTreeMap<String, EventCount> = BTreeMap::new();
+    for (field, count) in fields {
+        match field.split_once(':') {
+            None => counts.entry(field).or_default().count = count,
+            Some((name, prop)) => {
+                let Some((key, value)) = prop.split_once('=') else {
+                    continue;
+                };
+                counts
+                    .entry(name.to_string())
+                    .or_default()
+                    .props
+                    .entry(key.to_string())
+                    .or_default()
+                    .insert(value.to_string(), count);
+            }
+        }
+    }
+    counts
+}
+
+/// Parse a comma separated list of event names
+pub fn parse_event_names(names: &str) -> HashSet<String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_event_validation() {
+        let allowed = parse_event_names(DEFAULT_EVENT_NAMES);
+
+        let event = Event::new("copy_code", props(&[("language", "rust")]), &allowed).unwrap();
+        assert_eq!(event.fields(), ["copy_code", "copy_code:language=rust"]);
+
+        assert_eq!(
+            Event::new("purchase", BTreeMap::new(), &allowed),
+            Err(ApiError::UnknownEvent("purchase".to_string()))
+        );
+        assert!(Event::new("copy_code", props(&[("Language", "rust")]), &allowed).is_err());
+        assert!(Event::new("copy_code", props(&[("a=b", "rust")]), &allowed).is_err());
+        let too_many = props(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4"), ("e", "5")]);
+        assert!(Event::new("copy_code", too_many, &allowed).is_err());
+
+        let long = "x".repeat(500);
+        let event = Event::new("outbound_link", props(&[("host", &long)]), &allowed).unwrap();
+        assert_eq!(event.props["host"].len(), MAX_PROP_VALUE_LENGTH);
+    }
+
+    #[test]
+    fn test_counts_from_fields() {
+        let fields = HashMap::from([
+            ("copy_code".to_string(), 3),
+            ("copy_code:language=rust".to_string(), 2),
+            ("copy_code:language=toml".to_string(), 1),
+            ("image_open".to_string(), 1),
+        ]);
+
+        let counts = counts_from_fields(fields);
+        assert_eq!(counts["copy_code"].count, 3);
+        assert_eq!(counts["copy_code"].props["language"]["rust"], 2);
+        assert_eq!(counts["image_open"].count, 1);
+        assert!(counts["image_open"].props.is_empty());
+    }
+}
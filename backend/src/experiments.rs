@@ -0,0 +1,306 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+use crate::content::Post;
+
+/// Title and summary shown to the visitors of one variant of a post
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Variant {
+    pub title: String,
+    pub summary: String,
+}
+
+/// Variants of the posts with `title_variants` or `summary_variants` in their frontmatter
+#[derive(Debug, Default)]
+pub struct Experiments {
+    posts: HashMap<String, Vec<Variant>>,
+}
+
+impl Experiments {
+    /// Replace the experiments with those of these posts, drafts don't run experiments
+    pub fn update(&mut self, posts: &[Post]) {
+        self.posts = posts
+            .iter()
+            .filter(|post| post.frontmatter.published)
+            .filter_map(|post| Some((post.slug.clone(), variants(post)?)))
+            .collect();
+    }
+
+    /// Variants of a post, the first is its own title and summary
+    pub fn get(&self, slug: &str) -> Option<&[Variant]> {
+        self.posts.get(slug).map(Vec::as_slice)
+    }
+
+    /// Slugs of the posts with an experiment, sorted
+    pub fn slugs(&self) -> Vec<&str> {
+        let mut slugs: Vec<&str> = self.posts.keys().map(String::as_str).collect();
+        slugs.sort_unstable();
+        slugs
+    }
+}
+
+/// Variant `i` takes the `i`-th title and summary variant, where the post's own title and
+/// summary are variant 0. A list shorter than the other keeps the original for the rest.
+fn variants(post: &Post) -> Option<Vec<Variant>> {
+    let frontmatter = &post.frontmatter;
+    let count = frontmatter
+        .title_variants
+        .len()
+        .max(frontmatter.summary_variants.len());
+    if count == 0 {
+        return None;
+    }
+
+    let pick = |variants: &[String], original: &str, i: usize| {
+        i.checked_sub(1)
+            .and_then(|i| variants.get(i))
+            .map_or(original, String::as_str)
+            .to_string()
+    };
+    Some(
+        (0..=count)
+            .map(|i| Variant {
+                title: pick(&frontmatter.title_variants, &frontmatter.title, i),
+                summary: pick(&frontmatter.summary_variants, &frontmatter.summary, i),
+            })
+            .collect(),
+    )
+}
+
+/// Variant a visitor sees, the same for every request with the same salt, visitor and post.
+/// The visitor is only hashed, never stored.
+pub fn assign(salt: &str, visitor: &str, slug: &str, variants: usize) -> usize {
+    // FNV-1a, stable across builds unlike the std hasher
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in [salt, visitor, slug].join("\0").bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash % variants.max(1) as u64) as usize
+}
+
+/// Whether a visitor id the frontend keeps in localStorage looks like one (a UUID or similar
+/// token), anything else is ignored
+pub fn valid_visitor_id(id: &str) -> bool {
+    (8..=64).contains(&id.len()) && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+/// Whether `APP_ENV` names a production environment, which needs a fixed `EXPERIMENT_SALT`
+pub fn is_production(app_env: &str) -> bool {
+    app_env.eq_ignore_ascii_case("prod") || app_env.eq_ignore_ascii_case("production")
+}
+
+/// What happened to a variant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExperimentAction {
+    /// The variant was shown in the list of posts
+    Impression,
+    /// The variant was clicked to open the post
+    Click,
+}
+
+impl ExperimentAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExperimentAction::Impression => "impressions",
+            ExperimentAction::Click => "clicks",
+        }
+    }
+
+    /// Field of a variant in a post's experiment hash, e.g. `clicks:1`
+    pub fn field(self, variant: usize) -> String {
+        format!("{}:{}", self.as_str(), variant)
+    }
+}
+
+/// Impressions and clicks of one variant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VariantCounts {
+    pub impressions: u64,
+    pub clicks: u64,
+}
+
+/// Counts per variant from the fields of an experiment hash, see [`ExperimentAction::field`]
+pub fn counts_from_fields(fields: HashMap<String, u64>) -> BTreeMap<usize, VariantCounts> {
+    let mut counts: BTreeMap<usize, VariantCounts> = BTreeMap::new();
+    for (field, count) in fields {
+        let Some((action, variant)) = field.split_once(':') else {
+            continue;
+        };
+        let Ok(variant) = variant.parse() else {
+            continue;
+        };
+        let counts = counts.entry(variant).or_default();
+        match action {
+            "impressions" => counts.impressions = count,
+            "clicks" => counts.clicks = count,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// Click-through of a variant compared with variant 0
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct VariantReport {
+    pub variant: usize,
+    pub title: String,
+    pub summary: String,
+    pub impressions: u64,
+    pub clicks: u64,
+    /// Clicks per impression, 0 without impressions
+    pub ctr: f64,
+    /// Two-proportion z-score of the click-through against variant 0, positive when better
+    pub z_score: Option<f64>,
+    /// Two-sided p-value of the z-score, below 0.05 is usually taken as significant
+    pub p_value: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ExperimentReport {
+    pub slug: String,
+    pub variants: Vec<VariantReport>,
+}
+
+/// Report the variants of a post with their counts, comparing each with variant 0
+pub fn report(
+    slug: &str,
+    variants: &[Variant],
+    counts: &BTreeMap<usize, VariantCounts>,
+) -> ExperimentReport {
+    let count = |variant| counts.get(&variant).copied().unwrap_or_default();
+    let control = count(0);
+
+    let variants = variants
+        .iter()
+        .enumerate()
+        .map(|(i, variant)| {
+            let counts = count(i);
+            let z_score = (i > 0).then(|| z_score(control, counts)).flatten();
+            VariantReport {
+                variant: i,
+                title: variant.title.clone(),
+                summary: variant.summary.clone(),
+                impressions: counts.impressions,
+                clicks: counts.clicks,
+                ctr: ctr(counts),
+                z_score,
+                p_value: z_score.map(|z| 2.0 * (1.0 - normal_cdf(z.abs()))),
+            }
+        })
+        .collect();
+
+    ExperimentReport {
+        slug: slug.to_string(),
+        variants,
+    }
+}
+
+fn ctr(counts: VariantCounts) -> f64 {
+    if counts.impressions == 0 {
+        0.0
+    } else {
+        counts.clicks as f64 / counts.impressions as f64
+    }
+}
+
+/// Two-proportion z-test, `None` when either side has no impressions or the pooled
+/// click-through is 0 or 1
+fn z_score(control: VariantCounts, variant: VariantCounts) -> Option<f64> {
+    if control.impressions == 0 || variant.impressions == 0 {
+        return None;
+    }
+    let (n1, n2) = (control.impressions as f64, variant.impressions as f64);
+    let pooled = (control.clicks + variant.clicks) as f64 / (n1 + n2);
+    let error = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    (error > 0.0).then(|| (ctr(variant) - ctr(control)) / error)
+}
+
+/// Standard normal CDF, with the error function approximation of Abramowitz and Stegun
+/// (7.1.26, accurate to 1.5e-7)
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::parse_post;
+
+    const POST: &str = "---\ntitle: Original\nsummary: Plain\ntitle_variants: [\"Bold, new\", \"Quiet\"]\nsummary_variants: [\"Catchy\"]\n---\nBody";
+
+    #[test]
+    fn test_variants_from_frontmatter() {
+        let mut experiments = Experiments::default();
+        experiments.update(&[parse_post("test", POST), parse_post("plain", "No variants")]);
+
+        let variants = experiments.get("test").unwrap();
+        let titles: Vec<&str> = variants.iter().map(|v| v.title.as_str()).collect();
+        let summaries: Vec<&str> = variants.iter().map(|v| v.summary.as_str()).collect();
+        assert_eq!(titles, ["Original", "Bold, new", "Quiet"]);
+        assert_eq!(summaries, ["Plain", "Catchy", "Plain"]);
+        assert!(experiments.get("plain").is_none());
+        assert_eq!(experiments.slugs(), ["test"]);
+    }
+
+    #[test]
+    fn test_assignment_is_sticky_and_spread() {
+        let first = assign("salt", "203.0.113.7 Firefox", "test", 3);
+        assert_eq!(first, assign("salt", "203.0.113.7 Firefox", "test", 3));
+
+        let mut seen = [0; 3];
+        for visitor in 0..300 {
+            seen[assign("salt", &visitor.to_string(), "test", 3)] += 1;
+        }
+        assert!(seen.iter().all(|&count| count > 50), "{:?}", seen);
+    }
+
+    #[test]
+    fn test_visitor_ids() {
+        assert!(valid_visitor_id("0f8c2a4e-5b1d-4c3a-9e7f-1a2b3c4d5e6f"));
+        assert!(!valid_visitor_id("short"));
+        assert!(!valid_visitor_id("not a token"));
+        assert!(!valid_visitor_id(&"a".repeat(65)));
+
+        assert!(is_production("production"));
+        assert!(is_production("PROD"));
+        assert!(!is_production("dev"));
+    }
+
+    #[test]
+    fn test_report_significance() {
+        let variants = parse_post("test", POST);
+        let variants = super::variants(&variants).unwrap();
+        let counts = counts_from_fields(HashMap::from([
+            ("impressions:0".to_string(), 1000),
+            ("clicks:0".to_string(), 50),
+            ("impressions:1".to_string(), 1000),
+            ("clicks:1".to_string(), 80),
+        ]));
+
+        let report = report("test", &variants, &counts);
+        let [control, better, unseen] = report.variants.as_slice() else {
+            panic!("expected 3 variants");
+        };
+        assert_eq!(control.z_score, None);
+        assert_eq!(control.ctr, 0.05);
+        assert!(better.z_score.unwrap() > 2.0);
+        assert!(better.p_value.unwrap() < 0.05);
+        assert_eq!(unseen.impressions, 0);
+        assert_eq!(unseen.p_value, None);
+
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.0) - 0.1587).abs() < 1e-4);
+    }
+}
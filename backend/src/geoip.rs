@@ -0,0 +1,118 @@
+use axum::extract::{ConnectInfo, FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use maxminddb::{geoip2, Reader};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+use crate::rate_limit::{client_ip, TrustedProxies};
+
+/// Country lookups in a local MaxMind database (GeoLite2-Country, GeoIP2-Country or -City)
+pub struct GeoIp {
+    reader: Reader<Vec<u8>>,
+}
+
+impl GeoIp {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let reader = Reader::open_readfile(path)
+            .map_err(|e| anyhow::anyhow!("Could not open {}: {}", path.display(), e))?;
+        Ok(Self { reader })
+    }
+
+    /// Database type from the metadata, e.g. `GeoLite2-Country`
+    pub fn database_type(&self) -> &str {
+        &self.reader.metadata.database_type
+    }
+
+    /// ISO 3166-1 alpha-2 code of the country the address is in, falling back to the country
+    /// the network is registered in. None for addresses that aren't in the database.
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: geoip2::Country = self.reader.lookup(ip).ok()?;
+        record
+            .country
+            .and_then(|country| country.iso_code)
+            .or_else(|| {
+                record
+                    .registered_country
+                    .and_then(|country| country.iso_code)
+            })
+            .map(str::to_string)
+    }
+}
+
+/// Address of the client, resolved like the rate limiter does (see [`client_ip`]).
+/// It is only used for the country lookup and never stored.
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    TrustedProxies: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Infallible> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        Ok(ClientIp(client_ip(
+            &parts.headers,
+            peer,
+            TrustedProxies::from_ref(state),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    /// Generated by tools/make_geoip_fixture.py
+    fn fixture() -> GeoIp {
+        GeoIp::open(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/geoip-country.mmdb"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_country_lookup() {
+        let geoip = fixture();
+        let country = |ip: &str| geoip.country(ip.parse().unwrap());
+
+        assert_eq!(geoip.database_type(), "GeoIP2-Country");
+        assert_eq!(country("81.2.69.142").as_deref(), Some("GB"));
+        assert_eq!(country("89.160.20.1").as_deref(), Some("SE"));
+        assert_eq!(country("2001:db8::1").as_deref(), Some("NL"));
+        assert_eq!(country("127.0.0.1"), None);
+        assert_eq!(country("2001:db9::1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_client_ip_uses_trusted_proxies() {
+        let request = |forwarded: Option<&str>| {
+            let mut builder = Request::builder();
+            if let Some(forwarded) = forwarded {
+                builder = builder.header("x-forwarded-for", forwarded);
+            }
+            let mut request = builder.body(()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+            request.into_parts().0
+        };
+
+        for (hops, forwarded, expected) in [
+            (0, Some("81.2.69.142"), "10.0.0.1"),
+            (1, Some("89.160.20.1, 81.2.69.142"), "81.2.69.142"),
+            (1, Some("not an ip"), "10.0.0.1"),
+            (1, None, "10.0.0.1"),
+        ] {
+            let mut parts = request(forwarded);
+            let ClientIp(ip) = ClientIp::from_request_parts(&mut parts, &TrustedProxies(hops))
+                .await
+                .unwrap();
+            assert_eq!(ip, Some(expected.parse().unwrap()));
+        }
+    }
+}
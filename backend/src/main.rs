@@ -1,22 +1,70 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::{get, post},
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        FromRef, FromRequestParts, Path, Query, Request, State,
+    },
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::get,
     Router,
 };
-use clap::Parser;
-use serde::Deserialize;
-use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{info, warn};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{info, instrument, warn};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
+mod admin;
+mod bench;
+mod coalesce;
+mod content;
+mod error;
+mod events;
+mod experiments;
+mod geoip;
+mod posts;
+mod rate_limit;
+mod reading_times;
 mod redis_client;
-use redis_client::{PageStats, RedisPageStatsClient};
+mod resilience;
+mod retention;
+mod search;
+mod site;
+mod sites;
+mod store;
+mod telemetry;
+mod user_agent;
+use admin::AdminSecurity;
+use coalesce::{CoalesceConfig, CoalescingStore};
+use error::{ApiError, ErrorBody};
+use events::{Event, EventCount};
+use experiments::{ExperimentAction, Experiments, Variant};
+use geoip::{ClientIp, GeoIp};
+use posts::{PostCache, PostDetail, PostList, PostsQuery};
+use rate_limit::{RateLimiter, TrustedProxies};
+use reading_times::ReadingTimes;
+use redis_client::{ContentKind, PageId, PageStats, RedisPageStatsClient};
+use resilience::{ResilienceConfig, ResilientStore, StoreHealth};
+use retention::RetentionPolicy;
+use search::{SearchIndex, SearchResult};
+use sites::{Site, SiteConfig, Sites};
+use store::PageStatsStore;
+use telemetry::LogFormat;
+use user_agent::{Client, ClientBreakdown};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Redis URL
     #[arg(long, env = "REDIS_URL", default_value = "redis://127.0.0.1:6379")]
     redis_url: String,
@@ -32,177 +80,1612 @@ struct Args {
     /// Host to bind to
     #[arg(long, env = "HOST", default_value = "127.0.0.1")]
     host: String,
+
+    /// Trunk dist/ directory to serve the site from (API only when not set)
+    #[arg(long, env = "DIST_DIR")]
+    dist_dir: Option<PathBuf>,
+
+    /// Content directory holding the posts/ and pages/ markdown files (used for search and
+    /// reading times)
+    #[arg(long, env = "CONTENT_DIR", default_value = "content")]
+    content_dir: PathBuf,
+
+    /// TOML file mapping Host headers to sites, each with its own keys, origins and slugs
+    /// (one site for every host when not set)
+    #[arg(long, env = "SITES_CONFIG")]
+    sites: Option<PathBuf>,
+
+    /// Maximum write requests (likes, increments, tracked views) per client per minute
+    #[arg(long, env = "WRITE_RATE_LIMIT", default_value = "30")]
+    write_rate_limit: u32,
+
+    /// Reverse proxies in front of the server whose X-Forwarded-For entries are trusted
+    /// (0 identifies clients by the peer address only)
+    #[arg(long, env = "TRUSTED_PROXIES", default_value = "0")]
+    trusted_proxies: usize,
+
+    /// Timeout of a single Redis call in milliseconds
+    #[arg(long, env = "STORE_TIMEOUT_MS", default_value = "500")]
+    store_timeout_ms: u64,
+
+    /// Consecutive Redis failures after which calls fail fast (circuit opens)
+    #[arg(long, env = "CIRCUIT_BREAKER_THRESHOLD", default_value = "5")]
+    circuit_breaker_threshold: u32,
+
+    /// Seconds the circuit stays open before Redis is tried again
+    #[arg(long, env = "CIRCUIT_BREAKER_COOLDOWN", default_value = "10")]
+    circuit_breaker_cooldown: u64,
+
+    /// Increments buffered in memory while Redis is down
+    #[arg(long, env = "MAX_PENDING_WRITES", default_value = "10000")]
+    max_pending_writes: usize,
+
+    /// Sum view and like increments in memory and write them to Redis every this many
+    /// milliseconds (every increment is written right away when not set)
+    #[arg(long, env = "COALESCE_INTERVAL_MS")]
+    coalesce_interval_ms: Option<u64>,
+
+    /// Write coalesced increments early once this many are waiting
+    #[arg(long, env = "COALESCE_MAX_EVENTS", default_value = "1000")]
+    coalesce_max_events: usize,
+
+    /// Days of hourly view counts kept before they are rolled into daily counts
+    #[arg(long, env = "RETENTION_HOURLY_DAYS", default_value = "7",
+          value_parser = clap::value_parser!(u32).range(1..))]
+    retention_hourly_days: u32,
+
+    /// Months of daily view counts kept before they are rolled into monthly counts
+    #[arg(long, env = "RETENTION_DAILY_MONTHS", default_value = "3",
+          value_parser = clap::value_parser!(u32).range(1..))]
+    retention_daily_months: u32,
+
+    /// Minutes between roll-ups of expired view counts (no background roll-up when 0)
+    #[arg(long, env = "COMPACTION_INTERVAL", default_value = "60")]
+    compaction_interval: u64,
+
+    /// MaxMind country database (.mmdb) used to count views per country (no countries when
+    /// not set)
+    #[arg(long, env = "GEOIP_DB")]
+    geoip_db: Option<PathBuf>,
+
+    /// Names of the events counted by POST /api/v1/events, for sites that don't list their own
+    #[arg(long, env = "EVENT_NAMES", default_value = events::DEFAULT_EVENT_NAMES)]
+    event_names: String,
+
+    /// Secret mixed into the hash that assigns visitors to experiment variants; required when
+    /// APP_ENV is prod or production, elsewhere a random one reshuffles visitors on every restart
+    #[arg(long, env = "EXPERIMENT_SALT", hide_env_values = true)]
+    experiment_salt: Option<String>,
+
+    /// Bearer token of the admin endpoints (admin endpoints are off when not set)
+    #[arg(long, env = "ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+
+    /// Format of the log lines
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// OTLP/HTTP collector to export traces to, e.g. http://localhost:4318 (no export when not set)
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve the API (the default)
+    Serve,
+    /// Measure view throughput against Redis with and without write coalescing
+    Bench(bench::BenchArgs),
+    /// Rebuild the index of known pages from a SCAN of the stats keys
+    RepairIndex,
+    /// Move the stats of pages stored as posts before content kinds existed to the page kind
+    MigrateKinds {
+        /// Slugs of the pages to move, by default every page in `CONTENT_DIR/pages`
+        #[arg(long, value_delimiter = ',')]
+        pages: Vec<String>,
+    },
+    /// Roll hourly and daily view counts past their retention into coarser ones
+    Compact {
+        /// Only report what would be rolled up
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
+type Store = ResilientStore<CoalescingStore<RedisPageStatsClient>>;
+
 #[derive(Clone)]
 struct AppState {
-    redis_client: Arc<RedisPageStatsClient>,
+    sites: Arc<Sites<Arc<Store>>>,
+    search_index: Arc<RwLock<SearchIndex>>,
+    posts: Arc<RwLock<PostCache>>,
+    reading_times: Arc<RwLock<ReadingTimes>>,
+    experiments: Arc<RwLock<Experiments>>,
+    experiment_salt: Arc<str>,
+    geoip: Option<Arc<GeoIp>>,
+    event_names: Arc<HashSet<String>>,
+    admin_token: Option<Arc<str>>,
+    trusted_proxies: TrustedProxies,
+}
+
+impl FromRef<AppState> for TrustedProxies {
+    fn from_ref(state: &AppState) -> Self {
+        state.trusted_proxies
+    }
 }
 
-#[derive(Deserialize)]
+/// The site a request is for, resolved from its Host header
+struct CurrentSite(Arc<Site<Arc<Store>>>);
+
+impl FromRequestParts<AppState> for CurrentSite {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        let host = request_host(parts);
+        state
+            .sites
+            .resolve(host)
+            .map(CurrentSite)
+            .ok_or_else(|| ApiError::UnknownSite(host.unwrap_or_default().to_string()))
+    }
+}
+
+/// Host the request was sent to, from the Host header or the URI (HTTP/2)
+fn request_host(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| parts.uri.authority().map(|authority| authority.as_str()))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct StatsQuery {
+    /// Count this request as a view before returning the stats
     track_view: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListQuery {
+    /// Only return pages of this kind
+    kind: Option<ContentKind>,
+}
+
+#[derive(Deserialize, ToSchema)]
 struct IncrementRequest {
-    increment_type: String, // "views", "likes"
-    _amount: Option<u64>,
+    /// Stat to increment, "views" or "likes"
+    increment_type: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExperimentQuery {
+    /// Count this request as an impression of the assigned variant
+    track_impression: Option<bool>,
+    /// Random id the browser keeps for the visitor, hashed instead of the address and User-Agent
+    visitor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct VisitorQuery {
+    /// Random id the browser keeps for the visitor, hashed instead of the address and User-Agent
+    visitor: Option<String>,
+}
+
+/// The variant of a post's experiment assigned to the visitor
+#[derive(Serialize, ToSchema)]
+struct AssignedVariant {
+    slug: String,
+    /// Index of the variant, 0 is the post's own title and summary
+    variant: usize,
+    /// Number of variants of the post, including its own title and summary
+    variants: usize,
+    title: String,
+    summary: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct EventRequest {
+    /// Kind of the page the event happened on, "post" when not given
+    kind: Option<String>,
+    slug: String,
+    /// Name of the event, one of the site's allowed event names
+    name: String,
+    /// Details of the event, e.g. `{"language": "rust"}`; at most 4, names are `[a-z0-9_]`
+    #[serde(default)]
+    props: BTreeMap<String, String>,
 }
 
-#[derive(Deserialize)]
-struct TimeRequest {
-    seconds: u64,
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    /// Search terms
+    q: String,
+    /// Maximum number of results, at most 20
+    limit: Option<usize>,
 }
 
+/// Base of the generated OpenAPI document, paths are added by [`api_routes`]
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "page-stats-server",
+        description = "Page statistics and search API of gertjanassies.dev"
+    ),
+    tags(
+        (name = "stats", description = "Views, likes and reading time per page"),
+        (name = "search", description = "Full-text search over the posts"),
+        (name = "posts", description = "Frontmatter and markdown of the posts"),
+        (name = "experiments", description = "A/B tests of post titles and summaries"),
+        (name = "admin", description = "Analytics for the site owner, needs the admin token")
+    ),
+    modifiers(&AdminSecurity)
+)]
+struct ApiDoc;
+
+/// Default and maximum number of search results
+const SEARCH_LIMIT: usize = 20;
+
+/// How often the posts directory is checked for edits
+const CONTENT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often writes buffered during a Redis outage are retried
+const STORE_REPLAY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the page index check is retried while Redis is unreachable
+const INDEX_CHECK_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Window over which `--write-rate-limit` is counted
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
     // Initialize tracing
-    tracing_subscriber::fmt::init();
+    let tracer_provider = telemetry::init(args.log_format, args.otlp_endpoint.as_deref())?;
+    if let Some(endpoint) = &args.otlp_endpoint {
+        info!("Exporting traces to {}", endpoint);
+    }
 
-    let args = Args::parse();
+    if let Some(Command::Bench(bench_args)) = args.command {
+        let coalesce = CoalesceConfig {
+            interval: Duration::from_millis(args.coalesce_interval_ms.unwrap_or(50)),
+            max_events: args.coalesce_max_events,
+            timeout: Duration::from_millis(args.store_timeout_ms),
+        };
+        return bench::run(&args.redis_url, &args.app_env, coalesce, bench_args).await;
+    }
 
     info!("Starting page stats server...");
     info!("Redis URL: {}", args.redis_url);
     info!("Environment: {}", args.app_env);
     info!("Server: {}:{}", args.host, args.port);
 
-    // Initialize Redis client
-    let redis_client = Arc::new(
-        RedisPageStatsClient::new(&args.redis_url, &args.app_env)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to connect to Redis: {}", e))?,
-    );
+    let site_configs = match &args.sites {
+        Some(path) => sites::load_sites(path)?,
+        None => vec![SiteConfig::single()],
+    };
+
+    // Initialize Redis client, wrapped so a Redis outage degrades instead of failing requests.
+    // It connects on first use, so the server also starts while Redis is down.
+    let redis_client = RedisPageStatsClient::lazy(&args.redis_url, &args.app_env)
+        .map_err(|e| anyhow::anyhow!("Invalid Redis URL: {}", e))?;
 
-    let app_state = AppState { redis_client };
+    // Every configured site gets its own key namespace below APP_ENV
+    let site_client = |config: &SiteConfig| match &args.sites {
+        Some(_) => redis_client.with_prefix(&format!("{}:{}", args.app_env, config.id)),
+        None => redis_client.clone(),
+    };
+
+    if matches!(args.command, Some(Command::RepairIndex)) {
+        for config in &site_configs {
+            let repair = site_client(config).repair_index().await?;
+            println!(
+                "{}: indexed {} pages ({} added, {} removed)",
+                config.id, repair.pages, repair.added, repair.removed
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::MigrateKinds { pages }) = &args.command {
+        let pages = if pages.is_empty() {
+            let pages_dir = args.content_dir.join("pages");
+            content_model::markdown_files(&pages_dir)
+                .map_err(|e| anyhow::anyhow!("Could not read {}: {}", pages_dir.display(), e))?
+                .into_iter()
+                .map(|(slug, _)| slug)
+                .collect()
+        } else {
+            pages.clone()
+        };
+        for config in &site_configs {
+            let client = site_client(config);
+            for slug in &pages {
+                let from = PageId::post(slug);
+                let to = PageId::new(ContentKind::Page, slug);
+                if client.move_page(&from, &to).await? {
+                    println!("{}: moved {} to {}", config.id, from, to);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let retention = RetentionPolicy {
+        hourly_days: args.retention_hourly_days,
+        daily_months: args.retention_daily_months,
+    };
+    if let Some(Command::Compact { dry_run }) = args.command {
+        let today = chrono::Utc::now().date_naive();
+        for config in &site_configs {
+            let rollups =
+                retention::compact(&site_client(config), retention, today, dry_run).await?;
+            for rollup in &rollups {
+                println!("{}: {} ({} views)", config.id, rollup.bucket, rollup.views);
+            }
+            println!(
+                "{}: {} {} view buckets",
+                config.id,
+                if dry_run {
+                    "would roll up"
+                } else {
+                    "rolled up"
+                },
+                rollups.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let experiment_salt = match &args.experiment_salt {
+        Some(salt) => salt.clone(),
+        None if experiments::is_production(&args.app_env) => anyhow::bail!(
+            "EXPERIMENT_SALT must be set when APP_ENV is {}, \
+             or visitors get another experiment variant after every restart",
+            args.app_env
+        ),
+        None => {
+            warn!("EXPERIMENT_SALT is not set, experiment variants change on every restart");
+            uuid::Uuid::new_v4().to_string()
+        }
+    };
+
+    let coalesce = args.coalesce_interval_ms.map(|interval| CoalesceConfig {
+        interval: Duration::from_millis(interval),
+        max_events: args.coalesce_max_events,
+        timeout: Duration::from_millis(args.store_timeout_ms),
+    });
+    if let Some(coalesce) = &coalesce {
+        info!(
+            "Coalescing increments, flushing every {:?} or {} increments",
+            coalesce.interval, coalesce.max_events
+        );
+    }
+    let resilience = ResilienceConfig {
+        timeout: Duration::from_millis(args.store_timeout_ms),
+        failure_threshold: args.circuit_breaker_threshold,
+        cooldown: Duration::from_secs(args.circuit_breaker_cooldown),
+        max_pending_writes: args.max_pending_writes,
+    };
+    let sites = Arc::new(Sites::new(site_configs, |config| {
+        info!("Serving site {} for {:?}", config.id, config.hosts);
+        Arc::new(ResilientStore::new(
+            CoalescingStore::new(site_client(config), coalesce.clone()),
+            resilience.clone(),
+        ))
+    }));
+    let redis_up = match tokio::time::timeout(resilience.timeout, redis_client.ping()).await {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            warn!("Redis is unreachable, starting degraded: {}", e);
+            false
+        }
+        Err(_) => {
+            warn!(
+                "Redis didn't answer in {:?}, starting degraded",
+                resilience.timeout
+            );
+            false
+        }
+    };
+    let mut flushers = Vec::new();
+    for site in sites.iter() {
+        if !redis_up {
+            site.store.open_circuit();
+        }
+        spawn_index_check(site.config.id.clone(), site_client(&site.config));
+        site.store.spawn_replay(STORE_REPLAY_INTERVAL);
+        flushers.push(tokio::spawn({
+            let store = site.store.clone();
+            async move { store.inner().run_flusher().await }
+        }));
+    }
+    if args.compaction_interval > 0 {
+        for config in sites.iter().map(|site| &site.config) {
+            retention::spawn_compaction(
+                config.id.clone(),
+                site_client(config),
+                retention,
+                Duration::from_secs(args.compaction_interval * 60),
+            );
+        }
+    }
+
+    // Build the search index and post cache, and keep them up to date when posts change
+    let posts_dir = args.content_dir.join("posts");
+    let posts = content::load_posts(&posts_dir).unwrap_or_else(|e| {
+        warn!("Could not read posts from {}: {}", posts_dir.display(), e);
+        Vec::new()
+    });
+    let index = SearchIndex::build(&posts);
+    info!("Indexed {} posts for search", index.document_count());
+    let search_index = Arc::new(RwLock::new(index));
+    let mut post_cache = PostCache::default();
+    post_cache.update(posts.clone());
+    info!("Serving {} posts", post_cache.len());
+    let post_cache = Arc::new(RwLock::new(post_cache));
+
+    // Pages are markdown without frontmatter, read the same way as posts
+    let pages_dir = args.content_dir.join("pages");
+    let pages = content::load_posts(&pages_dir).unwrap_or_else(|e| {
+        warn!("Could not read pages from {}: {}", pages_dir.display(), e);
+        Vec::new()
+    });
+    let mut reading_times = ReadingTimes::default();
+    reading_times.update(ContentKind::Post, &posts);
+    reading_times.update(ContentKind::Page, &pages);
+    info!("Calculated reading times of {} pages", reading_times.len());
+    let reading_times = Arc::new(RwLock::new(reading_times));
+
+    let mut experiments = Experiments::default();
+    experiments.update(&posts);
+    info!("Running experiments on {} posts", experiments.slugs().len());
+    let experiments = Arc::new(RwLock::new(experiments));
+
+    {
+        let search_index = search_index.clone();
+        let post_cache = post_cache.clone();
+        let reading_times = reading_times.clone();
+        let experiments = experiments.clone();
+        content::watch_posts(posts_dir, CONTENT_POLL_INTERVAL, move |posts| {
+            let index = SearchIndex::build(&posts);
+            *search_index.write().expect("search index lock poisoned") = index;
+            reading_times
+                .write()
+                .expect("reading times lock poisoned")
+                .update(ContentKind::Post, &posts);
+            experiments
+                .write()
+                .expect("experiments lock poisoned")
+                .update(&posts);
+            post_cache
+                .write()
+                .expect("post cache lock poisoned")
+                .update(posts);
+        });
+    }
+    {
+        let reading_times = reading_times.clone();
+        content::watch_posts(pages_dir, CONTENT_POLL_INTERVAL, move |pages| {
+            reading_times
+                .write()
+                .expect("reading times lock poisoned")
+                .update(ContentKind::Page, &pages);
+        });
+    }
+
+    let geoip = match &args.geoip_db {
+        Some(path) => {
+            let geoip = GeoIp::open(path)?;
+            info!("Counting views per country with {}", geoip.database_type());
+            Some(Arc::new(geoip))
+        }
+        None => None,
+    };
+
+    let app_state = AppState {
+        sites: sites.clone(),
+        search_index,
+        posts: post_cache,
+        reading_times,
+        experiments,
+        experiment_salt: experiment_salt.into(),
+        geoip,
+        event_names: Arc::new(events::parse_event_names(&args.event_names)),
+        admin_token: args.admin_token.as_deref().map(Arc::from),
+        trusted_proxies: TrustedProxies(args.trusted_proxies),
+    };
+
+    let limiter = Arc::new(RateLimiter::new(
+        args.write_rate_limit,
+        RATE_LIMIT_WINDOW,
+        app_state.trusted_proxies,
+    ));
+    let rate_limit = middleware::from_fn_with_state(limiter, rate_limit::limit_writes);
+    let (api, openapi) = versioned_api();
+    let (legacy_api, _) = api_routes().split_for_parts();
+    let openapi_json = openapi.to_json()?;
 
     // Build our application with routes
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/health", get(health_check))
-        .route("/api/stats/:slug", get(get_page_stats))
-        .route("/api/stats/:slug/increment", post(increment_stats))
-        .route("/api/stats/:slug/reading-time", post(set_reading_time))
-        .route("/api/stats", get(get_all_stats))
+        .route("/ready", get(readiness))
+        .route(
+            "/api/openapi.json",
+            get(|| async move { ([(header::CONTENT_TYPE, "application/json")], openapi_json) }),
+        )
+        .merge(api.route_layer(rate_limit.clone()))
+        // Unversioned paths are kept as deprecated aliases of /api/v1
+        .nest(
+            "/api",
+            legacy_api
+                .route_layer(rate_limit)
+                .layer(middleware::from_fn(deprecated_alias)),
+        )
         .layer(
             CorsLayer::new()
-                .allow_origin(Any)
+                .allow_origin(allowed_origins(sites.clone()))
                 .allow_methods(Any)
                 .allow_headers(Any),
         )
         .with_state(app_state);
 
+    #[cfg(feature = "swagger-ui")]
+    {
+        use utoipa_swagger_ui::{Config, SwaggerUi};
+        app = app.merge(SwaggerUi::new("/api/docs").config(Config::from("/api/openapi.json")));
+    }
+
+    // Optionally serve the frontend as well, so no separate web server is needed
+    if let Some(dist_dir) = &args.dist_dir {
+        info!("Serving site from {}", dist_dir.display());
+        app = app.merge(site::router(dist_dir));
+    }
+    let app = app.layer(middleware::from_fn(telemetry::request_id));
+
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", args.host, args.port)).await?;
     info!("Server listening on {}:{}", args.host, args.port);
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    // Stop the periodic flushes, then write the coalesced increments that are still waiting
+    for site in sites.iter() {
+        site.store.inner().stop_flusher();
+    }
+    for flusher in flushers {
+        if let Err(e) = flusher.await {
+            warn!("Coalesced increments flusher failed: {}", e);
+        }
+    }
+    for site in sites.iter() {
+        if let Err(e) = site.store.inner().flush().await {
+            warn!(
+                "Failed to flush coalesced increments of {} on shutdown: {}",
+                site.config.id, e
+            );
+        }
+    }
+
+    // Flush the spans that haven't been exported yet
+    if let Some(provider) = tracer_provider {
+        provider.shutdown()?;
+    }
 
     Ok(())
 }
 
+/// Build the page index of stores written before it existed, retrying until Redis is reachable
+fn spawn_index_check(name: String, client: RedisPageStatsClient) {
+    tokio::spawn(async move {
+        loop {
+            match client.index_exists().await {
+                Ok(true) => return,
+                Ok(false) => match client.repair_index().await {
+                    Ok(repair) => {
+                        info!("Built page index of {} with {} pages", name, repair.pages);
+                        return;
+                    }
+                    Err(e) => warn!(
+                        "Failed to build the page index of {}, retrying: {}",
+                        name, e
+                    ),
+                },
+                Err(e) => warn!(
+                    "Failed to check the page index of {}, retrying: {}",
+                    name, e
+                ),
+            }
+            tokio::time::sleep(INDEX_CHECK_RETRY_INTERVAL).await;
+        }
+    });
+}
+
+/// CORS origins of the site the request is for, any origin for sites without a list
+fn allowed_origins(sites: Arc<Sites<Arc<Store>>>) -> AllowOrigin {
+    AllowOrigin::predicate(move |origin, parts| {
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+        sites
+            .resolve(request_host(parts))
+            .is_some_and(|site| site.config.allows_origin(origin))
+    })
+}
+
+/// Resolve on Ctrl+C or SIGTERM (docker stop)
+async fn shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = ctrl_c => {},
+            _ = terminate.recv() => {},
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = ctrl_c.await;
+
+    info!("Shutting down");
+}
+
+/// Routes of the stats and search API, registered together with their OpenAPI docs
+#[allow(deprecated)] // the slug-only aliases
+fn api_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(get_page_stats))
+        .routes(routes!(increment_stats))
+        .routes(routes!(get_page_stats_by_slug))
+        .routes(routes!(increment_stats_by_slug))
+        .routes(routes!(get_page_countries))
+        .routes(routes!(get_page_clients))
+        .routes(routes!(get_page_events))
+        .routes(routes!(track_event))
+        .routes(routes!(get_experiment))
+        .routes(routes!(track_experiment_click))
+        .routes(routes!(get_all_stats))
+        .routes(routes!(search_posts))
+        .routes(routes!(list_posts))
+        .routes(routes!(get_post))
+        .routes(routes!(admin::export_stats))
+        .routes(routes!(admin::experiments_report))
+}
+
+/// The API under /api/v1 and its OpenAPI document
+fn versioned_api() -> (Router<AppState>, utoipa::openapi::OpenApi) {
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .nest("/api/v1", api_routes())
+        .split_for_parts()
+}
+
+/// Mark responses of the unversioned /api paths as deprecated (RFC 8594 style), keeping
+/// the successor of routes that are deprecated under /api/v1 too
+async fn deprecated_alias(req: Request, next: Next) -> Response {
+    let successor = format!("</api/v1{}>; rel=\"successor-version\"", req.uri().path());
+    let mut response = next.run(req).await;
+
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.entry(header::LINK).or_insert(link);
+    }
+    response
+}
+
 /// Health check endpoint
 async fn health_check() -> &'static str {
     "OK"
 }
 
+#[derive(Serialize)]
+struct Readiness {
+    /// "ready", or "degraded" while Redis is unavailable or buffered writes are being replayed
+    status: &'static str,
+    /// Store health per site ID
+    sites: BTreeMap<String, StoreHealth>,
+}
+
+/// Readiness endpoint, reports whether stats are served from Redis or in degraded mode
+async fn readiness(State(state): State<AppState>) -> Json<Readiness> {
+    let sites: BTreeMap<String, StoreHealth> = state
+        .sites
+        .iter()
+        .map(|site| (site.config.id.clone(), site.store.health()))
+        .collect();
+    let status = if sites.values().any(StoreHealth::is_degraded) {
+        "degraded"
+    } else {
+        "ready"
+    };
+    Json(Readiness { status, sites })
+}
+
+/// Check the privacy signals a browser sends with each request
+/// Returns false when the reader asked not to be tracked (`DNT: 1` or `Sec-GPC: 1`)
+fn tracking_allowed(headers: &HeaderMap) -> bool {
+    let signal_set = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim() == "1")
+            .unwrap_or(false)
+    };
+
+    !(signal_set("dnt") || signal_set("sec-gpc"))
+}
+
 /// Get page stats for a specific slug
+#[utoipa::path(
+    get,
+    path = "/stats/{kind}/{slug}",
+    tag = "stats",
+    params(
+        ("kind" = ContentKind, Path, description = "Kind of content"),
+        ("slug" = String, Path, description = "Slug of the post, page or note"),
+        StatsQuery,
+        ("DNT" = Option<String>, Header, description = "`1` disables view tracking"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables view tracking")
+    ),
+    responses(
+        (status = 200, description = "Stats of the page", body = PageStats),
+        (status = 400, description = "Invalid kind, slug or query", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(kind = %kind, slug = %slug))]
 async fn get_page_stats(
     State(state): State<AppState>,
-    Path(slug): Path<String>,
-    Query(query): Query<StatsQuery>,
-) -> Result<Json<PageStats>, StatusCode> {
+    CurrentSite(site): CurrentSite,
+    Path((kind, slug)): Path<(String, String)>,
+    query: Result<Query<StatsQuery>, QueryRejection>,
+    headers: HeaderMap,
+    ClientIp(client_ip): ClientIp,
+) -> Result<Json<PageStats>, ApiError> {
+    let page = site.page(&kind, &slug)?;
+    let Query(query) = query?;
+
     info!(
-        "Getting stats for slug: {} (track_view: {:?})",
-        slug, query.track_view
+        "Getting stats for {} (track_view: {:?})",
+        page, query.track_view
     );
 
-    let track_view = query.track_view.unwrap_or(false);
+    let track_view = query.track_view.unwrap_or(false) && tracking_allowed(&headers);
 
     let stats = if track_view {
         // Increment view count and return updated stats
-        match state.redis_client.increment_views(&slug).await {
-            Ok(stats) => stats,
-            Err(e) => {
-                warn!("Failed to increment views for {}: {}", slug, e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
+        let stats = site.store.increment_views(&page).await.map_err(|e| {
+            warn!("Failed to increment views for {}: {}", page, e);
+            ApiError::StoreUnavailable
+        })?;
+        count_country(&state, &site, &page, client_ip).await;
+        count_client(&site, &page, &headers).await;
+        stats
     } else {
         // Just get existing stats
-        match state.redis_client.get_page_stats(&slug).await {
-            Ok(Some(stats)) => stats,
-            Ok(None) => PageStats::new(&slug),
-            Err(e) => {
-                warn!("Failed to get stats for {}: {}", slug, e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
+        site.store
+            .get_page_stats(&page)
+            .await
+            .map_err(|e| {
+                warn!("Failed to get stats for {}: {}", page, e);
+                ApiError::StoreUnavailable
+            })?
+            .unwrap_or_else(|| PageStats::new(&page))
     };
 
-    Ok(Json(stats))
+    Ok(Json(
+        refresh_reading_time(&state, &site, &page, stats).await,
+    ))
 }
 
 /// Increment specific stat types
+#[utoipa::path(
+    post,
+    path = "/stats/{kind}/{slug}/increment",
+    tag = "stats",
+    params(
+        ("kind" = ContentKind, Path, description = "Kind of content"),
+        ("slug" = String, Path, description = "Slug of the post, page or note"),
+        ("DNT" = Option<String>, Header, description = "`1` disables counting views"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables counting views")
+    ),
+    request_body = IncrementRequest,
+    responses(
+        (status = 200, description = "Updated stats of the page", body = PageStats),
+        (status = 400, description = "Invalid kind, slug, body or increment type", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(kind = %kind, slug = %slug))]
 async fn increment_stats(
     State(state): State<AppState>,
-    Path(slug): Path<String>,
-    Json(payload): Json<IncrementRequest>,
-) -> Result<Json<PageStats>, StatusCode> {
-    info!("Incrementing {} for slug: {}", payload.increment_type, slug);
+    CurrentSite(site): CurrentSite,
+    Path((kind, slug)): Path<(String, String)>,
+    headers: HeaderMap,
+    ClientIp(client_ip): ClientIp,
+    payload: Result<Json<IncrementRequest>, JsonRejection>,
+) -> Result<Json<PageStats>, ApiError> {
+    let page = site.page(&kind, &slug)?;
+    let Json(payload) = payload?;
+
+    info!("Incrementing {} for {}", payload.increment_type, page);
 
     let stats = match payload.increment_type.as_str() {
-        "views" => state.redis_client.increment_views(&slug).await,
-        "likes" => state.redis_client.increment_likes(&slug).await,
-        _ => {
-            warn!("Invalid increment type: {}", payload.increment_type);
-            return Err(StatusCode::BAD_REQUEST);
+        "views" if !tracking_allowed(&headers) => {
+            // Honour DNT / GPC: report the current stats without counting the view
+            site.store
+                .get_page_stats(&page)
+                .await
+                .map(|stats| stats.unwrap_or_else(|| PageStats::new(&page)))
+        }
+        "views" => {
+            let stats = site.store.increment_views(&page).await;
+            if stats.is_ok() {
+                count_country(&state, &site, &page, client_ip).await;
+                count_client(&site, &page, &headers).await;
+            }
+            stats
         }
+        "likes" => site.store.increment_likes(&page).await,
+        _ => return Err(ApiError::InvalidIncrementType(payload.increment_type)),
     };
 
-    match stats {
-        Ok(stats) => Ok(Json(stats)),
-        Err(e) => {
-            warn!(
-                "Failed to increment {} for {}: {}",
-                payload.increment_type, slug, e
-            );
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
+    let stats = stats.map_err(|e| {
+        warn!(
+            "Failed to increment {} for {}: {}",
+            payload.increment_type, page, e
+        );
+        ApiError::StoreUnavailable
+    })?;
+    Ok(Json(
+        refresh_reading_time(&state, &site, &page, stats).await,
+    ))
+}
+
+/// Kind of a page addressed by slug only, as the API did before kinds: a page if the
+/// content has one with that slug, else a post
+fn legacy_kind(state: &AppState, slug: &str) -> ContentKind {
+    let is_page = state
+        .reading_times
+        .read()
+        .expect("reading times lock poisoned")
+        .get(&PageId::new(ContentKind::Page, slug))
+        .is_some();
+    if is_page {
+        ContentKind::Page
+    } else {
+        ContentKind::Post
     }
 }
 
-/// Set reading time for a page (only if not already set)
-async fn set_reading_time(
+/// Mark a response of a slug-only path as deprecated, pointing at the path with the kind
+fn deprecated_slug_route(mut response: Response, successor: &str) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!(
+        "</api/v1{}>; rel=\"successor-version\"",
+        successor
+    )) {
+        headers.insert(header::LINK, link);
+    }
+    response
+}
+
+/// Get page stats by slug only, use `/stats/{kind}/{slug}` instead
+#[utoipa::path(
+    get,
+    path = "/stats/{slug}",
+    tag = "stats",
+    params(
+        ("slug" = String, Path, description = "Slug of the post or page"),
+        StatsQuery,
+        ("DNT" = Option<String>, Header, description = "`1` disables view tracking"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables view tracking")
+    ),
+    responses(
+        (status = 200, description = "Stats of the page", body = PageStats),
+        (status = 400, description = "Invalid slug or query", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[deprecated]
+async fn get_page_stats_by_slug(
     State(state): State<AppState>,
+    site: CurrentSite,
     Path(slug): Path<String>,
-    Json(payload): Json<TimeRequest>,
-) -> Result<Json<PageStats>, StatusCode> {
-    info!(
-        "Setting reading time {} seconds for slug: {}",
-        payload.seconds, slug
+    query: Result<Query<StatsQuery>, QueryRejection>,
+    headers: HeaderMap,
+    client_ip: ClientIp,
+) -> Response {
+    let kind = legacy_kind(&state, &slug);
+    let successor = format!("/stats/{}/{}", kind, slug);
+    let response = get_page_stats(
+        State(state),
+        site,
+        Path((kind.to_string(), slug)),
+        query,
+        headers,
+        client_ip,
+    )
+    .await
+    .into_response();
+    deprecated_slug_route(response, &successor)
+}
+
+/// Increment stats by slug only, use `/stats/{kind}/{slug}/increment` instead
+#[utoipa::path(
+    post,
+    path = "/stats/{slug}/increment",
+    tag = "stats",
+    params(
+        ("slug" = String, Path, description = "Slug of the post or page"),
+        ("DNT" = Option<String>, Header, description = "`1` disables counting views"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables counting views")
+    ),
+    request_body = IncrementRequest,
+    responses(
+        (status = 200, description = "Updated stats of the page", body = PageStats),
+        (status = 400, description = "Invalid slug, body or increment type", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[deprecated]
+async fn increment_stats_by_slug(
+    State(state): State<AppState>,
+    site: CurrentSite,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+    client_ip: ClientIp,
+    payload: Result<Json<IncrementRequest>, JsonRejection>,
+) -> Response {
+    let kind = legacy_kind(&state, &slug);
+    let successor = format!("/stats/{}/{}/increment", kind, slug);
+    let response = increment_stats(
+        State(state),
+        site,
+        Path((kind.to_string(), slug)),
+        headers,
+        client_ip,
+        payload,
+    )
+    .await
+    .into_response();
+    deprecated_slug_route(response, &successor)
+}
+
+/// Count a custom event on a page, e.g. a click on the copy button of a code block
+#[utoipa::path(
+    post,
+    path = "/events",
+    tag = "stats",
+    params(
+        ("DNT" = Option<String>, Header, description = "`1` disables counting events"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables counting events")
+    ),
+    request_body = EventRequest,
+    responses(
+        (status = 204, description = "Event counted, or ignored because of DNT / GPC"),
+        (status = 400, description = "Invalid kind, slug, body or event name", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all)]
+async fn track_event(
+    State(state): State<AppState>,
+    CurrentSite(site): CurrentSite,
+    headers: HeaderMap,
+    payload: Result<Json<EventRequest>, JsonRejection>,
+) -> Result<StatusCode, ApiError> {
+    let Json(payload) = payload?;
+    let kind = payload
+        .kind
+        .as_deref()
+        .unwrap_or(ContentKind::Post.as_str());
+    let page = site.page(kind, &payload.slug)?;
+    let event = Event::new(
+        &payload.name,
+        payload.props,
+        site.config.event_names(&state.event_names),
+    )?;
+
+    if !tracking_allowed(&headers) {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    info!("Counting event {} on {}", event.name, page);
+    site.store
+        .increment_event(&page, &event)
+        .await
+        .map_err(|e| {
+            warn!("Failed to count event {} on {}: {}", event.name, page, e);
+            ApiError::StoreUnavailable
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Variant of a post's experiment the visitor is assigned to, from a hash of the id their
+/// browser keeps, or else of their address and User-Agent, so the same visitor keeps seeing
+/// the same variant without a cookie
+fn assign_variant(
+    state: &AppState,
+    site: &Site<Arc<Store>>,
+    slug: &str,
+    visitor: Option<&str>,
+    headers: &HeaderMap,
+    client_ip: Option<IpAddr>,
+) -> Result<(PageId, usize, Variant, usize), ApiError> {
+    site.check_slug(slug)?;
+    let experiments = state.experiments.read().expect("experiments lock poisoned");
+    let variants = experiments
+        .get(slug)
+        .ok_or_else(|| ApiError::UnknownExperiment(slug.to_string()))?;
+
+    let visitor = match visitor.filter(|id| experiments::valid_visitor_id(id)) {
+        Some(id) => format!("id|{}", id),
+        None => {
+            let user_agent = headers
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            format!(
+                "{}|{}",
+                client_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+                user_agent
+            )
+        }
+    };
+    let variant = experiments::assign(
+        &state.experiment_salt,
+        &visitor,
+        &format!("{}:{}", site.config.id, slug),
+        variants.len(),
     );
+    Ok((
+        PageId::post(slug),
+        variant,
+        variants[variant].clone(),
+        variants.len(),
+    ))
+}
+
+/// Title and summary of a post shown to this visitor
+#[utoipa::path(
+    get,
+    path = "/experiments/{slug}",
+    tag = "experiments",
+    params(
+        ("slug" = String, Path, description = "Slug of the post"),
+        ExperimentQuery,
+        ("DNT" = Option<String>, Header, description = "`1` disables counting impressions"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables counting impressions")
+    ),
+    responses(
+        (status = 200, description = "Variant assigned to the visitor", body = AssignedVariant),
+        (status = 400, description = "Invalid slug or query", body = ErrorBody),
+        (status = 404, description = "Unknown site or page, or the post has no variants", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(slug = %slug))]
+async fn get_experiment(
+    State(state): State<AppState>,
+    CurrentSite(site): CurrentSite,
+    Path(slug): Path<String>,
+    query: Result<Query<ExperimentQuery>, QueryRejection>,
+    headers: HeaderMap,
+    ClientIp(client_ip): ClientIp,
+) -> Result<Json<AssignedVariant>, ApiError> {
+    let Query(query) = query?;
+    let (page, variant, shown, variants) = assign_variant(
+        &state,
+        &site,
+        &slug,
+        query.visitor.as_deref(),
+        &headers,
+        client_ip,
+    )?;
+    info!("Assigned variant {} of {}", variant, page);
+
+    if query.track_impression.unwrap_or(false) && tracking_allowed(&headers) {
+        site.store
+            .increment_experiment(&page, variant, ExperimentAction::Impression)
+            .await
+            .map_err(|e| {
+                warn!("Failed to count an impression of {}: {}", page, e);
+                ApiError::StoreUnavailable
+            })?;
+    }
 
-    match state
-        .redis_client
-        .set_reading_time(&slug, payload.seconds)
+    Ok(Json(AssignedVariant {
+        slug,
+        variant,
+        variants,
+        title: shown.title,
+        summary: shown.summary,
+    }))
+}
+
+/// Count a click on the visitor's variant of a post in the list of posts
+#[utoipa::path(
+    post,
+    path = "/experiments/{slug}/click",
+    tag = "experiments",
+    params(
+        ("slug" = String, Path, description = "Slug of the post"),
+        VisitorQuery,
+        ("DNT" = Option<String>, Header, description = "`1` disables counting clicks"),
+        ("Sec-GPC" = Option<String>, Header, description = "`1` disables counting clicks")
+    ),
+    responses(
+        (status = 204, description = "Click counted, or ignored because of DNT / GPC"),
+        (status = 400, description = "Invalid slug or query", body = ErrorBody),
+        (status = 404, description = "Unknown site or page, or the post has no variants", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(slug = %slug))]
+async fn track_experiment_click(
+    State(state): State<AppState>,
+    CurrentSite(site): CurrentSite,
+    Path(slug): Path<String>,
+    query: Result<Query<VisitorQuery>, QueryRejection>,
+    headers: HeaderMap,
+    ClientIp(client_ip): ClientIp,
+) -> Result<StatusCode, ApiError> {
+    let Query(query) = query?;
+    let (page, variant, _, _) = assign_variant(
+        &state,
+        &site,
+        &slug,
+        query.visitor.as_deref(),
+        &headers,
+        client_ip,
+    )?;
+    if !tracking_allowed(&headers) {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    info!("Counting a click on variant {} of {}", variant, page);
+    site.store
+        .increment_experiment(&page, variant, ExperimentAction::Click)
         .await
-    {
-        Ok(stats) => Ok(Json(stats)),
+        .map_err(|e| {
+            warn!("Failed to count a click on {}: {}", page, e);
+            ApiError::StoreUnavailable
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Count a view from the client's country when a GeoIP database is configured. The address
+/// is only looked up, failures are logged and don't fail the request.
+async fn count_country(
+    state: &AppState,
+    site: &Site<Arc<Store>>,
+    page: &PageId,
+    client_ip: Option<IpAddr>,
+) {
+    let Some(country) = state
+        .geoip
+        .as_ref()
+        .zip(client_ip)
+        .and_then(|(geoip, ip)| geoip.country(ip))
+    else {
+        return;
+    };
+
+    if let Err(e) = site.store.increment_country(page, &country).await {
+        warn!(
+            "Failed to count a view from {} for {}: {}",
+            country, page, e
+        );
+    }
+}
+
+/// Count a view by the device, browser and OS family of the reader's User-Agent,
+/// the header itself isn't kept
+async fn count_client(site: &Site<Arc<Store>>, page: &PageId, headers: &HeaderMap) {
+    let Some(user_agent) = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return;
+    };
+
+    let client = Client::parse(user_agent);
+    if let Err(e) = site.store.increment_client(page, &client).await {
+        warn!(
+            "Failed to count a view from a {:?} client for {}: {}",
+            client.device, page, e
+        );
+    }
+}
+
+/// Views of a page per country
+#[utoipa::path(
+    get,
+    path = "/stats/{kind}/{slug}/countries",
+    tag = "stats",
+    params(
+        ("kind" = ContentKind, Path, description = "Kind of content"),
+        ("slug" = String, Path, description = "Slug of the post, page or note")
+    ),
+    responses(
+        (status = 200, description = "Views per ISO 3166-1 alpha-2 country code, empty when no GeoIP database is configured", body = BTreeMap<String, u64>),
+        (status = 400, description = "Invalid kind or slug", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(kind = %kind, slug = %slug))]
+async fn get_page_countries(
+    CurrentSite(site): CurrentSite,
+    Path((kind, slug)): Path<(String, String)>,
+) -> Result<Json<BTreeMap<String, u64>>, ApiError> {
+    let page = site.page(&kind, &slug)?;
+    info!("Getting countries for {}", page);
+
+    site.store
+        .get_countries(&page)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            warn!("Failed to get countries for {}: {}", page, e);
+            ApiError::StoreUnavailable
+        })
+}
+
+/// Custom events on a page with their counts per property value
+#[utoipa::path(
+    get,
+    path = "/stats/{kind}/{slug}/events",
+    tag = "stats",
+    params(
+        ("kind" = ContentKind, Path, description = "Kind of content"),
+        ("slug" = String, Path, description = "Slug of the post, page or note")
+    ),
+    responses(
+        (status = 200, description = "Counts per event name", body = BTreeMap<String, EventCount>),
+        (status = 400, description = "Invalid kind or slug", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(kind = %kind, slug = %slug))]
+async fn get_page_events(
+    CurrentSite(site): CurrentSite,
+    Path((kind, slug)): Path<(String, String)>,
+) -> Result<Json<BTreeMap<String, EventCount>>, ApiError> {
+    let page = site.page(&kind, &slug)?;
+    info!("Getting events for {}", page);
+
+    site.store.get_events(&page).await.map(Json).map_err(|e| {
+        warn!("Failed to get events for {}: {}", page, e);
+        ApiError::StoreUnavailable
+    })
+}
+
+/// Views of a page per device type, browser family and OS family
+#[utoipa::path(
+    get,
+    path = "/stats/{kind}/{slug}/clients",
+    tag = "stats",
+    params(
+        ("kind" = ContentKind, Path, description = "Kind of content"),
+        ("slug" = String, Path, description = "Slug of the post, page or note")
+    ),
+    responses(
+        (status = 200, description = "Views per device, browser and OS", body = ClientBreakdown),
+        (status = 400, description = "Invalid kind or slug", body = ErrorBody),
+        (status = 404, description = "Unknown site or page", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(kind = %kind, slug = %slug))]
+async fn get_page_clients(
+    CurrentSite(site): CurrentSite,
+    Path((kind, slug)): Path<(String, String)>,
+) -> Result<Json<ClientBreakdown>, ApiError> {
+    let page = site.page(&kind, &slug)?;
+    info!("Getting clients for {}", page);
+
+    let mut clients = site
+        .store
+        .get_clients(std::slice::from_ref(&page))
+        .await
+        .map_err(|e| {
+            warn!("Failed to get clients for {}: {}", page, e);
+            ApiError::StoreUnavailable
+        })?;
+    Ok(Json(clients.pop().unwrap_or_default()))
+}
+
+/// Store the reading time calculated from the page's markdown when the stats were calculated
+/// from another version of it, or never were. Pages without markdown keep their stats.
+async fn refresh_reading_time(
+    state: &AppState,
+    site: &Site<Arc<Store>>,
+    page: &PageId,
+    mut stats: PageStats,
+) -> PageStats {
+    let reading = state
+        .reading_times
+        .read()
+        .expect("reading times lock poisoned")
+        .get(page)
+        .cloned();
+    let Some(reading) = reading.filter(|reading| stats.needs_reading_time(reading)) else {
+        return stats;
+    };
+
+    info!(
+        "Updating reading time of {} to {} seconds",
+        page, reading.seconds
+    );
+    match site.store.set_reading_time(page, &reading).await {
+        Ok(updated) => updated,
         Err(e) => {
-            warn!("Failed to set reading time for {}: {}", slug, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            // Still show the new reading time, the next request tries to store it again
+            warn!("Failed to update reading time of {}: {}", page, e);
+            stats.set_reading_time(&reading);
+            stats
         }
     }
 }
 
+/// Full-text search over the published posts
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching posts, best match first", body = Vec<SearchResult>),
+        (status = 400, description = "Invalid query", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all)]
+async fn search_posts(
+    State(state): State<AppState>,
+    query: Result<Query<SearchQuery>, QueryRejection>,
+) -> Result<Json<Vec<SearchResult>>, ApiError> {
+    let Query(query) = query?;
+    info!("Searching posts for: {}", query.q);
+
+    let limit = query.limit.unwrap_or(SEARCH_LIMIT).min(SEARCH_LIMIT);
+    let results = state
+        .search_index
+        .read()
+        .expect("search index lock poisoned")
+        .search(&query.q, limit);
+
+    Ok(Json(results))
+}
+
+/// Frontmatter of the posts matching the filters, newest first
+#[utoipa::path(
+    get,
+    path = "/posts",
+    tag = "posts",
+    params(PostsQuery),
+    responses(
+        (status = 200, description = "One page of matching posts", body = PostList),
+        (status = 400, description = "Invalid query", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all)]
+async fn list_posts(
+    State(state): State<AppState>,
+    query: Result<Query<PostsQuery>, QueryRejection>,
+) -> Result<Json<PostList>, ApiError> {
+    let Query(query) = query?;
+    info!("Listing posts: {:?}", query);
+
+    let list = state
+        .posts
+        .read()
+        .expect("post cache lock poisoned")
+        .list(&query)?;
+    Ok(Json(list))
+}
+
+/// Frontmatter and markdown body of a post, drafts included
+#[utoipa::path(
+    get,
+    path = "/posts/{slug}",
+    tag = "posts",
+    params(("slug" = String, Path, description = "File name of the post without `.md`")),
+    responses(
+        (status = 200, description = "The post", body = PostDetail),
+        (status = 400, description = "Invalid slug", body = ErrorBody),
+        (status = 404, description = "Unknown post", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all, fields(slug = %slug))]
+async fn get_post(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<PostDetail>, ApiError> {
+    error::validate_slug(&slug)?;
+    info!("Getting post {}", slug);
+
+    state
+        .posts
+        .read()
+        .expect("post cache lock poisoned")
+        .get(&slug)
+        .map(Json)
+        .ok_or(ApiError::UnknownPost(slug))
+}
+
 /// Get all page stats (for analytics)
-async fn get_all_stats(State(state): State<AppState>) -> Result<Json<Vec<PageStats>>, StatusCode> {
-    info!("Getting all page stats");
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "stats",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "Stats of every page of the site", body = Vec<PageStats>),
+        (status = 400, description = "Invalid query", body = ErrorBody),
+        (status = 404, description = "Unknown site", body = ErrorBody),
+        (status = 503, description = "Stats store unavailable", body = ErrorBody)
+    )
+)]
+#[instrument(skip_all)]
+async fn get_all_stats(
+    CurrentSite(site): CurrentSite,
+    query: Result<Query<ListQuery>, QueryRejection>,
+) -> Result<Json<Vec<PageStats>>, ApiError> {
+    let Query(query) = query?;
+    info!("Getting all page stats (kind: {:?})", query.kind);
 
-    match state.redis_client.get_all_page_stats().await {
-        Ok(stats) => Ok(Json(stats)),
-        Err(e) => {
-            warn!("Failed to get all stats: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let mut all_stats = site.store.get_all_page_stats().await.map_err(|e| {
+        warn!("Failed to get all stats: {}", e);
+        ApiError::StoreUnavailable
+    })?;
+    if let Some(kind) = query.kind {
+        all_stats.retain(|stats| stats.kind == kind);
+    }
+    Ok(Json(all_stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_tracking_allowed_without_signals() {
+        let headers = HeaderMap::new();
+        assert!(tracking_allowed(&headers));
+    }
+
+    #[test]
+    fn test_tracking_disallowed_with_privacy_signals() {
+        let mut headers = HeaderMap::new();
+        headers.insert("DNT", HeaderValue::from_static("1"));
+        assert!(!tracking_allowed(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Sec-GPC", HeaderValue::from_static("1"));
+        assert!(!tracking_allowed(&headers));
+    }
+
+    #[test]
+    fn test_tracking_allowed_when_signal_is_off() {
+        let mut headers = HeaderMap::new();
+        headers.insert("DNT", HeaderValue::from_static("0"));
+        assert!(tracking_allowed(&headers));
+    }
+
+    #[test]
+    fn test_versioned_and_legacy_routes_do_not_conflict() {
+        let (api, _) = versioned_api();
+        let (legacy_api, _) = api_routes().split_for_parts();
+        let _: Router<AppState> = Router::new().merge(api).nest("/api", legacy_api);
+    }
+
+    /// The committed openapi.json is what the dashboard and CLI build against.
+    /// Regenerate it with `UPDATE_OPENAPI=1 cargo test -p page-stats-server`.
+    #[test]
+    fn test_openapi_spec_is_up_to_date() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        let (_, openapi) = versioned_api();
+        let generated = openapi.to_pretty_json().unwrap() + "\n";
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(&path, &generated).unwrap();
         }
+
+        let committed = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "openapi.json is out of date with the handlers, \
+             run `UPDATE_OPENAPI=1 cargo test -p page-stats-server` and commit the result"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_alias_headers() {
+        use axum::body::Body;
+        use tower::ServiceExt;
+
+        let app: Router = Router::new().nest(
+            "/api",
+            Router::new()
+                .route("/search", get(|| async { "ok" }))
+                .layer(middleware::from_fn(deprecated_alias)),
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search?q=rust")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(
+            response.headers()[header::LINK],
+            "</api/v1/search>; rel=\"successor-version\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_alias_keeps_successor_of_slug_routes() {
+        use axum::body::Body;
+        use tower::ServiceExt;
+
+        let app: Router = Router::new().nest(
+            "/api",
+            Router::new()
+                .route(
+                    "/stats/{slug}",
+                    get(|| async {
+                        deprecated_slug_route("ok".into_response(), "/stats/page/about")
+                    }),
+                )
+                .layer(middleware::from_fn(deprecated_alias)),
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/stats/about")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(
+            response.headers()[header::LINK],
+            "</api/v1/stats/page/about>; rel=\"successor-version\""
+        );
     }
 }
@@ -0,0 +1,251 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::content::{Post, PostFrontmatter};
+use crate::error::ApiError;
+
+/// Posts per page when the request doesn't ask for a number
+const DEFAULT_PER_PAGE: usize = 20;
+
+/// Most posts returned in one page
+pub const MAX_PER_PAGE: usize = 100;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PostsQuery {
+    /// Only posts with this tag
+    tag: Option<String>,
+    /// Only posts in this category, posts can list several separated by commas
+    category: Option<String>,
+    /// Include drafts (`published: false`), left out by default
+    draft: Option<bool>,
+    /// Only posts dated on or after this day, `YYYY-MM-DD`
+    from: Option<String>,
+    /// Only posts dated on or before this day, `YYYY-MM-DD`
+    to: Option<String>,
+    /// Page to return, starting at 1
+    page: Option<usize>,
+    /// Posts per page, 20 by default and at most 100
+    per_page: Option<usize>,
+}
+
+/// Slug and frontmatter of a post, without its body
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PostSummary {
+    pub slug: String,
+    pub frontmatter: PostFrontmatter,
+}
+
+/// A post with its raw markdown body
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PostDetail {
+    pub slug: String,
+    pub frontmatter: PostFrontmatter,
+    /// Markdown after the frontmatter
+    pub body: String,
+}
+
+/// One page of the posts matching a query, newest first
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PostList {
+    pub posts: Vec<PostSummary>,
+    /// Matching posts on all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+/// The posts read from the content directory, newest first
+#[derive(Debug, Default)]
+pub struct PostCache {
+    posts: Vec<Post>,
+}
+
+impl PostCache {
+    /// Replace the cached posts, sorting them by date (newest first) and slug
+    pub fn update(&mut self, mut posts: Vec<Post>) {
+        posts.sort_by(|a, b| {
+            b.frontmatter
+                .date
+                .cmp(&a.frontmatter.date)
+                .then_with(|| a.slug.cmp(&b.slug))
+        });
+        self.posts = posts;
+    }
+
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+
+    /// A post by slug, drafts included as they are reachable by URL
+    pub fn get(&self, slug: &str) -> Option<PostDetail> {
+        self.posts
+            .iter()
+            .find(|post| post.slug == slug)
+            .map(|post| PostDetail {
+                slug: post.slug.clone(),
+                frontmatter: post.frontmatter.clone(),
+                body: post.body.clone(),
+            })
+    }
+
+    /// The page of posts matching the filters of the query
+    pub fn list(&self, query: &PostsQuery) -> Result<PostList, ApiError> {
+        let from = query.from.as_deref().map(parse_day).transpose()?;
+        let to = query.to.as_deref().map(parse_day).transpose()?;
+        let page = query.page.unwrap_or(1);
+        if page == 0 {
+            return Err(ApiError::InvalidRequest("page starts at 1".to_string()));
+        }
+        let per_page = query
+            .per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE);
+
+        let matching: Vec<&Post> = self
+            .posts
+            .iter()
+            .filter(|post| query.draft.unwrap_or(false) || post.frontmatter.published)
+            .filter(|post| {
+                query
+                    .tag
+                    .as_ref()
+                    .is_none_or(|tag| post.frontmatter.tags.contains(tag))
+            })
+            .filter(|post| {
+                query
+                    .category
+                    .as_deref()
+                    .is_none_or(|category| post.frontmatter.categories().any(|c| c == category))
+            })
+            .filter(|post| {
+                if from.is_none() && to.is_none() {
+                    return true;
+                }
+                // Posts without a date can't be placed in a range
+                post.frontmatter.date.is_some_and(|day| {
+                    from.is_none_or(|from| day >= from) && to.is_none_or(|to| day <= to)
+                })
+            })
+            .collect();
+
+        let posts = matching
+            .iter()
+            .skip((page - 1).saturating_mul(per_page))
+            .take(per_page)
+            .map(|post| PostSummary {
+                slug: post.slug.clone(),
+                frontmatter: post.frontmatter.clone(),
+            })
+            .collect();
+
+        Ok(PostList {
+            posts,
+            total: matching.len(),
+            page,
+            per_page,
+        })
+    }
+}
+
+fn parse_day(day: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d").map_err(|_| {
+        ApiError::InvalidRequest(format!(
+            "expected a YYYY-MM-DD date, got '{}'",
+            day.chars().take(32).collect::<String>()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::parse_post;
+
+    fn cache() -> PostCache {
+        let mut cache = PostCache::default();
+        cache.update(vec![
+            parse_post(
+                "old",
+                "---\ntitle: Old\ndate: 2021-06-09\ntags: [rust]\ncategory: code, embedded\n---\nOld",
+            ),
+            parse_post(
+                "new",
+                "---\ntitle: New\ndate: \"2024-01-01\"\ntags: [rust, featured]\ncategory: code\n---\nNew",
+            ),
+            parse_post(
+                "draft",
+                "---\ntitle: Draft\ndate: 2025-01-01\ntags: [rust]\npublished: false\n---\nDraft",
+            ),
+        ]);
+        cache
+    }
+
+    fn slugs(list: &PostList) -> Vec<&str> {
+        list.posts.iter().map(|post| post.slug.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let cache = cache();
+
+        let all = cache.list(&PostsQuery::default()).unwrap();
+        assert_eq!(slugs(&all), ["new", "old"]);
+
+        let query = PostsQuery {
+            draft: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(slugs(&cache.list(&query).unwrap()), ["draft", "new", "old"]);
+
+        let query = PostsQuery {
+            tag: Some("featured".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(slugs(&cache.list(&query).unwrap()), ["new"]);
+
+        let query = PostsQuery {
+            category: Some("embedded".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(slugs(&cache.list(&query).unwrap()), ["old"]);
+
+        let query = PostsQuery {
+            from: Some("2022-01-01".to_string()),
+            to: Some("2024-01-01".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(slugs(&cache.list(&query).unwrap()), ["new"]);
+
+        let query = PostsQuery {
+            from: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+        assert!(cache.list(&query).is_err());
+    }
+
+    #[test]
+    fn test_list_pagination() {
+        let cache = cache();
+        let query = PostsQuery {
+            draft: Some(true),
+            page: Some(2),
+            per_page: Some(2),
+            ..Default::default()
+        };
+
+        let list = cache.list(&query).unwrap();
+        assert_eq!(slugs(&list), ["old"]);
+        assert_eq!(list.total, 3);
+
+        let query = PostsQuery {
+            page: Some(0),
+            ..Default::default()
+        };
+        assert!(cache.list(&query).is_err());
+
+        assert_eq!(cache.get("draft").unwrap().body, "Draft");
+        assert!(cache.get("missing").is_none());
+    }
+}
@@ -0,0 +1,218 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::ApiError;
+
+/// Number of tracked clients after which expired windows are purged
+const PURGE_THRESHOLD: usize = 10_000;
+
+/// Fixed-window limiter counting requests per client
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    trusted_proxies: TrustedProxies,
+    clients: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration, trusted_proxies: TrustedProxies) -> Self {
+        Self {
+            limit,
+            window,
+            trusted_proxies,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request for the client, returns how long to wait when over the limit
+    pub fn check(&self, client: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().expect("rate limiter lock poisoned");
+
+        if clients.len() >= PURGE_THRESHOLD {
+            clients.retain(|_, (started, _)| now.duration_since(*started) < self.window);
+        }
+
+        let (started, count) = clients.entry(client.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= self.window {
+            *started = now;
+            *count = 0;
+        }
+
+        if *count >= self.limit {
+            return Err(self.window - now.duration_since(*started));
+        }
+
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Number of reverse proxies in front of the server that append to `X-Forwarded-For`.
+/// With none the header is ignored, as any client can set it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrustedProxies(pub usize);
+
+/// Address of the client: the peer, or with trusted proxies the rightmost `X-Forwarded-For`
+/// entry they didn't add themselves. Entries further left are client supplied and ignored.
+pub fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    TrustedProxies(hops): TrustedProxies,
+) -> Option<IpAddr> {
+    if hops == 0 {
+        return peer;
+    }
+
+    // The peer is the nearest proxy, each one before it appended the address it saw
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .collect();
+
+    forwarded
+        .iter()
+        .rev()
+        .nth(hops - 1)
+        .or(forwarded.first())
+        .and_then(|ip| ip.parse().ok())
+        .or(peer)
+}
+
+/// Identify the client for rate limiting
+fn client_key(headers: &HeaderMap, peer: Option<SocketAddr>, trusted: TrustedProxies) -> String {
+    client_ip(headers, peer.map(|addr| addr.ip()), trusted)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Query parameters that make a GET count something
+const TRACKING_PARAMS: [&str; 2] = ["track_view=true", "track_impression=true"];
+
+/// Whether the request writes: anything but GET, or a GET counting a view or an impression
+fn is_write(req: &Request) -> bool {
+    req.method() != Method::GET
+        || req
+            .uri()
+            .query()
+            .is_some_and(|query| query.split('&').any(|pair| TRACKING_PARAMS.contains(&pair)))
+}
+
+/// Middleware rejecting write requests above the limit
+pub async fn limit_writes(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if is_write(&req) {
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        let client = client_key(req.headers(), peer, limiter.trusted_proxies);
+
+        limiter
+            .check(&client)
+            .map_err(|retry_after| ApiError::RateLimited { retry_after })?;
+    }
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_limit_per_client() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60), TrustedProxies(0));
+
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_err());
+        assert!(limiter.check("b").is_ok());
+    }
+
+    #[test]
+    fn test_window_resets() {
+        let limiter = RateLimiter::new(1, Duration::ZERO, TrustedProxies(0));
+
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_ok());
+    }
+
+    #[test]
+    fn test_client_key_ignores_forwarded_for_without_trusted_proxies() {
+        let peer = Some("10.0.0.1:1234".parse().unwrap());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_key(&headers, peer, TrustedProxies(0)), "10.0.0.1");
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.7"));
+        assert_eq!(client_key(&headers, peer, TrustedProxies(0)), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_client_key_takes_rightmost_untrusted_entry() {
+        let peer = Some("10.0.0.1:1234".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("198.51.100.1, 203.0.113.7, 10.0.0.2"),
+        );
+
+        assert_eq!(client_key(&headers, peer, TrustedProxies(1)), "10.0.0.2");
+        assert_eq!(client_key(&headers, peer, TrustedProxies(2)), "203.0.113.7");
+        // More hops than entries: the leftmost was still added by a trusted proxy
+        assert_eq!(
+            client_key(&headers, peer, TrustedProxies(5)),
+            "198.51.100.1"
+        );
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("not an ip"));
+        assert_eq!(client_key(&headers, peer, TrustedProxies(1)), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_view_tracking_counts_as_write() {
+        let request = |method: Method, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        assert!(is_write(&request(
+            Method::POST,
+            "/api/v1/stats/post/a/like"
+        )));
+        assert!(is_write(&request(
+            Method::GET,
+            "/api/v1/stats/post/a?track_view=true"
+        )));
+        assert!(!is_write(&request(
+            Method::GET,
+            "/api/v1/stats/post/a?track_view=false"
+        )));
+        assert!(!is_write(&request(Method::GET, "/api/v1/stats/post/a")));
+        assert!(is_write(&request(
+            Method::GET,
+            "/api/v1/experiments/a?track_impression=true"
+        )));
+        assert!(!is_write(&request(Method::GET, "/api/v1/experiments/a")));
+    }
+}
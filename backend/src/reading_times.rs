@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use crate::content::Post;
+use crate::redis_client::{ContentKind, PageId};
+
+/// Reading time of a page and a hash of the markdown it was calculated from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingTime {
+    pub seconds: u64,
+    pub content_hash: String,
+}
+
+impl ReadingTime {
+    pub fn of(markdown: &str) -> Self {
+        Self {
+            seconds: reading_time::calculate_reading_time(markdown),
+            content_hash: reading_time::content_hash(markdown),
+        }
+    }
+}
+
+/// Reading times of the posts and pages in the content directory. Stored stats whose hash
+/// differs are updated when the page is next requested.
+#[derive(Debug, Default)]
+pub struct ReadingTimes {
+    times: HashMap<PageId, ReadingTime>,
+}
+
+impl ReadingTimes {
+    /// Replace the reading times of one kind of content with those of `posts`
+    pub fn update(&mut self, kind: ContentKind, posts: &[Post]) {
+        self.times.retain(|page, _| page.kind != kind);
+        self.times.extend(
+            posts
+                .iter()
+                .map(|post| (PageId::new(kind, &post.slug), ReadingTime::of(&post.body))),
+        );
+    }
+
+    pub fn get(&self, page: &PageId) -> Option<&ReadingTime> {
+        self.times.get(page)
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::parse_post;
+
+    #[test]
+    fn test_update_replaces_one_kind() {
+        let mut times = ReadingTimes::default();
+        times.update(ContentKind::Post, &[parse_post("a", "First version")]);
+        times.update(ContentKind::Page, &[parse_post("a", "An about page")]);
+        let before = times.get(&PageId::post("a")).cloned().unwrap();
+
+        times.update(ContentKind::Post, &[parse_post("a", "Edited version")]);
+
+        let after = times.get(&PageId::post("a")).unwrap();
+        assert_ne!(before.content_hash, after.content_hash);
+        assert!(times.get(&PageId::new(ContentKind::Page, "a")).is_some());
+        assert_eq!(times.len(), 2);
+    }
+
+    #[test]
+    fn test_frontmatter_is_not_counted() {
+        let post = parse_post("a", "---\ntitle: A long title here\n---\nBody");
+        assert_eq!(ReadingTime::of(&post.body), ReadingTime::of("Body"));
+    }
+}
@@ -1,28 +1,144 @@
+use chrono::{NaiveDate, Utc};
 use redis::aio::ConnectionManager;
-use redis::{AsyncCommands, Client, RedisResult};
+use redis::{AsyncCommands, Client, Pipeline, RedisResult, Script};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::OnceCell;
+use tracing::instrument;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+use crate::events::{self, Event, EventCount};
+use crate::experiments::{self, ExperimentAction, VariantCounts};
+use crate::reading_times::ReadingTime;
+use crate::store::Increment;
+use crate::user_agent::{self, ClientBreakdown};
+
+/// What a page is, stats of different kinds never share a key
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    /// A blog post from content/posts
+    #[default]
+    Post,
+    /// A fixed page of the site, e.g. home or about
+    Page,
+    /// A short note
+    Note,
+}
+
+impl ContentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContentKind::Post => "post",
+            ContentKind::Page => "page",
+            ContentKind::Note => "note",
+        }
+    }
+}
+
+impl std::fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "post" => Ok(ContentKind::Post),
+            "page" => Ok(ContentKind::Page),
+            "note" => Ok(ContentKind::Note),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A page stats are kept for: its kind and slug
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PageId {
+    pub kind: ContentKind,
+    pub slug: String,
+}
+
+impl PageId {
+    pub fn new(kind: ContentKind, slug: &str) -> Self {
+        Self {
+            kind,
+            slug: slug.to_string(),
+        }
+    }
+
+    pub fn post(slug: &str) -> Self {
+        Self::new(ContentKind::Post, slug)
+    }
+
+    /// Parse the `<kind>:<slug>` form used in the index set
+    fn parse(s: &str) -> Option<Self> {
+        let (kind, slug) = s.split_once(':')?;
+        if slug.is_empty() || slug.contains(':') {
+            return None;
+        }
+        Some(Self::new(kind.parse().ok()?, slug))
+    }
+}
+
+impl std::fmt::Display for PageId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.kind, self.slug)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, ToSchema)]
 pub struct PageStats {
     pub slug: String,
+    /// Stats written before kinds existed are posts
+    #[serde(default)]
+    pub kind: ContentKind,
     pub reads: u64,
     pub views: u64,
     pub likes: u64,
+    /// Reading time in seconds, calculated by the server from the page's markdown
     pub time: u64,
+    /// Hash of the markdown `time` was calculated from, empty when it never was
+    #[serde(default)]
+    pub content_hash: String,
 }
 
 impl PageStats {
-    pub fn new(slug: &str) -> Self {
+    pub fn new(page: &PageId) -> Self {
         Self {
-            slug: slug.to_string(),
+            slug: page.slug.clone(),
+            kind: page.kind,
             reads: 0,
             views: 0,
             likes: 0,
             time: 0,
+            content_hash: String::new(),
         }
     }
 
+    pub fn id(&self) -> PageId {
+        PageId::new(self.kind, &self.slug)
+    }
+
     pub fn increment_views(&mut self) {
         self.views += 1;
     }
@@ -31,15 +147,125 @@ impl PageStats {
         self.likes += 1;
     }
 
-    pub fn set_reading_time(&mut self, seconds: u64) {
-        if self.time == 0 {
-            self.time = seconds;
+    /// Whether `reading` was calculated from other markdown than the stored reading time
+    pub fn needs_reading_time(&self, reading: &ReadingTime) -> bool {
+        self.content_hash != reading.content_hash
+    }
+
+    pub fn set_reading_time(&mut self, reading: &ReadingTime) {
+        self.time = reading.seconds;
+        self.content_hash = reading.content_hash.clone();
+    }
+}
+
+/// Hash of view counts over a period of time, kept next to the totals in [`PageStats`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ViewBucket {
+    /// Views per hour of a day, fields `00` to `23`
+    Hourly { page: PageId, day: NaiveDate },
+    /// Views per day of a month (given by its first day), fields `01` to `31`
+    Daily { page: PageId, month: NaiveDate },
+}
+
+impl std::fmt::Display for ViewBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViewBucket::Hourly { page, day } => write!(f, "{} hourly {}", page, day),
+            ViewBucket::Daily { page, month } => {
+                write!(f, "{} daily {}", page, month.format("%Y-%m"))
+            }
         }
     }
 }
 
+/// Lua reading the stats of a page, or fresh stats when there are none or they don't parse
+const LOAD_STATS_LUA: &str = r#"
+local function load_stats(key, slug)
+  local stats
+  local json = redis.call('GET', key)
+  if json then
+    local ok, decoded = pcall(cjson.decode, json)
+    if ok and type(decoded) == 'table' then
+      stats = decoded
+    end
+  end
+  stats = stats or {}
+  stats.slug = stats.slug or slug
+  stats.reads = stats.reads or 0
+  stats.views = stats.views or 0
+  stats.likes = stats.likes or 0
+  stats.time = stats.time or 0
+  stats.content_hash = stats.content_hash or ''
+  return stats
+end
+"#;
+
+/// Adds views and likes to pages in one step, so no other write can land between reading
+/// and writing a page's stats.
+/// KEYS: the index set, then per page its stats key and hourly views key.
+/// ARGV: the hour, then per page its index member, slug, kind, views and likes.
+static INCREMENT_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(&format!(
+        "{}{}",
+        LOAD_STATS_LUA,
+        r#"
+local updated = {}
+for i = 0, (#KEYS - 1) / 2 - 1 do
+  local member, slug, kind = ARGV[2 + i * 5], ARGV[3 + i * 5], ARGV[4 + i * 5]
+  local views, likes = tonumber(ARGV[5 + i * 5]), tonumber(ARGV[6 + i * 5])
+  local stats = load_stats(KEYS[2 + i * 2], slug)
+  stats.kind = kind
+  stats.views = stats.views + views
+  stats.likes = stats.likes + likes
+  local json = cjson.encode(stats)
+  redis.call('SET', KEYS[2 + i * 2], json)
+  redis.call('SADD', KEYS[1], member)
+  if views > 0 then
+    redis.call('HINCRBY', KEYS[3 + i * 2], ARGV[1], views)
+  end
+  updated[i + 1] = json
+end
+return updated
+"#
+    ))
+});
+
+/// Replaces the reading time of a page without touching its counts.
+/// KEYS: the index set and the page's stats key.
+/// ARGV: the page's index member, slug, kind, reading time and content hash.
+static READING_TIME_SCRIPT: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(&format!(
+        "{}{}",
+        LOAD_STATS_LUA,
+        r#"
+local stats = load_stats(KEYS[2], ARGV[2])
+stats.kind = ARGV[3]
+stats.time = tonumber(ARGV[4])
+stats.content_hash = ARGV[5]
+local json = cjson.encode(stats)
+redis.call('SET', KEYS[2], json)
+redis.call('SADD', KEYS[1], ARGV[1])
+return json
+"#
+    ))
+});
+
+/// Parse stats as returned by one of the scripts
+fn parse_stats(json: &str) -> RedisResult<PageStats> {
+    serde_json::from_str(json).map_err(|e| {
+        redis::RedisError::from((
+            redis::ErrorKind::Parse,
+            "JSON deserialization failed",
+            e.to_string(),
+        ))
+    })
+}
+
+#[derive(Clone)]
 pub struct RedisPageStatsClient {
-    connection_manager: ConnectionManager,
+    client: Client,
+    /// Connected on first use, so a client can be created while Redis is down
+    connection_manager: Arc<OnceCell<ConnectionManager>>,
     env_prefix: String,
 }
 
@@ -50,10 +276,17 @@ impl RedisPageStatsClient {
     /// * `redis_url` - Redis connection URL (e.g., "redis://127.0.0.1:6379")
     /// * `env_prefix` - Environment prefix for keys (e.g., "prod", "dev", "staging")
     pub async fn new(redis_url: &str, env_prefix: &str) -> RedisResult<Self> {
-        let client = Client::open(redis_url)?;
-        let connection_manager = ConnectionManager::new(client).await?;
+        let client = Self::lazy(redis_url, env_prefix)?;
+        client.get_connection().await?;
+        Ok(client)
+    }
+
+    /// Create a client that connects on its first call instead of right away, failing
+    /// only on an invalid URL. Calls fail until Redis can be reached.
+    pub fn lazy(redis_url: &str, env_prefix: &str) -> RedisResult<Self> {
         Ok(Self {
-            connection_manager,
+            client: Client::open(redis_url)?,
+            connection_manager: Arc::new(OnceCell::new()),
             env_prefix: env_prefix.to_string(),
         })
     }
@@ -71,37 +304,181 @@ impl RedisPageStatsClient {
         Self::new(&redis_url, &env_prefix).await
     }
 
-    /// Generate the Redis key for a given slug
-    /// Format: <env>:post:<slug>:page_stats
-    fn generate_key(&self, slug: &str) -> String {
-        format!("{}:post:{}:page_stats", self.env_prefix, slug)
+    /// Client sharing this connection that uses another key prefix, e.g. `<env>:<site>`
+    pub fn with_prefix(&self, env_prefix: &str) -> Self {
+        Self {
+            client: self.client.clone(),
+            connection_manager: self.connection_manager.clone(),
+            env_prefix: env_prefix.to_string(),
+        }
+    }
+
+    /// Generate the Redis key for a given page
+    /// Format: <env>:<kind>:<slug>:page_stats
+    fn generate_key(&self, page: &PageId) -> String {
+        Self::_generate_key_static(&self.env_prefix, page)
     }
 
     /// Generate a Redis key without needing a connection (for testing)
-    pub fn _generate_key_static(env_prefix: &str, slug: &str) -> String {
-        format!("{}:post:{}:page_stats", env_prefix, slug)
+    pub fn _generate_key_static(env_prefix: &str, page: &PageId) -> String {
+        format!("{}:{}:{}:page_stats", env_prefix, page.kind, page.slug)
+    }
+
+    /// Key of the set holding `<kind>:<slug>` of all pages with stats
+    /// Format: <env>:page_index
+    fn index_key(&self) -> String {
+        format!("{}:page_index", self.env_prefix)
+    }
+
+    /// Index set of post slugs used before pages had a kind, removed by [`Self::repair_index`]
+    fn legacy_index_key(&self) -> String {
+        format!("{}:page_stats_index", self.env_prefix)
+    }
+
+    /// Key of the hourly views of a page on one day
+    /// Format: <env>:<kind>:<slug>:views:hourly:<YYYY-MM-DD>
+    fn hourly_views_key(&self, page: &PageId, day: NaiveDate) -> String {
+        format!(
+            "{}:{}:{}:views:hourly:{}",
+            self.env_prefix,
+            page.kind,
+            page.slug,
+            day.format("%Y-%m-%d")
+        )
+    }
+
+    /// Key of the daily views of a page in one month
+    /// Format: <env>:<kind>:<slug>:views:daily:<YYYY-MM>
+    fn daily_views_key(&self, page: &PageId, month: NaiveDate) -> String {
+        format!(
+            "{}:{}:{}:views:daily:{}",
+            self.env_prefix,
+            page.kind,
+            page.slug,
+            month.format("%Y-%m")
+        )
+    }
+
+    /// Key of the monthly views of a page, fields `YYYY-MM`
+    /// Format: <env>:<kind>:<slug>:views:monthly
+    fn monthly_views_key(&self, page: &PageId) -> String {
+        format!(
+            "{}:{}:{}:views:monthly",
+            self.env_prefix, page.kind, page.slug
+        )
+    }
+
+    /// Key of the views of a page per country, fields are ISO 3166-1 alpha-2 codes
+    /// Format: <env>:<kind>:<slug>:countries
+    fn countries_key(&self, page: &PageId) -> String {
+        format!("{}:{}:{}:countries", self.env_prefix, page.kind, page.slug)
+    }
+
+    /// Key of the views of a page per device, browser and OS, see [`Client::fields`]
+    /// Format: <env>:<kind>:<slug>:clients
+    fn clients_key(&self, page: &PageId) -> String {
+        format!("{}:{}:{}:clients", self.env_prefix, page.kind, page.slug)
+    }
+
+    /// Key of the custom events of a page, see [`Event::fields`]
+    /// Format: <env>:<kind>:<slug>:events
+    fn events_key(&self, page: &PageId) -> String {
+        format!("{}:{}:{}:events", self.env_prefix, page.kind, page.slug)
+    }
+
+    /// Key of the impressions and clicks per variant of a post, see [`ExperimentAction::field`]
+    /// Format: <env>:<kind>:<slug>:experiment
+    fn experiment_key(&self, page: &PageId) -> String {
+        format!("{}:{}:{}:experiment", self.env_prefix, page.kind, page.slug)
+    }
+
+    /// Key of the views of all pages per device, browser and OS
+    /// Format: <env>:clients
+    fn site_clients_key(&self) -> String {
+        format!("{}:clients", self.env_prefix)
+    }
+
+    /// Page and rest of a `<env>:<kind>:<slug>:<rest>` key, None for keys of other
+    /// environments
+    fn split_key<'a>(env_prefix: &str, key: &'a str) -> Option<(PageId, &'a str)> {
+        let rest = key.strip_prefix(env_prefix)?.strip_prefix(':')?;
+        let (kind, rest) = rest.split_once(':')?;
+        let (slug, rest) = rest.split_once(':')?;
+        Some((PageId::new(kind.parse().ok()?, slug), rest))
+    }
+
+    /// Bucket of an hourly or daily views key, None for other keys
+    fn bucket_from_key(env_prefix: &str, key: &str) -> Option<ViewBucket> {
+        let (page, rest) = Self::split_key(env_prefix, key)?;
+        match rest.strip_prefix("views:")?.split_once(':')? {
+            ("hourly", day) => Some(ViewBucket::Hourly {
+                page,
+                day: NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?,
+            }),
+            ("daily", month) => Some(ViewBucket::Daily {
+                page,
+                month: NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()?,
+            }),
+            _ => None,
+        }
     }
 
-    /// Get a clone of the connection manager
-    fn get_connection(&self) -> ConnectionManager {
-        self.connection_manager.clone()
+    /// Page of a stats key, None for keys of other environments or types
+    fn page_from_key(env_prefix: &str, key: &str) -> Option<PageId> {
+        Self::split_key(env_prefix, key)
+            .and_then(|(page, rest)| (rest == "page_stats").then_some(page))
     }
 
-    /// Get page stats for a specific slug
+    /// Get a clone of the connection manager, connecting first if that didn't happen yet
+    async fn get_connection(&self) -> RedisResult<ConnectionManager> {
+        self.connection_manager
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+
+    /// All keys matching a pattern, found with a SCAN
+    async fn scan_keys(&self, pattern: &str) -> RedisResult<Vec<String>> {
+        let mut conn = self.get_connection().await?;
+        let mut found = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (new_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .cursor_arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .query_async(&mut conn)
+                .await?;
+            found.extend(keys);
+
+            cursor = new_cursor;
+            if cursor == 0 {
+                break;
+            }
+        }
+        Ok(found)
+    }
+
+    /// Get page stats for a specific page
     /// Returns None if the key doesn't exist
-    pub async fn get_page_stats(&self, slug: &str) -> RedisResult<Option<PageStats>> {
-        let mut conn = self.get_connection();
-        let key = self.generate_key(slug);
+    #[instrument(name = "redis.get_page_stats", skip(self), fields(page = %page))]
+    pub async fn get_page_stats(&self, page: &PageId) -> RedisResult<Option<PageStats>> {
+        let mut conn = self.get_connection().await?;
+        let key = self.generate_key(page);
 
         let json_string: Option<String> = conn.get(&key).await?;
 
         match json_string {
             Some(json) => {
                 match serde_json::from_str::<PageStats>(&json) {
-                    Ok(stats) => Ok(Some(stats)),
+                    // The key decides the kind, stats written before kinds existed have none
+                    Ok(stats) => Ok(Some(PageStats {
+                        kind: page.kind,
+                        ..stats
+                    })),
                     Err(_) => {
                         // If JSON parsing fails, return a new PageStats with the slug
-                        Ok(Some(PageStats::new(slug)))
+                        Ok(Some(PageStats::new(page)))
                     }
                 }
             }
@@ -109,11 +486,8 @@ impl RedisPageStatsClient {
         }
     }
 
-    /// Set page stats for a specific slug
-    pub async fn set_page_stats(&self, stats: &PageStats) -> RedisResult<()> {
-        let mut conn = self.get_connection();
-        let key = self.generate_key(&stats.slug);
-
+    /// Queue the writes of one page's stats: the stats and its index entry
+    fn add_to_pipe(&self, pipe: &mut Pipeline, stats: &PageStats) -> RedisResult<()> {
         let json_string = serde_json::to_string(stats).map_err(|e| {
             redis::RedisError::from((
                 redis::ErrorKind::Parse,
@@ -122,89 +496,444 @@ impl RedisPageStatsClient {
             ))
         })?;
 
-        conn.set::<_, _, ()>(&key, json_string).await?;
+        let page = stats.id();
+        pipe.set(self.generate_key(&page), json_string)
+            .ignore()
+            .sadd(self.index_key(), page.to_string())
+            .ignore();
         Ok(())
     }
 
-    /// Increment the view count for a specific slug
-    pub async fn increment_views(&self, slug: &str) -> RedisResult<PageStats> {
-        let mut stats = self
-            .get_page_stats(slug)
-            .await?
-            .unwrap_or_else(|| PageStats::new(slug));
+    /// Remove the stats of a page
+    pub async fn delete_page_stats(&self, page: &PageId) -> RedisResult<()> {
+        let mut conn = self.get_connection().await?;
+        redis::pipe()
+            .atomic()
+            .del(self.generate_key(page))
+            .ignore()
+            .srem(self.index_key(), page.to_string())
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await
+    }
 
-        stats.increment_views();
-        self.set_page_stats(&stats).await?;
-        Ok(stats)
+    /// Count a view of a page from a country
+    pub async fn increment_country(&self, page: &PageId, country: &str) -> RedisResult<()> {
+        let mut conn = self.get_connection().await?;
+        conn.hincr(self.countries_key(page), country, 1).await
     }
 
-    /// Increment the like count for a specific slug
-    pub async fn increment_likes(&self, slug: &str) -> RedisResult<PageStats> {
-        let mut stats = self
-            .get_page_stats(slug)
-            .await?
-            .unwrap_or_else(|| PageStats::new(slug));
+    /// Views of a page per country
+    pub async fn get_countries(&self, page: &PageId) -> RedisResult<BTreeMap<String, u64>> {
+        let mut conn = self.get_connection().await?;
+        conn.hgetall(self.countries_key(page)).await
+    }
 
-        stats.increment_likes();
-        self.set_page_stats(&stats).await?;
-        Ok(stats)
+    /// Count an event on a page, with each of its properties
+    pub async fn increment_event(&self, page: &PageId, event: &Event) -> RedisResult<()> {
+        let mut conn = self.get_connection().await?;
+        let mut pipe = redis::pipe();
+        for field in event.fields() {
+            pipe.hincr(self.events_key(page), field, 1).ignore();
+        }
+        pipe.query_async(&mut conn).await
     }
 
-    /// Set reading time for a specific slug (only if not already set)
-    pub async fn set_reading_time(&self, slug: &str, seconds: u64) -> RedisResult<PageStats> {
-        let mut stats = self
-            .get_page_stats(slug)
-            .await?
-            .unwrap_or_else(|| PageStats::new(slug));
+    /// Counts of the events on a page
+    pub async fn get_events(&self, page: &PageId) -> RedisResult<BTreeMap<String, EventCount>> {
+        let mut conn = self.get_connection().await?;
+        let fields: HashMap<String, u64> = conn.hgetall(self.events_key(page)).await?;
+        Ok(events::counts_from_fields(fields))
+    }
+
+    /// Count an impression or click of a variant of a page's experiment
+    pub async fn increment_experiment(
+        &self,
+        page: &PageId,
+        variant: usize,
+        action: ExperimentAction,
+    ) -> RedisResult<()> {
+        let mut conn = self.get_connection().await?;
+        conn.hincr(self.experiment_key(page), action.field(variant), 1)
+            .await
+    }
+
+    /// Impressions and clicks per variant of each page's experiment, in one round trip
+    pub async fn get_experiments(
+        &self,
+        pages: &[PageId],
+    ) -> RedisResult<Vec<BTreeMap<usize, VariantCounts>>> {
+        let mut conn = self.get_connection().await?;
+        let mut pipe = redis::pipe();
+        for page in pages {
+            pipe.hgetall(self.experiment_key(page));
+        }
+        let fields: Vec<HashMap<String, u64>> = pipe.query_async(&mut conn).await?;
+        Ok(fields
+            .into_iter()
+            .map(experiments::counts_from_fields)
+            .collect())
+    }
 
-        stats.set_reading_time(seconds);
-        self.set_page_stats(&stats).await?;
-        Ok(stats)
+    /// Count a view of a page from a client, for the page and for the whole site
+    pub async fn increment_client(
+        &self,
+        page: &PageId,
+        client: &user_agent::Client,
+    ) -> RedisResult<()> {
+        let mut conn = self.get_connection().await?;
+        let mut pipe = redis::pipe();
+        for field in client.fields() {
+            pipe.hincr(self.clients_key(page), &field, 1)
+                .ignore()
+                .hincr(self.site_clients_key(), &field, 1)
+                .ignore();
+        }
+        pipe.query_async(&mut conn).await
+    }
+
+    /// Views of each page per device, browser and OS, in one round trip
+    pub async fn get_clients(&self, pages: &[PageId]) -> RedisResult<Vec<ClientBreakdown>> {
+        let mut conn = self.get_connection().await?;
+        let mut pipe = redis::pipe();
+        for page in pages {
+            pipe.hgetall(self.clients_key(page));
+        }
+        let fields: Vec<HashMap<String, u64>> = pipe.query_async(&mut conn).await?;
+        Ok(fields
+            .into_iter()
+            .map(ClientBreakdown::from_fields)
+            .collect())
+    }
+
+    /// Views of the whole site per device, browser and OS
+    pub async fn get_site_clients(&self) -> RedisResult<ClientBreakdown> {
+        let mut conn = self.get_connection().await?;
+        let fields: HashMap<String, u64> = conn.hgetall(self.site_clients_key()).await?;
+        Ok(ClientBreakdown::from_fields(fields))
+    }
+
+    /// Increment the view count for a specific page
+    pub async fn increment_views(&self, page: &PageId) -> RedisResult<PageStats> {
+        self.increment_one(page, 1, 0).await
+    }
+
+    /// Increment the like count for a specific page
+    pub async fn increment_likes(&self, page: &PageId) -> RedisResult<PageStats> {
+        self.increment_one(page, 0, 1).await
+    }
+
+    async fn increment_one(&self, page: &PageId, views: u64, likes: u64) -> RedisResult<PageStats> {
+        let increment = Increment {
+            page: page.clone(),
+            views,
+            likes,
+        };
+        let mut updated = self.increment_many(&[increment]).await?;
+        Ok(updated.remove(0))
+    }
+
+    /// Set the reading time of a specific page, replacing the one calculated before
+    pub async fn set_reading_time(
+        &self,
+        page: &PageId,
+        reading: &ReadingTime,
+    ) -> RedisResult<PageStats> {
+        let mut conn = self.get_connection().await?;
+        let json: String = READING_TIME_SCRIPT
+            .key(self.index_key())
+            .key(self.generate_key(page))
+            .arg(page.to_string())
+            .arg(&page.slug)
+            .arg(page.kind.as_str())
+            .arg(reading.seconds)
+            .arg(&reading.content_hash)
+            .invoke_async(&mut conn)
+            .await?;
+        parse_stats(&json)
+    }
+
+    /// Apply a batch of increments in one round trip, running [`INCREMENT_SCRIPT`] so each
+    /// page is read and written back without another write in between
+    #[instrument(name = "redis.increment_many", skip_all, fields(pages = increments.len()))]
+    pub async fn increment_many(&self, increments: &[Increment]) -> RedisResult<Vec<PageStats>> {
+        if increments.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.get_connection().await?;
+        let now = Utc::now();
+        let mut invocation = INCREMENT_SCRIPT.key(self.index_key());
+        invocation.arg(now.format("%H").to_string());
+        for increment in increments {
+            let page = &increment.page;
+            invocation
+                .key(self.generate_key(page))
+                .key(self.hourly_views_key(page, now.date_naive()))
+                .arg(page.to_string())
+                .arg(&page.slug)
+                .arg(page.kind.as_str())
+                .arg(increment.views)
+                .arg(increment.likes);
+        }
+
+        let updated: Vec<String> = invocation.invoke_async(&mut conn).await?;
+        updated.iter().map(|json| parse_stats(json)).collect()
     }
 
     /// Get all page stats (useful for analytics)
-    /// Returns a vector of all PageStats found in Redis
+    /// Reads the pages from the index set and fetches all of them with a single MGET
+    #[instrument(name = "redis.get_all_page_stats", skip(self))]
     pub async fn get_all_page_stats(&self) -> RedisResult<Vec<PageStats>> {
-        let mut conn = self.get_connection();
-        let pattern = format!("{}:post:*:page_stats", self.env_prefix);
+        let mut conn = self.get_connection().await?;
 
-        let mut all_stats = Vec::new();
-        let mut cursor = 0u64;
+        let members: Vec<String> = conn.smembers(self.index_key()).await?;
+        let mut pages: Vec<PageId> = members.iter().filter_map(|m| PageId::parse(m)).collect();
+        if pages.is_empty() {
+            return Ok(Vec::new());
+        }
+        pages.sort();
 
-        loop {
-            let (new_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
-                .cursor_arg(cursor)
-                .arg("MATCH")
-                .arg(&pattern)
-                .query_async(&mut conn)
-                .await?;
+        let keys: Vec<String> = pages.iter().map(|page| self.generate_key(page)).collect();
+        let values: Vec<Option<String>> =
+            redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
 
-            for key in keys {
-                if let Ok(Some(json_string)) = conn.get::<_, Option<String>>(&key).await {
-                    if let Ok(stats) = serde_json::from_str::<PageStats>(&json_string) {
-                        all_stats.push(stats);
-                    }
-                }
+        Ok(pages
+            .iter()
+            .zip(values)
+            .filter_map(|(page, json)| {
+                let stats = serde_json::from_str::<PageStats>(&json?).ok()?;
+                Some(PageStats {
+                    kind: page.kind,
+                    ..stats
+                })
+            })
+            .collect())
+    }
+
+    /// Check that Redis can be reached
+    pub async fn ping(&self) -> RedisResult<()> {
+        let mut conn = self.get_connection().await?;
+        redis::cmd("PING").query_async(&mut conn).await
+    }
+
+    /// Whether the index set exists, it is missing on stores written before it was introduced
+    pub async fn index_exists(&self) -> RedisResult<bool> {
+        let mut conn = self.get_connection().await?;
+        conn.exists(self.index_key()).await
+    }
+
+    /// Rebuild the index set from a SCAN of the stats keys. Pages are added before stale ones
+    /// are removed, and a page is only removed when its key is gone, so pages written during
+    /// the repair stay in the index.
+    #[instrument(name = "redis.repair_index", skip(self))]
+    pub async fn repair_index(&self) -> RedisResult<IndexRepair> {
+        let mut conn = self.get_connection().await?;
+        let index_key = self.index_key();
+
+        let found: HashSet<String> = self
+            .scan_keys(&format!("{}:*:page_stats", self.env_prefix))
+            .await?
+            .iter()
+            .filter_map(|key| Self::page_from_key(&self.env_prefix, key))
+            .map(|page| page.to_string())
+            .collect();
+
+        let indexed: HashSet<String> = conn.smembers(&index_key).await?;
+
+        let missing: Vec<&String> = found.difference(&indexed).collect();
+        if !missing.is_empty() {
+            conn.sadd::<_, _, ()>(&index_key, &missing).await?;
+        }
+
+        let mut removed = 0;
+        for member in indexed.difference(&found) {
+            let gone = match PageId::parse(member) {
+                Some(page) => !conn.exists::<_, bool>(self.generate_key(&page)).await?,
+                None => true,
+            };
+            if gone {
+                conn.srem::<_, _, ()>(&index_key, member).await?;
+                removed += 1;
+            }
+        }
+
+        conn.del::<_, ()>(self.legacy_index_key()).await?;
+
+        Ok(IndexRepair {
+            pages: found.len(),
+            added: missing.len(),
+            removed,
+        })
+    }
+
+    /// Move the stats, view history, country and client counts of a page to another page, e.g. to
+    /// give it another kind. Counts already at the target are added to. Returns false when there was nothing
+    /// to move.
+    #[instrument(name = "redis.move_page", skip(self), fields(from = %from, to = %to))]
+    pub async fn move_page(&self, from: &PageId, to: &PageId) -> RedisResult<bool> {
+        let mut conn = self.get_connection().await?;
+        let Some(moved) = self.get_page_stats(from).await? else {
+            return Ok(false);
+        };
+
+        let mut stats = self
+            .get_page_stats(to)
+            .await?
+            .unwrap_or_else(|| PageStats::new(to));
+        stats.reads += moved.reads;
+        stats.views += moved.views;
+        stats.likes += moved.likes;
+        if stats.content_hash.is_empty() {
+            stats.time = moved.time;
+            stats.content_hash = moved.content_hash;
+        }
+
+        let from_prefix = format!("{}:{}:{}:", self.env_prefix, from.kind, from.slug);
+        let to_prefix = format!("{}:{}:{}:", self.env_prefix, to.kind, to.slug);
+        // Every other key of the page is a hash of counts
+        let stats_key = self.generate_key(from);
+        let hashes: Vec<String> = self
+            .scan_keys(&format!("{}*", from_prefix))
+            .await?
+            .into_iter()
+            .filter(|key| *key != stats_key)
+            .collect();
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        self.add_to_pipe(&mut pipe, &stats)?;
+        for key in &hashes {
+            let counts: HashMap<String, u64> = conn.hgetall(key).await?;
+            let target = key.replacen(&from_prefix, &to_prefix, 1);
+            for (field, count) in counts {
+                pipe.hincr(&target, field, count).ignore();
             }
+            pipe.del(key).ignore();
+        }
+        pipe.del(stats_key)
+            .ignore()
+            .srem(self.index_key(), from.to_string())
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(true)
+    }
 
-            cursor = new_cursor;
-            if cursor == 0 {
-                break;
+    /// All hourly and daily view buckets, found with a SCAN
+    #[instrument(name = "redis.view_buckets", skip(self))]
+    pub async fn view_buckets(&self) -> RedisResult<Vec<ViewBucket>> {
+        let mut buckets: Vec<ViewBucket> = self
+            .scan_keys(&format!("{}:*:views:*", self.env_prefix))
+            .await?
+            .iter()
+            .filter_map(|key| Self::bucket_from_key(&self.env_prefix, key))
+            .collect();
+
+        buckets.sort();
+        buckets.dedup();
+        Ok(buckets)
+    }
+
+    /// Views of each page on each day, from the hourly buckets and the daily ones they were
+    /// rolled into. Days already rolled into the monthly bucket count as 0.
+    #[instrument(name = "redis.get_daily_views", skip_all, fields(pages = pages.len(), days = days.len()))]
+    pub async fn get_daily_views(
+        &self,
+        pages: &[PageId],
+        days: &[NaiveDate],
+    ) -> RedisResult<Vec<Vec<u64>>> {
+        if pages.is_empty() || days.is_empty() {
+            return Ok(vec![Vec::new(); pages.len()]);
+        }
+
+        let mut conn = self.get_connection().await?;
+        let mut hourly = redis::pipe();
+        let mut daily = redis::pipe();
+        for page in pages {
+            for day in days {
+                hourly.hvals(self.hourly_views_key(page, *day));
+                daily.hget(
+                    self.daily_views_key(page, *day),
+                    day.format("%d").to_string(),
+                );
             }
         }
 
-        Ok(all_stats)
+        let hourly: Vec<Vec<u64>> = hourly.query_async(&mut conn).await?;
+        let daily: Vec<Option<u64>> = daily.query_async(&mut conn).await?;
+        let views: Vec<u64> = hourly
+            .iter()
+            .zip(daily)
+            .map(|(hours, day)| hours.iter().sum::<u64>() + day.unwrap_or(0))
+            .collect();
+
+        Ok(views.chunks(days.len()).map(<[u64]>::to_vec).collect())
+    }
+
+    /// Total views in a bucket
+    pub async fn bucket_views(&self, bucket: &ViewBucket) -> RedisResult<u64> {
+        let mut conn = self.get_connection().await?;
+        let counts: HashMap<String, u64> = conn.hgetall(self.bucket_key(bucket)).await?;
+        Ok(counts.values().sum())
+    }
+
+    /// Add the views of a bucket to the next coarser one (hourly into daily, daily into
+    /// monthly) and delete it. Returns the views moved.
+    #[instrument(name = "redis.roll_up", skip(self))]
+    pub async fn roll_up(&self, bucket: &ViewBucket) -> RedisResult<u64> {
+        let mut conn = self.get_connection().await?;
+        let key = self.bucket_key(bucket);
+        let views = self.bucket_views(bucket).await?;
+
+        let (target, field) = match bucket {
+            ViewBucket::Hourly { page, day } => (
+                self.daily_views_key(page, *day),
+                day.format("%d").to_string(),
+            ),
+            ViewBucket::Daily { page, month } => (
+                self.monthly_views_key(page),
+                month.format("%Y-%m").to_string(),
+            ),
+        };
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        if views > 0 {
+            pipe.hincr(target, field, views).ignore();
+        }
+        pipe.del(key).ignore().query_async::<()>(&mut conn).await?;
+        Ok(views)
+    }
+
+    fn bucket_key(&self, bucket: &ViewBucket) -> String {
+        match bucket {
+            ViewBucket::Hourly { page, day } => self.hourly_views_key(page, *day),
+            ViewBucket::Daily { page, month } => self.daily_views_key(page, *month),
+        }
     }
 }
 
+/// Outcome of [`RedisPageStatsClient::repair_index`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexRepair {
+    /// Stats keys found by the SCAN
+    pub pages: usize,
+    /// Pages that were missing from the index
+    pub added: usize,
+    /// Pages in the index without stats
+    pub removed: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
     async fn test_page_stats_creation() {
-        let stats = PageStats::new("test_slug");
+        let stats = PageStats::new(&PageId::post("test_slug"));
         assert_eq!(stats.slug, "test_slug");
+        assert_eq!(stats.kind, ContentKind::Post);
         assert_eq!(stats.views, 0);
         assert_eq!(stats.reads, 0);
         assert_eq!(stats.likes, 0);
@@ -213,7 +942,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_page_stats_increments() {
-        let mut stats = PageStats::new("test_slug");
+        let mut stats = PageStats::new(&PageId::post("test_slug"));
 
         stats.increment_views();
         assert_eq!(stats.views, 1);
@@ -221,33 +950,105 @@ mod tests {
         stats.increment_likes();
         assert_eq!(stats.likes, 1);
 
-        stats.set_reading_time(30);
-        assert_eq!(stats.time, 30);
+        let reading = ReadingTime::of(&"word ".repeat(200));
+        assert!(stats.needs_reading_time(&reading));
+        stats.set_reading_time(&reading);
+        assert_eq!(stats.time, 60);
+        assert!(!stats.needs_reading_time(&reading));
 
-        // Test that reading time doesn't get overwritten
-        stats.set_reading_time(60);
-        assert_eq!(stats.time, 30); // Should still be 30
+        // An edited post gets its reading time recalculated
+        let edited = ReadingTime::of(&"word ".repeat(400));
+        assert!(stats.needs_reading_time(&edited));
+        stats.set_reading_time(&edited);
+        assert_eq!(stats.time, 120);
     }
 
     #[tokio::test]
     async fn test_key_generation() {
-        let key = RedisPageStatsClient::_generate_key_static("test", "my_blog_post");
+        let key = RedisPageStatsClient::_generate_key_static("test", &PageId::post("my_blog_post"));
         assert_eq!(key, "test:post:my_blog_post:page_stats");
+
+        let key = RedisPageStatsClient::_generate_key_static(
+            "test",
+            &PageId::new(ContentKind::Page, "about"),
+        );
+        assert_eq!(key, "test:page:about:page_stats");
+    }
+
+    #[test]
+    fn test_page_from_key() {
+        let page = |key| RedisPageStatsClient::page_from_key("test", key);
+
+        assert_eq!(
+            page("test:post:my_blog_post:page_stats"),
+            Some(PageId::post("my_blog_post"))
+        );
+        assert_eq!(
+            page("test:note:til:page_stats"),
+            Some(PageId::new(ContentKind::Note, "til"))
+        );
+        assert_eq!(page("prod:post:my_blog_post:page_stats"), None);
+        assert_eq!(page("test:post:my_blog_post:other"), None);
+        assert_eq!(page("test:team:post:my_blog_post:page_stats"), None);
+    }
+
+    #[test]
+    fn test_index_members() {
+        let page = PageId::new(ContentKind::Page, "home");
+        assert_eq!(page.to_string(), "page:home");
+        assert_eq!(PageId::parse("page:home"), Some(page));
+
+        // Members of the index used before kinds existed are bare slugs
+        assert_eq!(PageId::parse("home"), None);
+        assert_eq!(PageId::parse("blog:home"), None);
+    }
+
+    #[test]
+    fn test_bucket_from_key() {
+        let bucket = |key| RedisPageStatsClient::bucket_from_key("test", key);
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(
+            bucket("test:post:home:views:hourly:2024-02-29"),
+            Some(ViewBucket::Hourly {
+                page: PageId::post("home"),
+                day: date(2024, 2, 29)
+            })
+        );
+        assert_eq!(
+            bucket("test:page:home:views:daily:2024-02"),
+            Some(ViewBucket::Daily {
+                page: PageId::new(ContentKind::Page, "home"),
+                month: date(2024, 2, 1)
+            })
+        );
+        assert_eq!(bucket("test:post:home:views:monthly"), None);
+        assert_eq!(bucket("test:post:home:views:hourly:2024-02-30"), None);
+        assert_eq!(bucket("test:site:post:home:views:daily:2024-02"), None);
+        assert_eq!(bucket("test:post:home:page_stats"), None);
     }
 
     #[test]
     fn test_json_serialization() {
         let stats = PageStats {
             slug: "240125_rust_on_esp32_2_hardware".to_string(),
+            kind: ContentKind::Post,
             reads: 0,
             views: 2,
             likes: 0,
             time: 6,
+            content_hash: "af63dc4c8601ec8c".to_string(),
         };
 
         let json = serde_json::to_string(&stats).unwrap();
         let deserialized: PageStats = serde_json::from_str(&json).unwrap();
 
         assert_eq!(stats, deserialized);
+
+        // Stats stored before kinds existed
+        let legacy: PageStats =
+            serde_json::from_str(r#"{"slug":"about","reads":0,"views":3,"likes":1,"time":0}"#)
+                .unwrap();
+        assert_eq!(legacy.kind, ContentKind::Post);
     }
 }
@@ -0,0 +1,689 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::events::{Event, EventCount};
+use crate::experiments::{ExperimentAction, VariantCounts};
+use crate::reading_times::ReadingTime;
+use crate::redis_client::{PageId, PageStats};
+use crate::store::{PageStatsStore, StoreError, StoreResult};
+use crate::user_agent::{Client, ClientBreakdown};
+
+/// Pages kept in the last-known cache, further pages are served from the store only
+const MAX_CACHED_PAGES: usize = 10_000;
+
+/// Tuning of the [`ResilientStore`]
+#[derive(Debug, Clone)]
+pub struct ResilienceConfig {
+    /// Maximum time a single store call may take
+    pub timeout: Duration,
+    /// Consecutive failures after which the circuit opens
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial call is let through
+    pub cooldown: Duration,
+    /// Increments buffered while the store is down, newer ones are dropped
+    pub max_pending_writes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls go to the store
+    Closed,
+    /// The store is failing, calls fail fast
+    Open,
+    /// Cooldown passed, a trial call decides whether to close again
+    HalfOpen,
+}
+
+struct CircuitBreaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    changed_at: Instant,
+}
+
+impl CircuitBreaker {
+    fn new() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            changed_at: Instant::now(),
+        }
+    }
+
+    /// Whether a call may go to the store. An open circuit lets one trial call through
+    /// per cooldown, so a trial that never finishes doesn't keep it half-open forever.
+    fn allow(&mut self, cooldown: Duration) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::Open | CircuitState::HalfOpen => {
+                if self.changed_at.elapsed() >= cooldown {
+                    self.state = CircuitState::HalfOpen;
+                    self.changed_at = Instant::now();
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn open(&mut self) {
+        self.state = CircuitState::Open;
+        self.changed_at = Instant::now();
+    }
+
+    fn record_success(&mut self) {
+        if self.state != CircuitState::Closed {
+            info!("Stats store recovered, closing circuit");
+        }
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+    }
+
+    fn record_failure(&mut self, threshold: u32) {
+        self.consecutive_failures += 1;
+        let trip = self.state == CircuitState::HalfOpen || self.consecutive_failures >= threshold;
+        if trip && self.state != CircuitState::Open {
+            warn!(
+                "Stats store failed {} times in a row, opening circuit",
+                self.consecutive_failures
+            );
+            self.open();
+        }
+    }
+}
+
+/// A write that couldn't reach the store yet
+#[derive(Debug, Clone, PartialEq)]
+enum PendingWrite {
+    View(PageId),
+    Like(PageId),
+    ReadingTime(PageId, ReadingTime),
+}
+
+impl PendingWrite {
+    fn page(&self) -> &PageId {
+        match self {
+            PendingWrite::View(page)
+            | PendingWrite::Like(page)
+            | PendingWrite::ReadingTime(page, _) => page,
+        }
+    }
+
+    /// Whether sending the write twice has the same effect as sending it once
+    fn is_idempotent(&self) -> bool {
+        matches!(self, PendingWrite::ReadingTime(..))
+    }
+
+    fn apply_to(&self, stats: &mut PageStats) {
+        match self {
+            PendingWrite::View(_) => stats.increment_views(),
+            PendingWrite::Like(_) => stats.increment_likes(),
+            PendingWrite::ReadingTime(_, reading) => stats.set_reading_time(reading),
+        }
+    }
+}
+
+/// State of the store as reported by the readiness endpoint
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoreHealth {
+    pub circuit: CircuitState,
+    /// Writes waiting to be replayed
+    pub pending_writes: usize,
+    /// Writes lost because the pending queue was full
+    pub dropped_writes: u64,
+    pub cached_pages: usize,
+}
+
+impl StoreHealth {
+    /// Served from the cache and buffering writes instead of using the store directly
+    pub fn is_degraded(&self) -> bool {
+        self.circuit != CircuitState::Closed || self.pending_writes > 0
+    }
+}
+
+/// Wraps a store with timeouts and a circuit breaker. While the store is down, reads
+/// are answered from the last-known stats and increments are buffered, to be replayed
+/// by [`ResilientStore::spawn_replay`] once the store is back.
+pub struct ResilientStore<S> {
+    inner: S,
+    config: ResilienceConfig,
+    breaker: Mutex<CircuitBreaker>,
+    pending: Mutex<VecDeque<PendingWrite>>,
+    cache: Mutex<HashMap<PageId, PageStats>>,
+    dropped_writes: AtomicU64,
+}
+
+impl<S: PageStatsStore> ResilientStore<S> {
+    pub fn new(inner: S, config: ResilienceConfig) -> Self {
+        Self {
+            inner,
+            config,
+            breaker: Mutex::new(CircuitBreaker::new()),
+            pending: Mutex::new(VecDeque::new()),
+            cache: Mutex::new(HashMap::new()),
+            dropped_writes: AtomicU64::new(0),
+        }
+    }
+
+    /// The wrapped store
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Fail fast until the next trial call, e.g. when the store was unreachable at startup
+    pub fn open_circuit(&self) {
+        self.breaker.lock().expect("breaker lock poisoned").open();
+    }
+
+    pub fn health(&self) -> StoreHealth {
+        StoreHealth {
+            circuit: self.breaker.lock().expect("breaker lock poisoned").state,
+            pending_writes: self.pending.lock().expect("pending lock poisoned").len(),
+            dropped_writes: self.dropped_writes.load(Ordering::Relaxed),
+            cached_pages: self.cache.lock().expect("cache lock poisoned").len(),
+        }
+    }
+
+    /// Run a store call through the circuit breaker and timeout
+    async fn call<T>(&self, operation: impl Future<Output = StoreResult<T>>) -> StoreResult<T> {
+        if !self
+            .breaker
+            .lock()
+            .expect("breaker lock poisoned")
+            .allow(self.config.cooldown)
+        {
+            return Err(StoreError::CircuitOpen);
+        }
+
+        let result = match tokio::time::timeout(self.config.timeout, operation).await {
+            Ok(result) => result,
+            Err(_) => Err(StoreError::Timeout),
+        };
+
+        let mut breaker = self.breaker.lock().expect("breaker lock poisoned");
+        match &result {
+            Ok(_) => breaker.record_success(),
+            Err(_) => breaker.record_failure(self.config.failure_threshold),
+        }
+        result
+    }
+
+    fn remember(&self, stats: &PageStats) {
+        let mut cache = self.cache.lock().expect("cache lock poisoned");
+        let page = stats.id();
+        if cache.len() < MAX_CACHED_PAGES || cache.contains_key(&page) {
+            cache.insert(page, stats.clone());
+        }
+    }
+
+    /// Last-known stats of a page with its pending writes applied, None if nothing is known
+    fn last_known(&self, page: &PageId) -> Option<PageStats> {
+        let cached = self
+            .cache
+            .lock()
+            .expect("cache lock poisoned")
+            .get(page)
+            .cloned();
+        let pending = self.pending.lock().expect("pending lock poisoned");
+        let mut writes = pending
+            .iter()
+            .filter(|write| write.page() == page)
+            .peekable();
+
+        if cached.is_none() && writes.peek().is_none() {
+            return None;
+        }
+        let mut stats = cached.unwrap_or_else(|| PageStats::new(page));
+        writes.for_each(|write| write.apply_to(&mut stats));
+        Some(stats)
+    }
+
+    /// Buffer a write that failed, answering with the stats it will lead to. A view or like
+    /// that may have reached the store (e.g. it timed out) isn't buffered, as replaying it
+    /// could count it twice.
+    fn defer(&self, write: PendingWrite, error: StoreError) -> StoreResult<PageStats> {
+        if !error.is_unsent() && !write.is_idempotent() {
+            warn!(
+                "Not replaying {:?}, the store may have applied it: {}",
+                write, error
+            );
+            return Err(error);
+        }
+
+        {
+            let mut pending = self.pending.lock().expect("pending lock poisoned");
+            if pending.len() >= self.config.max_pending_writes {
+                self.dropped_writes.fetch_add(1, Ordering::Relaxed);
+                warn!("Pending write queue is full, dropping {:?}", write);
+                return Err(error);
+            }
+            pending.push_back(write.clone());
+        }
+
+        Ok(self
+            .last_known(write.page())
+            .unwrap_or_else(|| PageStats::new(write.page())))
+    }
+
+    async fn write(&self, write: PendingWrite) -> StoreResult<PageStats> {
+        let result = self
+            .call(async {
+                match &write {
+                    PendingWrite::View(page) => self.inner.increment_views(page).await,
+                    PendingWrite::Like(page) => self.inner.increment_likes(page).await,
+                    PendingWrite::ReadingTime(page, reading) => {
+                        self.inner.set_reading_time(page, reading).await
+                    }
+                }
+            })
+            .await;
+
+        match result {
+            Ok(stats) => {
+                self.remember(&stats);
+                Ok(stats)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Send the buffered writes to the store in order, stopping at the first one that didn't
+    /// reach it. Views and likes that may have reached it are dropped instead of retried.
+    pub async fn replay_pending(&self) -> usize {
+        let mut replayed = 0;
+
+        loop {
+            let next = self
+                .pending
+                .lock()
+                .expect("pending lock poisoned")
+                .front()
+                .cloned();
+            let Some(write) = next else {
+                break;
+            };
+
+            match self.write(write.clone()).await {
+                Ok(_) => replayed += 1,
+                Err(e) if e.is_unsent() || write.is_idempotent() => break,
+                // Sending it again could count it twice
+                Err(e) => warn!("Dropping {:?}, the store may have applied it: {}", write, e),
+            }
+            self.pending
+                .lock()
+                .expect("pending lock poisoned")
+                .pop_front();
+        }
+
+        replayed
+    }
+
+    /// Periodically replay the buffered writes once the store is reachable again
+    pub fn spawn_replay(self: &Arc<Self>, interval: Duration) {
+        let store = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if store
+                    .pending
+                    .lock()
+                    .expect("pending lock poisoned")
+                    .is_empty()
+                {
+                    continue;
+                }
+
+                let replayed = store.replay_pending().await;
+                if replayed > 0 {
+                    info!("Replayed {} pending writes to the stats store", replayed);
+                }
+            }
+        });
+    }
+}
+
+impl<S: PageStatsStore> PageStatsStore for ResilientStore<S> {
+    async fn get_page_stats(&self, page: &PageId) -> StoreResult<Option<PageStats>> {
+        match self.call(self.inner.get_page_stats(page)).await {
+            Ok(stats) => {
+                if let Some(stats) = &stats {
+                    self.remember(stats);
+                }
+                Ok(stats)
+            }
+            Err(e) => match self.last_known(page) {
+                Some(stats) => {
+                    warn!("Serving cached stats for {}: {}", page, e);
+                    Ok(Some(stats))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn increment_views(&self, page: &PageId) -> StoreResult<PageStats> {
+        let write = PendingWrite::View(page.clone());
+        match self.write(write.clone()).await {
+            Ok(stats) => Ok(stats),
+            Err(e) => self.defer(write, e),
+        }
+    }
+
+    async fn increment_likes(&self, page: &PageId) -> StoreResult<PageStats> {
+        let write = PendingWrite::Like(page.clone());
+        match self.write(write.clone()).await {
+            Ok(stats) => Ok(stats),
+            Err(e) => self.defer(write, e),
+        }
+    }
+
+    async fn set_reading_time(
+        &self,
+        page: &PageId,
+        reading: &ReadingTime,
+    ) -> StoreResult<PageStats> {
+        let write = PendingWrite::ReadingTime(page.clone(), reading.clone());
+        match self.write(write.clone()).await {
+            Ok(stats) => Ok(stats),
+            Err(e) => self.defer(write, e),
+        }
+    }
+
+    async fn get_all_page_stats(&self) -> StoreResult<Vec<PageStats>> {
+        match self.call(self.inner.get_all_page_stats()).await {
+            Ok(all_stats) => {
+                all_stats.iter().for_each(|stats| self.remember(stats));
+                Ok(all_stats)
+            }
+            Err(e) => {
+                let mut pages: Vec<PageId> = self
+                    .cache
+                    .lock()
+                    .expect("cache lock poisoned")
+                    .keys()
+                    .cloned()
+                    .collect();
+                pages.extend(
+                    self.pending
+                        .lock()
+                        .expect("pending lock poisoned")
+                        .iter()
+                        .map(|write| write.page().clone()),
+                );
+                if pages.is_empty() {
+                    return Err(e);
+                }
+                pages.sort();
+                pages.dedup();
+
+                warn!("Serving cached stats for all pages: {}", e);
+                Ok(pages
+                    .iter()
+                    .filter_map(|page| self.last_known(page))
+                    .collect())
+            }
+        }
+    }
+
+    /// Not cached, the history is only read by the dashboard
+    async fn get_daily_views(
+        &self,
+        pages: &[PageId],
+        days: &[NaiveDate],
+    ) -> StoreResult<Vec<Vec<u64>>> {
+        self.call(self.inner.get_daily_views(pages, days)).await
+    }
+
+    /// Not buffered, views counted while the store is down have no country
+    async fn increment_country(&self, page: &PageId, country: &str) -> StoreResult<()> {
+        self.call(self.inner.increment_country(page, country)).await
+    }
+
+    async fn get_countries(&self, page: &PageId) -> StoreResult<BTreeMap<String, u64>> {
+        self.call(self.inner.get_countries(page)).await
+    }
+
+    /// Not buffered either, events while the store is down are lost
+    async fn increment_event(&self, page: &PageId, event: &Event) -> StoreResult<()> {
+        self.call(self.inner.increment_event(page, event)).await
+    }
+
+    async fn get_events(&self, page: &PageId) -> StoreResult<BTreeMap<String, EventCount>> {
+        self.call(self.inner.get_events(page)).await
+    }
+
+    /// Not buffered either, experiments miss the impressions and clicks of an outage
+    async fn increment_experiment(
+        &self,
+        page: &PageId,
+        variant: usize,
+        action: ExperimentAction,
+    ) -> StoreResult<()> {
+        self.call(self.inner.increment_experiment(page, variant, action))
+            .await
+    }
+
+    async fn get_experiments(
+        &self,
+        pages: &[PageId],
+    ) -> StoreResult<Vec<BTreeMap<usize, VariantCounts>>> {
+        self.call(self.inner.get_experiments(pages)).await
+    }
+
+    /// Not buffered either, views counted while the store is down have no client
+    async fn increment_client(&self, page: &PageId, client: &Client) -> StoreResult<()> {
+        self.call(self.inner.increment_client(page, client)).await
+    }
+
+    async fn get_clients(&self, pages: &[PageId]) -> StoreResult<Vec<ClientBreakdown>> {
+        self.call(self.inner.get_clients(pages)).await
+    }
+
+    async fn get_site_clients(&self) -> StoreResult<ClientBreakdown> {
+        self.call(self.inner.get_site_clients()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::testing::FakeStore;
+
+    fn config() -> ResilienceConfig {
+        ResilienceConfig {
+            timeout: Duration::from_millis(50),
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(60),
+            max_pending_writes: 3,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_fails_fast() {
+        let store = ResilientStore::new(FakeStore::default(), config());
+        store.inner.down.store(true, Ordering::SeqCst);
+
+        assert!(store.get_page_stats(&PageId::post("a")).await.is_err());
+        assert!(store.get_page_stats(&PageId::post("a")).await.is_err());
+        assert_eq!(store.health().circuit, CircuitState::Open);
+
+        assert!(matches!(
+            store.get_page_stats(&PageId::post("a")).await,
+            Err(StoreError::CircuitOpen)
+        ));
+        assert_eq!(store.inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_buffers_writes_until_trial() {
+        let store = ResilientStore::new(FakeStore::default(), config());
+        store.open_circuit();
+
+        assert!(store.health().is_degraded());
+        assert_eq!(
+            store
+                .increment_views(&PageId::post("a"))
+                .await
+                .unwrap()
+                .views,
+            1
+        );
+        assert_eq!(store.health().pending_writes, 1);
+        assert_eq!(store.inner.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_store_times_out() {
+        let inner = FakeStore {
+            delay: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let store = ResilientStore::new(inner, config());
+
+        assert!(matches!(
+            store.get_page_stats(&PageId::post("a")).await,
+            Err(StoreError::Timeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_increment_is_not_replayed() {
+        let inner = FakeStore {
+            answer_delay: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let store = ResilientStore::new(inner, config());
+
+        // The store applies the view but answers after the timeout
+        assert!(matches!(
+            store.increment_views(&PageId::post("a")).await,
+            Err(StoreError::Timeout)
+        ));
+        assert_eq!(store.health().pending_writes, 0);
+        assert_eq!(store.replay_pending().await, 0);
+        assert_eq!(store.inner.views("a"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reads_served_from_cache_during_outage() {
+        let store = ResilientStore::new(FakeStore::default(), config());
+        store.increment_likes(&PageId::post("a")).await.unwrap();
+
+        store.inner.down.store(true, Ordering::SeqCst);
+        let stats = store
+            .get_page_stats(&PageId::post("a"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.likes, 1);
+        assert_eq!(store.get_all_page_stats().await.unwrap(), vec![stats]);
+
+        // Pages never seen before have nothing to fall back on
+        assert!(store.get_page_stats(&PageId::post("b")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_increments_replayed_after_outage() {
+        let config = ResilienceConfig {
+            cooldown: Duration::ZERO,
+            ..config()
+        };
+        let store = ResilientStore::new(FakeStore::default(), config);
+        store.increment_views(&PageId::post("a")).await.unwrap();
+
+        store.inner.down.store(true, Ordering::SeqCst);
+        assert_eq!(
+            store
+                .increment_views(&PageId::post("a"))
+                .await
+                .unwrap()
+                .views,
+            2
+        );
+        assert_eq!(
+            store
+                .increment_views(&PageId::post("a"))
+                .await
+                .unwrap()
+                .views,
+            3
+        );
+        assert_eq!(
+            store
+                .set_reading_time(&PageId::post("a"), &ReadingTime::of(&"word ".repeat(200)))
+                .await
+                .unwrap()
+                .time,
+            60
+        );
+        assert!(store.health().is_degraded());
+        assert_eq!(store.health().pending_writes, 3);
+
+        store.inner.down.store(false, Ordering::SeqCst);
+        assert_eq!(store.replay_pending().await, 3);
+
+        let stats = store.inner.stats.lock().unwrap()[&PageId::post("a")].clone();
+        assert_eq!((stats.views, stats.time), (3, 60));
+        assert!(!store.health().is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_reading_time_recomputed_after_edit_during_outage() {
+        let config = ResilienceConfig {
+            cooldown: Duration::ZERO,
+            ..config()
+        };
+        let store = ResilientStore::new(FakeStore::default(), config);
+        let page = PageId::post("a");
+        let before = ReadingTime::of(&"word ".repeat(200));
+        let after = ReadingTime::of(&"word ".repeat(400));
+        store.set_reading_time(&page, &before).await.unwrap();
+
+        store.inner.down.store(true, Ordering::SeqCst);
+        let stats = store.increment_views(&page).await.unwrap();
+        assert!(!stats.needs_reading_time(&before));
+
+        // The post is edited while the view is buffered, its stats have the old hash
+        assert!(stats.needs_reading_time(&after));
+        let stats = store.set_reading_time(&page, &after).await.unwrap();
+        assert_eq!((stats.views, stats.time), (1, after.seconds));
+        assert!(!stats.needs_reading_time(&after));
+        assert!(!store
+            .get_page_stats(&page)
+            .await
+            .unwrap()
+            .unwrap()
+            .needs_reading_time(&after));
+
+        store.inner.down.store(false, Ordering::SeqCst);
+        assert_eq!(store.replay_pending().await, 2);
+
+        let stats = store.inner.stats.lock().unwrap()[&page].clone();
+        assert_eq!((stats.views, stats.time), (1, after.seconds));
+        assert_eq!(stats.content_hash, after.content_hash);
+    }
+
+    #[tokio::test]
+    async fn test_pending_queue_is_bounded() {
+        let store = ResilientStore::new(FakeStore::default(), config());
+        store.inner.down.store(true, Ordering::SeqCst);
+
+        for _ in 0..3 {
+            assert!(store.increment_views(&PageId::post("a")).await.is_ok());
+        }
+        assert!(store.increment_views(&PageId::post("a")).await.is_err());
+
+        let health = store.health();
+        assert_eq!(health.pending_writes, 3);
+        assert_eq!(health.dropped_writes, 1);
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::redis_client::{RedisPageStatsClient, ViewBucket};
+
+/// How long view buckets are kept at each granularity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetentionPolicy {
+    /// Days of hourly buckets kept before today's, older days are rolled into daily buckets
+    pub hourly_days: u32,
+    /// Months of daily buckets kept before the current one, older months are rolled into
+    /// the monthly bucket
+    pub daily_months: u32,
+}
+
+impl RetentionPolicy {
+    /// Whether a bucket is past its retention on `today` and should be rolled up
+    pub fn is_expired(&self, bucket: &ViewBucket, today: NaiveDate) -> bool {
+        match bucket {
+            ViewBucket::Hourly { day, .. } => today
+                .checked_sub_days(Days::new(self.hourly_days.into()))
+                .is_some_and(|cutoff| *day < cutoff),
+            ViewBucket::Daily { month, .. } => today
+                .with_day(1)
+                .and_then(|this_month| {
+                    this_month.checked_sub_months(Months::new(self.daily_months))
+                })
+                .is_some_and(|cutoff| *month < cutoff),
+        }
+    }
+}
+
+/// Buckets to roll up on `today`, hourly ones first so the days they add to a daily
+/// bucket are included when that month is rolled up on a later run
+pub fn plan(
+    buckets: Vec<ViewBucket>,
+    policy: RetentionPolicy,
+    today: NaiveDate,
+) -> Vec<ViewBucket> {
+    let mut expired: Vec<ViewBucket> = buckets
+        .into_iter()
+        .filter(|bucket| policy.is_expired(bucket, today))
+        .collect();
+    expired.sort_by_key(|bucket| matches!(bucket, ViewBucket::Daily { .. }));
+    expired
+}
+
+/// A bucket that was, or in a dry run would be, rolled up
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rollup {
+    pub bucket: ViewBucket,
+    pub views: u64,
+}
+
+/// Roll up the expired buckets of one store. A dry run only reads them.
+pub async fn compact(
+    client: &RedisPageStatsClient,
+    policy: RetentionPolicy,
+    today: NaiveDate,
+    dry_run: bool,
+) -> redis::RedisResult<Vec<Rollup>> {
+    let buckets = plan(client.view_buckets().await?, policy, today);
+
+    let mut rollups = Vec::with_capacity(buckets.len());
+    for bucket in buckets {
+        let views = if dry_run {
+            client.bucket_views(&bucket).await?
+        } else {
+            client.roll_up(&bucket).await?
+        };
+        rollups.push(Rollup { bucket, views });
+    }
+    Ok(rollups)
+}
+
+/// Compact a store every `interval`, starting right away
+pub fn spawn_compaction(
+    name: String,
+    client: RedisPageStatsClient,
+    policy: RetentionPolicy,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match compact(&client, policy, Utc::now().date_naive(), false).await {
+                Ok(rollups) if rollups.is_empty() => {}
+                Ok(rollups) => info!("Rolled up {} view buckets of {}", rollups.len(), name),
+                Err(e) => warn!("Failed to roll up view buckets of {}: {}", name, e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis_client::PageId;
+
+    const POLICY: RetentionPolicy = RetentionPolicy {
+        hourly_days: 7,
+        daily_months: 3,
+    };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn hourly(day: NaiveDate) -> ViewBucket {
+        ViewBucket::Hourly {
+            page: PageId::post("home"),
+            day,
+        }
+    }
+
+    fn daily(month: NaiveDate) -> ViewBucket {
+        ViewBucket::Daily {
+            page: PageId::post("home"),
+            month,
+        }
+    }
+
+    #[test]
+    fn test_hourly_buckets_expire_after_the_policy_days() {
+        let today = date(2024, 3, 10);
+
+        assert!(!POLICY.is_expired(&hourly(today), today));
+        assert!(!POLICY.is_expired(&hourly(date(2024, 3, 3)), today));
+        assert!(POLICY.is_expired(&hourly(date(2024, 3, 2)), today));
+        assert!(POLICY.is_expired(&hourly(date(2023, 12, 31)), today));
+    }
+
+    #[test]
+    fn test_daily_buckets_expire_after_the_policy_months() {
+        let today = date(2024, 3, 31);
+
+        assert!(!POLICY.is_expired(&daily(date(2024, 3, 1)), today));
+        assert!(!POLICY.is_expired(&daily(date(2023, 12, 1)), today));
+        assert!(POLICY.is_expired(&daily(date(2023, 11, 1)), today));
+    }
+
+    #[test]
+    fn test_plan_rolls_up_hourly_buckets_first() {
+        let today = date(2024, 3, 10);
+        let buckets = vec![
+            daily(date(2023, 1, 1)),
+            daily(date(2024, 3, 1)),
+            hourly(date(2024, 1, 5)),
+            hourly(today),
+        ];
+
+        assert_eq!(
+            plan(buckets, POLICY, today),
+            vec![hourly(date(2024, 1, 5)), daily(date(2023, 1, 1))]
+        );
+    }
+}
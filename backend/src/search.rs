@@ -0,0 +1,299 @@
+use crate::content::Post;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 document length normalisation
+const B: f64 = 0.75;
+
+/// Field weights, a match in the title counts as three matches in the body
+const TITLE_WEIGHT: u32 = 3;
+const TAGS_WEIGHT: u32 = 2;
+const SUMMARY_WEIGHT: u32 = 2;
+const BODY_WEIGHT: u32 = 1;
+
+/// Characters of body text shown around the first match
+const SNIPPET_CONTEXT: usize = 80;
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct SearchResult {
+    pub slug: String,
+    pub title: String,
+    pub score: f64,
+    /// HTML-escaped text with matched terms wrapped in `<mark>`
+    pub snippet: String,
+}
+
+struct Document {
+    slug: String,
+    title: String,
+    summary: String,
+    text: String, // Body with markdown stripped
+    length: f64,
+}
+
+/// In-memory inverted index over the published posts
+#[derive(Default)]
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    /// term -> (document index, weighted term frequency)
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    average_length: f64,
+}
+
+/// Lowercase alphanumeric words
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+/// Render markdown to plain text, dropping markup, html and embedded components
+fn strip_markdown(markdown: &str) -> String {
+    let mut text = String::new();
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item) => text.push(' '),
+            Event::Start(Tag::CodeBlock(_)) | Event::End(TagEnd::CodeBlock) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+impl SearchIndex {
+    /// Build the index from posts, drafts (`published: false`) are left out
+    pub fn build(posts: &[Post]) -> Self {
+        let mut index = SearchIndex::default();
+
+        for post in posts.iter().filter(|post| post.frontmatter.published) {
+            let doc_id = index.documents.len();
+            let text = strip_markdown(&post.body);
+            let fields = [
+                (post.frontmatter.title.as_str(), TITLE_WEIGHT),
+                (post.frontmatter.summary.as_str(), SUMMARY_WEIGHT),
+                (&post.frontmatter.tags.join(" "), TAGS_WEIGHT),
+                (text.as_str(), BODY_WEIGHT),
+            ];
+
+            let mut frequencies: HashMap<String, u32> = HashMap::new();
+            let mut length = 0;
+            for (field, weight) in fields {
+                for term in tokenize(field) {
+                    *frequencies.entry(term).or_default() += weight;
+                    length += weight;
+                }
+            }
+
+            for (term, frequency) in frequencies {
+                index
+                    .postings
+                    .entry(term)
+                    .or_default()
+                    .push((doc_id, frequency));
+            }
+
+            index.documents.push(Document {
+                slug: post.slug.clone(),
+                title: post.frontmatter.title.clone(),
+                summary: post.frontmatter.summary.clone(),
+                text,
+                length: length as f64,
+            });
+        }
+
+        if !index.documents.is_empty() {
+            index.average_length = index.documents.iter().map(|doc| doc.length).sum::<f64>()
+                / index.documents.len() as f64;
+        }
+
+        index
+    }
+
+    /// Number of indexed posts
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Rank posts for a query using BM25, best match first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let terms: HashSet<String> = tokenize(query).collect();
+        let total = self.documents.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+
+            let df = postings.len() as f64;
+            let idf = (1.0 + (total - df + 0.5) / (df + 0.5)).ln();
+
+            for &(doc_id, frequency) in postings {
+                let tf = frequency as f64;
+                let norm = 1.0 - B + B * self.documents[doc_id].length / self.average_length;
+                *scores.entry(doc_id).or_default() += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| self.documents[a.0].slug.cmp(&self.documents[b.0].slug))
+        });
+
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(doc_id, score)| {
+                let doc = &self.documents[doc_id];
+                SearchResult {
+                    slug: doc.slug.clone(),
+                    title: doc.title.clone(),
+                    score,
+                    snippet: snippet(doc, &terms),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Excerpt around the first matching word of the body (or the summary when the
+/// body doesn't match), with every matching word highlighted
+fn snippet(doc: &Document, terms: &HashSet<String>) -> String {
+    let is_match = |word: &str| terms.contains(&word.to_lowercase());
+
+    let source = [doc.text.as_str(), doc.summary.as_str()]
+        .into_iter()
+        .find(|text| tokenize(text).any(|word| terms.contains(&word)))
+        .unwrap_or(&doc.summary);
+
+    // Byte ranges of all words in the source text
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in source.char_indices().chain([(source.len(), ' ')]) {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let first_match = words
+        .iter()
+        .find(|(s, e)| is_match(&source[*s..*e]))
+        .map(|(s, _)| *s)
+        .unwrap_or(0);
+
+    // Align the window on char boundaries
+    let mut from = first_match.saturating_sub(SNIPPET_CONTEXT);
+    while !source.is_char_boundary(from) {
+        from -= 1;
+    }
+    let mut to = (first_match + 2 * SNIPPET_CONTEXT).min(source.len());
+    while !source.is_char_boundary(to) {
+        to += 1;
+    }
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = from;
+    for &(s, e) in words.iter().filter(|(s, e)| *s >= from && *e <= to) {
+        if is_match(&source[s..e]) {
+            snippet.push_str(&escape_html(&source[cursor..s]));
+            snippet.push_str("<mark>");
+            snippet.push_str(&escape_html(&source[s..e]));
+            snippet.push_str("</mark>");
+            cursor = e;
+        }
+    }
+    snippet.push_str(&escape_html(&source[cursor..to]));
+    if to < source.len() {
+        snippet.push('…');
+    }
+
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::parse_post;
+
+    fn posts() -> Vec<Post> {
+        vec![
+            parse_post(
+                "rust_post",
+                "---\ntitle: Rust on the ESP32\ntags: rust, embedded\nsummary: Microcontrollers\npublished: true\n---\nRunning **Rust** code on a tiny chip.",
+            ),
+            parse_post(
+                "elixir_post",
+                "---\ntitle: Recursion in Elixir\ntags: elixir\nsummary: Tail calls\npublished: true\n---\nA short mention of rust here, mostly elixir.",
+            ),
+            parse_post(
+                "draft_post",
+                "---\ntitle: Secret Rust draft\npublished: false\n---\nRust rust rust.",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        let text = strip_markdown("# Title\n\nSome **bold** and `code`.\n\n<Image path=\"x\" />");
+        assert_eq!(text, "Title Some bold and code.");
+    }
+
+    #[test]
+    fn test_search_ranks_title_matches_first() {
+        let index = SearchIndex::build(&posts());
+        let results = index.search("rust", 10);
+
+        let slugs: Vec<&str> = results.iter().map(|r| r.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["rust_post", "elixir_post"]);
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_search_excludes_drafts() {
+        let index = SearchIndex::build(&posts());
+        assert_eq!(index.document_count(), 2);
+        assert!(index.search("secret", 10).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_highlights_terms() {
+        let index = SearchIndex::build(&posts());
+        let results = index.search("Rust chip", 10);
+
+        assert_eq!(
+            results[0].snippet,
+            "Running <mark>Rust</mark> code on a tiny <mark>chip</mark>."
+        );
+    }
+
+    #[test]
+    fn test_empty_query() {
+        let index = SearchIndex::build(&posts());
+        assert!(index.search("   ", 10).is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::convert::Infallible;
+use std::path::{Component, Path, PathBuf};
+use tower::{service_fn, ServiceExt};
+use tower_http::compression::CompressionLayer;
+use tower_http::services::{ServeDir, ServeFile};
+
+/// Markdown under /content/ is fetched at runtime and must always be revalidated
+const CONTENT_CACHE_CONTROL: &str = "no-cache, must-revalidate";
+/// Trunk puts a content hash in asset file names, so they never change
+const HASHED_ASSET_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// Everything else (index.html, sidecars, /static/) is revalidated using the ETag
+const DEFAULT_CACHE_CONTROL: &str = "no-cache";
+
+/// Build a router serving the Trunk `dist/` directory
+///
+/// Replaces the nginx setup:
+/// - `/content/` is served as-is and never falls back to the SPA
+/// - real files are served first, then pages pre-rendered by `meta-gen` (`<path>/index.html`),
+///   then the SPA shell (`app.html`, or `index.html` without meta-gen) so the SPA router can
+///   take over
+/// - responses get an ETag, cache headers and gzip/brotli compression
+pub fn router(dist_dir: &Path) -> Router {
+    let fallback_dist_dir = dist_dir.to_path_buf();
+    let spa_fallback = service_fn(move |req: Request| {
+        let dist_dir = fallback_dist_dir.clone();
+        async move {
+            if req.uri().path().starts_with("/api/") {
+                return Ok::<_, Infallible>(StatusCode::NOT_FOUND.into_response());
+            }
+
+            let file = resolve_fallback(&dist_dir, req.uri().path());
+            let response = ServeFile::new(file).oneshot(req).await?;
+            Ok(response.into_response())
+        }
+    });
+
+    let static_files = ServeDir::new(dist_dir)
+        .append_index_html_on_directories(false)
+        .fallback(spa_fallback);
+
+    Router::new()
+        .nest_service("/content", ServeDir::new(dist_dir.join("content")))
+        .fallback_service(static_files)
+        .layer(middleware::from_fn(cache_headers))
+        .layer(CompressionLayer::new())
+}
+
+/// Trunk's `index.html` as meta-gen keeps it, before pre-rendering the home page into it
+const SPA_SHELL: &str = "app.html";
+
+/// Pick the page meta-gen pre-rendered for a path if there is one, otherwise the SPA shell
+fn resolve_fallback(dist_dir: &Path, request_path: &str) -> PathBuf {
+    let relative = Path::new(request_path.trim_start_matches('/'));
+    let is_safe = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if is_safe {
+        let sidecar = dist_dir.join(relative).join("index.html");
+        if sidecar.is_file() {
+            return sidecar;
+        }
+    }
+
+    let shell = dist_dir.join(SPA_SHELL);
+    if shell.is_file() {
+        return shell;
+    }
+    dist_dir.join("index.html")
+}
+
+/// Check if a file name carries a Trunk content hash, e.g. `index-1a2b3c4d5e6f7a8b.css`
+fn is_hashed_asset(request_path: &str) -> bool {
+    let file_name = request_path.rsplit('/').next().unwrap_or_default();
+
+    file_name.split('-').skip(1).any(|part| {
+        let hash_len = part.chars().take_while(|c| c.is_ascii_hexdigit()).count();
+        (12..=16).contains(&hash_len) && part[hash_len..].starts_with(['.', '_'])
+    })
+}
+
+/// Weak ETag derived from the file's Last-Modified date and size
+fn weak_etag(last_modified: &HeaderValue, content_length: &HeaderValue) -> String {
+    // FNV-1a, stable across builds unlike std's DefaultHasher
+    let hash = last_modified
+        .as_bytes()
+        .iter()
+        .chain(b"-")
+        .chain(content_length.as_bytes())
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        });
+
+    format!("W/\"{:016x}\"", hash)
+}
+
+/// Add Cache-Control and ETag headers and answer If-None-Match with 304
+async fn cache_headers(req: Request, next: Next) -> Response {
+    let path = req.uri().path().to_string();
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let mut response = next.run(req).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let cache_control = if path.starts_with("/content/") {
+        CONTENT_CACHE_CONTROL
+    } else if is_hashed_asset(&path) {
+        HASHED_ASSET_CACHE_CONTROL
+    } else {
+        DEFAULT_CACHE_CONTROL
+    };
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+
+    let headers = response.headers();
+    let etag = match (
+        headers.get(header::LAST_MODIFIED),
+        headers.get(header::CONTENT_LENGTH),
+    ) {
+        (Some(last_modified), Some(content_length)) => weak_etag(last_modified, content_length),
+        _ => return response,
+    };
+
+    let not_modified = if_none_match
+        .as_ref()
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        })
+        .unwrap_or(false);
+
+    let etag = HeaderValue::from_str(&etag).expect("ETag is valid header value");
+    if not_modified {
+        let mut not_modified = Response::new(Body::empty());
+        *not_modified.status_mut() = StatusCode::NOT_MODIFIED;
+        let headers = not_modified.headers_mut();
+        headers.insert(header::ETAG, etag);
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        );
+        return not_modified;
+    }
+
+    response.headers_mut().insert(header::ETAG, etag);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    struct TestDist(PathBuf);
+
+    impl TestDist {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("site-test-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(dir.join("post/my_post")).unwrap();
+            fs::create_dir_all(dir.join("content/pages")).unwrap();
+            fs::write(dir.join("index.html"), "spa index").unwrap();
+            fs::write(dir.join("post/my_post/index.html"), "sidecar").unwrap();
+            fs::write(dir.join("content/pages/home.md"), "# Home").unwrap();
+            fs::write(dir.join("app-0123456789abcdef_bg.wasm"), "wasm").unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TestDist {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn get(dist: &TestDist, uri: &str, if_none_match: Option<&str>) -> Response {
+        let mut req = Request::builder().uri(uri);
+        if let Some(etag) = if_none_match {
+            req = req.header(header::IF_NONE_MATCH, etag);
+        }
+        router(&dist.0)
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_is_hashed_asset() {
+        assert!(is_hashed_asset(
+            "/gertjanassies_dev-yew-0123456789abcdef_bg.wasm"
+        ));
+        assert!(is_hashed_asset("/index-0123456789abcdef.css"));
+        assert!(!is_hashed_asset("/index.html"));
+        assert!(!is_hashed_asset("/static/logo_ga.svg"));
+        assert!(!is_hashed_asset("/post/my-post"));
+    }
+
+    #[test]
+    fn test_resolve_fallback_rejects_traversal() {
+        let dist = Path::new("/srv/dist");
+        assert_eq!(
+            resolve_fallback(dist, "/../etc/passwd"),
+            dist.join("index.html")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sidecar_served_before_spa_fallback() {
+        let dist = TestDist::new();
+
+        let response = get(&dist, "/post/my_post", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "sidecar");
+
+        let response = get(&dist, "/blog", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "spa index");
+    }
+
+    #[tokio::test]
+    async fn test_spa_shell_served_when_home_is_prerendered() {
+        let dist = TestDist::new();
+        fs::write(dist.0.join("app.html"), "spa shell").unwrap();
+
+        let response = get(&dist, "/", None).await;
+        assert_eq!(body_string(response).await, "spa index");
+
+        let response = get(&dist, "/search", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "spa shell");
+
+        let response = get(&dist, "/post/my_post", None).await;
+        assert_eq!(body_string(response).await, "sidecar");
+    }
+
+    #[tokio::test]
+    async fn test_content_is_not_cached_and_never_falls_back() {
+        let dist = TestDist::new();
+
+        let response = get(&dist, "/content/pages/home.md", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            CONTENT_CACHE_CONTROL
+        );
+
+        let response = get(&dist, "/content/pages/missing.md", None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = get(&dist, "/api/unknown", None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_hashed_assets_are_immutable() {
+        let dist = TestDist::new();
+
+        let response = get(&dist, "/app-0123456789abcdef_bg.wasm", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            HASHED_ASSET_CACHE_CONTROL
+        );
+    }
+
+    #[tokio::test]
+    async fn test_etag_revalidation() {
+        let dist = TestDist::new();
+
+        let response = get(&dist, "/index.html", None).await;
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = get(&dist, "/index.html", Some(&etag)).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+}
@@ -0,0 +1,267 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{validate_slug, ApiError};
+use crate::redis_client::PageId;
+
+/// One site served by this server, as configured in the sites file
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SiteConfig {
+    /// Namespace of the site's keys, `[A-Za-z0-9_-]`
+    pub id: String,
+    /// Host headers (optionally with port) served as this site
+    pub hosts: Vec<String>,
+    /// Origins allowed to call the API from a browser, any origin when empty
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Slugs that may have stats, any valid slug when not set
+    #[serde(default)]
+    pub slugs: Option<HashSet<String>>,
+    /// Event names counted by `POST /events`, the `--event-names` list when not set
+    #[serde(default)]
+    pub events: Option<HashSet<String>>,
+    /// Serve requests for unknown hosts as this site
+    #[serde(default)]
+    pub default: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SitesFile {
+    #[serde(rename = "site")]
+    sites: Vec<SiteConfig>,
+}
+
+impl SiteConfig {
+    /// The only site when no sites file is configured: every host, origin and slug
+    pub fn single() -> Self {
+        Self {
+            id: "default".to_string(),
+            hosts: Vec::new(),
+            allowed_origins: Vec::new(),
+            slugs: None,
+            events: None,
+            default: true,
+        }
+    }
+
+    pub fn allows_slug(&self, slug: &str) -> bool {
+        self.slugs.as_ref().is_none_or(|slugs| slugs.contains(slug))
+    }
+
+    /// Event names counted on this site, `default` unless the site lists its own
+    pub fn event_names<'a>(&'a self, default: &'a HashSet<String>) -> &'a HashSet<String> {
+        self.events.as_ref().unwrap_or(default)
+    }
+
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.is_empty()
+            || self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.trim_end_matches('/') == origin)
+    }
+}
+
+/// Parse and check a sites file (TOML with one `[[site]]` table per site)
+pub fn parse_sites(toml_str: &str) -> anyhow::Result<Vec<SiteConfig>> {
+    let file: SitesFile = toml::from_str(toml_str)?;
+    let mut ids = HashSet::new();
+    let mut hosts = HashSet::new();
+
+    for site in &file.sites {
+        validate_slug(&site.id).map_err(|_| anyhow::anyhow!("Invalid site id '{}'", site.id))?;
+        if !ids.insert(site.id.as_str()) {
+            anyhow::bail!("Site '{}' is configured twice", site.id);
+        }
+        for host in &site.hosts {
+            if !hosts.insert(host.to_ascii_lowercase()) {
+                anyhow::bail!("Host '{}' is configured for more than one site", host);
+            }
+        }
+    }
+
+    if file.sites.is_empty() {
+        anyhow::bail!("No sites configured");
+    }
+    if file.sites.iter().filter(|site| site.default).count() > 1 {
+        anyhow::bail!("More than one site is marked as default");
+    }
+
+    Ok(file.sites)
+}
+
+pub fn load_sites(path: &Path) -> anyhow::Result<Vec<SiteConfig>> {
+    let toml_str = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))?;
+    parse_sites(&toml_str)
+}
+
+/// A configured site with its stats store
+pub struct Site<S> {
+    pub config: SiteConfig,
+    pub store: S,
+}
+
+impl<S> Site<S> {
+    /// Check a slug is valid and may have stats on this site
+    pub fn check_slug(&self, slug: &str) -> Result<(), ApiError> {
+        validate_slug(slug)?;
+        if !self.config.allows_slug(slug) {
+            return Err(ApiError::UnknownPage(slug.to_string()));
+        }
+        Ok(())
+    }
+
+    /// The page a `kind` and `slug` from a request path refer to, checked like [`Self::check_slug`]
+    pub fn page(&self, kind: &str, slug: &str) -> Result<PageId, ApiError> {
+        let kind = kind
+            .parse()
+            .map_err(|_| ApiError::InvalidKind(kind.chars().take(16).collect()))?;
+        self.check_slug(slug)?;
+        Ok(PageId::new(kind, slug))
+    }
+}
+
+/// The sites served by this server, looked up by Host header
+pub struct Sites<S> {
+    sites: Vec<Arc<Site<S>>>,
+}
+
+impl<S> Sites<S> {
+    pub fn new(configs: Vec<SiteConfig>, mut make_store: impl FnMut(&SiteConfig) -> S) -> Self {
+        let sites = configs
+            .into_iter()
+            .map(|config| {
+                let store = make_store(&config);
+                Arc::new(Site { config, store })
+            })
+            .collect();
+        Self { sites }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Site<S>>> {
+        self.sites.iter()
+    }
+
+    /// Site for a Host header: an exact match first, then the host without port,
+    /// then the default site
+    pub fn resolve(&self, host: Option<&str>) -> Option<Arc<Site<S>>> {
+        let find = |name: &str| {
+            self.sites.iter().find(|site| {
+                site.config
+                    .hosts
+                    .iter()
+                    .any(|host| host.eq_ignore_ascii_case(name))
+            })
+        };
+
+        host.and_then(|host| {
+            find(host).or_else(|| {
+                host.rsplit_once(':')
+                    .and_then(|(name, port)| port.parse::<u16>().ok().and(find(name)))
+            })
+        })
+        .or_else(|| self.sites.iter().find(|site| site.config.default))
+        .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITES: &str = r#"
+        [[site]]
+        id = "personal"
+        hosts = ["gertjanassies.dev", "localhost"]
+        allowed_origins = ["https://gertjanassies.dev/"]
+        default = true
+
+        [[site]]
+        id = "team"
+        hosts = ["team.example.com", "localhost:3002"]
+        slugs = ["home", "roadmap"]
+        events = ["signup"]
+    "#;
+
+    fn sites() -> Sites<()> {
+        Sites::new(parse_sites(SITES).unwrap(), |_| ())
+    }
+
+    fn resolve(host: Option<&str>) -> Option<String> {
+        sites().resolve(host).map(|site| site.config.id.clone())
+    }
+
+    #[test]
+    fn test_resolve_by_host() {
+        assert_eq!(resolve(Some("team.example.com")).unwrap(), "team");
+        assert_eq!(resolve(Some("Team.Example.com:443")).unwrap(), "team");
+        assert_eq!(resolve(Some("localhost:3002")).unwrap(), "team");
+        assert_eq!(resolve(Some("localhost:3001")).unwrap(), "personal");
+        assert_eq!(resolve(Some("unknown.example.com")).unwrap(), "personal");
+        assert_eq!(resolve(None).unwrap(), "personal");
+    }
+
+    #[test]
+    fn test_unknown_host_without_default() {
+        let configs = parse_sites(&SITES.replace("default = true", "")).unwrap();
+        let sites = Sites::new(configs, |_| ());
+        assert!(sites.resolve(Some("unknown.example.com")).is_none());
+    }
+
+    #[test]
+    fn test_site_allow_lists() {
+        let configs = parse_sites(SITES).unwrap();
+
+        assert!(configs[0].allows_slug("anything"));
+        assert!(configs[0].allows_origin("https://gertjanassies.dev"));
+        assert!(!configs[0].allows_origin("https://evil.example.com"));
+
+        assert!(configs[1].allows_slug("roadmap"));
+        assert!(!configs[1].allows_slug("anything"));
+        assert!(configs[1].allows_origin("https://evil.example.com"));
+
+        let default = HashSet::from(["copy_code".to_string()]);
+        assert!(configs[0].event_names(&default).contains("copy_code"));
+        assert!(configs[1].event_names(&default).contains("signup"));
+        assert!(!configs[1].event_names(&default).contains("copy_code"));
+    }
+
+    #[test]
+    fn test_page_from_path() {
+        use crate::redis_client::ContentKind;
+
+        let site = sites().resolve(Some("team.example.com")).unwrap();
+        assert_eq!(
+            site.page("page", "home").unwrap(),
+            PageId::new(ContentKind::Page, "home")
+        );
+        assert_eq!(
+            site.page("posts", "home"),
+            Err(ApiError::InvalidKind("posts".to_string()))
+        );
+        assert_eq!(
+            site.page("post", "about"),
+            Err(ApiError::UnknownPage("about".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_sites_files() {
+        let duplicate_host = r#"
+            [[site]]
+            id = "a"
+            hosts = ["example.com"]
+            [[site]]
+            id = "b"
+            hosts = ["EXAMPLE.com"]
+        "#;
+        assert!(parse_sites(duplicate_host).is_err());
+        assert!(parse_sites("[[site]]\nid = \"a:b\"\nhosts = []").is_err());
+        assert!(parse_sites("site = []").is_err());
+    }
+}
@@ -0,0 +1,486 @@
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use crate::events::{Event, EventCount};
+use crate::experiments::{ExperimentAction, VariantCounts};
+use crate::reading_times::ReadingTime;
+use crate::redis_client::{PageId, PageStats, RedisPageStatsClient};
+use crate::user_agent::{Client, ClientBreakdown};
+
+/// Errors of a [`PageStatsStore`]
+#[derive(Debug)]
+pub enum StoreError {
+    /// Redis returned an error or the connection failed
+    Redis(redis::RedisError),
+    /// The store didn't answer within the configured timeout
+    Timeout,
+    /// The circuit breaker is open, the store isn't called until it cools down
+    CircuitOpen,
+}
+
+impl StoreError {
+    /// Whether the call certainly never reached the store, so it can be sent again without
+    /// counting twice. A timeout or a dropped connection may come after the store applied it.
+    pub fn is_unsent(&self) -> bool {
+        match self {
+            StoreError::CircuitOpen => true,
+            StoreError::Redis(e) => e.is_connection_refusal(),
+            StoreError::Timeout => false,
+        }
+    }
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Redis(e) => write!(f, "redis error: {}", e),
+            StoreError::Timeout => write!(f, "store timed out"),
+            StoreError::CircuitOpen => write!(f, "circuit breaker is open"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<redis::RedisError> for StoreError {
+    fn from(e: redis::RedisError) -> Self {
+        StoreError::Redis(e)
+    }
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// Views and likes to add to a page in one go
+#[derive(Debug, Clone, PartialEq)]
+pub struct Increment {
+    pub page: PageId,
+    pub views: u64,
+    pub likes: u64,
+}
+
+/// Operations the API needs from the stats store, implemented by
+/// [`RedisPageStatsClient`] and by the layers wrapping it
+pub trait PageStatsStore: Send + Sync + 'static {
+    fn get_page_stats(
+        &self,
+        page: &PageId,
+    ) -> impl Future<Output = StoreResult<Option<PageStats>>> + Send;
+
+    fn increment_views(&self, page: &PageId)
+        -> impl Future<Output = StoreResult<PageStats>> + Send;
+
+    fn increment_likes(&self, page: &PageId)
+        -> impl Future<Output = StoreResult<PageStats>> + Send;
+
+    fn set_reading_time(
+        &self,
+        page: &PageId,
+        reading: &ReadingTime,
+    ) -> impl Future<Output = StoreResult<PageStats>> + Send;
+
+    fn get_all_page_stats(&self) -> impl Future<Output = StoreResult<Vec<PageStats>>> + Send;
+
+    /// Views of each page on each day, one row per page with one count per day
+    fn get_daily_views(
+        &self,
+        pages: &[PageId],
+        days: &[NaiveDate],
+    ) -> impl Future<Output = StoreResult<Vec<Vec<u64>>>> + Send;
+
+    /// Count a view of a page from a country, given as ISO 3166-1 alpha-2 code
+    fn increment_country(
+        &self,
+        page: &PageId,
+        country: &str,
+    ) -> impl Future<Output = StoreResult<()>> + Send;
+
+    /// Views of a page per country
+    fn get_countries(
+        &self,
+        page: &PageId,
+    ) -> impl Future<Output = StoreResult<BTreeMap<String, u64>>> + Send;
+
+    /// Count a custom event on a page
+    fn increment_event(
+        &self,
+        page: &PageId,
+        event: &Event,
+    ) -> impl Future<Output = StoreResult<()>> + Send;
+
+    /// Counts of the custom events on a page
+    fn get_events(
+        &self,
+        page: &PageId,
+    ) -> impl Future<Output = StoreResult<BTreeMap<String, EventCount>>> + Send;
+
+    /// Count an impression or click of a variant of a page's experiment
+    fn increment_experiment(
+        &self,
+        page: &PageId,
+        variant: usize,
+        action: ExperimentAction,
+    ) -> impl Future<Output = StoreResult<()>> + Send;
+
+    /// Impressions and clicks per variant of each page's experiment
+    fn get_experiments(
+        &self,
+        pages: &[PageId],
+    ) -> impl Future<Output = StoreResult<Vec<BTreeMap<usize, VariantCounts>>>> + Send;
+
+    /// Count a view of a page from a client, for the page and for the whole site
+    fn increment_client(
+        &self,
+        page: &PageId,
+        client: &Client,
+    ) -> impl Future<Output = StoreResult<()>> + Send;
+
+    /// Views of each page per device, browser and OS
+    fn get_clients(
+        &self,
+        pages: &[PageId],
+    ) -> impl Future<Output = StoreResult<Vec<ClientBreakdown>>> + Send;
+
+    /// Views of the whole site per device, browser and OS
+    fn get_site_clients(&self) -> impl Future<Output = StoreResult<ClientBreakdown>> + Send;
+
+    /// Apply a batch of increments, returning the updated stats of each page. The default
+    /// applies them one at a time, stores that can do better use a single round trip.
+    fn increment_many(
+        &self,
+        increments: &[Increment],
+    ) -> impl Future<Output = StoreResult<Vec<PageStats>>> + Send {
+        async move {
+            let mut updated = Vec::with_capacity(increments.len());
+            for increment in increments {
+                let mut stats = None;
+                for _ in 0..increment.views {
+                    stats = Some(self.increment_views(&increment.page).await?);
+                }
+                for _ in 0..increment.likes {
+                    stats = Some(self.increment_likes(&increment.page).await?);
+                }
+                updated.extend(stats);
+            }
+            Ok(updated)
+        }
+    }
+}
+
+impl PageStatsStore for RedisPageStatsClient {
+    async fn get_page_stats(&self, page: &PageId) -> StoreResult<Option<PageStats>> {
+        Ok(RedisPageStatsClient::get_page_stats(self, page).await?)
+    }
+
+    async fn increment_views(&self, page: &PageId) -> StoreResult<PageStats> {
+        Ok(RedisPageStatsClient::increment_views(self, page).await?)
+    }
+
+    async fn increment_likes(&self, page: &PageId) -> StoreResult<PageStats> {
+        Ok(RedisPageStatsClient::increment_likes(self, page).await?)
+    }
+
+    async fn set_reading_time(
+        &self,
+        page: &PageId,
+        reading: &ReadingTime,
+    ) -> StoreResult<PageStats> {
+        Ok(RedisPageStatsClient::set_reading_time(self, page, reading).await?)
+    }
+
+    async fn get_all_page_stats(&self) -> StoreResult<Vec<PageStats>> {
+        Ok(RedisPageStatsClient::get_all_page_stats(self).await?)
+    }
+
+    async fn get_daily_views(
+        &self,
+        pages: &[PageId],
+        days: &[NaiveDate],
+    ) -> StoreResult<Vec<Vec<u64>>> {
+        Ok(RedisPageStatsClient::get_daily_views(self, pages, days).await?)
+    }
+
+    async fn increment_country(&self, page: &PageId, country: &str) -> StoreResult<()> {
+        Ok(RedisPageStatsClient::increment_country(self, page, country).await?)
+    }
+
+    async fn get_countries(&self, page: &PageId) -> StoreResult<BTreeMap<String, u64>> {
+        Ok(RedisPageStatsClient::get_countries(self, page).await?)
+    }
+
+    async fn increment_event(&self, page: &PageId, event: &Event) -> StoreResult<()> {
+        Ok(RedisPageStatsClient::increment_event(self, page, event).await?)
+    }
+
+    async fn get_events(&self, page: &PageId) -> StoreResult<BTreeMap<String, EventCount>> {
+        Ok(RedisPageStatsClient::get_events(self, page).await?)
+    }
+
+    async fn increment_experiment(
+        &self,
+        page: &PageId,
+        variant: usize,
+        action: ExperimentAction,
+    ) -> StoreResult<()> {
+        Ok(RedisPageStatsClient::increment_experiment(self, page, variant, action).await?)
+    }
+
+    async fn get_experiments(
+        &self,
+        pages: &[PageId],
+    ) -> StoreResult<Vec<BTreeMap<usize, VariantCounts>>> {
+        Ok(RedisPageStatsClient::get_experiments(self, pages).await?)
+    }
+
+    async fn increment_client(&self, page: &PageId, client: &Client) -> StoreResult<()> {
+        Ok(RedisPageStatsClient::increment_client(self, page, client).await?)
+    }
+
+    async fn get_clients(&self, pages: &[PageId]) -> StoreResult<Vec<ClientBreakdown>> {
+        Ok(RedisPageStatsClient::get_clients(self, pages).await?)
+    }
+
+    async fn get_site_clients(&self) -> StoreResult<ClientBreakdown> {
+        Ok(RedisPageStatsClient::get_site_clients(self).await?)
+    }
+
+    async fn increment_many(&self, increments: &[Increment]) -> StoreResult<Vec<PageStats>> {
+        Ok(RedisPageStatsClient::increment_many(self, increments).await?)
+    }
+}
+
+/// In-memory store for the tests of the layers wrapping a store
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Store keeping stats in a map, it can be switched off and slowed down
+    #[derive(Default)]
+    pub struct FakeStore {
+        pub stats: Mutex<HashMap<PageId, PageStats>>,
+        pub countries: Mutex<HashMap<PageId, BTreeMap<String, u64>>>,
+        pub events: Mutex<HashMap<PageId, HashMap<String, u64>>>,
+        pub experiments: Mutex<HashMap<PageId, HashMap<String, u64>>>,
+        /// Client counts per page, `None` holds the whole site's
+        pub clients: Mutex<HashMap<Option<PageId>, HashMap<String, u64>>>,
+        pub down: AtomicBool,
+        /// Number of calls made, a batch counts as one
+        pub calls: AtomicUsize,
+        pub delay: Option<Duration>,
+        /// Time between applying a write and answering, a timeout can hit after the write
+        pub answer_delay: Option<Duration>,
+    }
+
+    impl FakeStore {
+        async fn check(&self) -> StoreResult<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+            if self.down.load(Ordering::SeqCst) {
+                return Err(StoreError::Redis(redis::RedisError::from(
+                    std::io::Error::from(std::io::ErrorKind::ConnectionRefused),
+                )));
+            }
+            Ok(())
+        }
+
+        /// Answer a write that was applied
+        async fn answer<T>(&self, result: T) -> StoreResult<T> {
+            if let Some(delay) = self.answer_delay {
+                tokio::time::sleep(delay).await;
+            }
+            Ok(result)
+        }
+
+        fn update(&self, page: &PageId, f: impl FnOnce(&mut PageStats)) -> PageStats {
+            let mut all = self.stats.lock().unwrap();
+            let stats = all
+                .entry(page.clone())
+                .or_insert_with(|| PageStats::new(page));
+            f(stats);
+            stats.clone()
+        }
+
+        /// Views of the post with this slug
+        pub fn views(&self, slug: &str) -> u64 {
+            self.stats
+                .lock()
+                .unwrap()
+                .get(&PageId::post(slug))
+                .map_or(0, |stats| stats.views)
+        }
+    }
+
+    impl PageStatsStore for FakeStore {
+        async fn get_page_stats(&self, page: &PageId) -> StoreResult<Option<PageStats>> {
+            self.check().await?;
+            Ok(self.stats.lock().unwrap().get(page).cloned())
+        }
+
+        async fn increment_views(&self, page: &PageId) -> StoreResult<PageStats> {
+            self.check().await?;
+            self.answer(self.update(page, PageStats::increment_views))
+                .await
+        }
+
+        async fn increment_likes(&self, page: &PageId) -> StoreResult<PageStats> {
+            self.check().await?;
+            self.answer(self.update(page, PageStats::increment_likes))
+                .await
+        }
+
+        async fn set_reading_time(
+            &self,
+            page: &PageId,
+            reading: &ReadingTime,
+        ) -> StoreResult<PageStats> {
+            self.check().await?;
+            Ok(self.update(page, |stats| stats.set_reading_time(reading)))
+        }
+
+        async fn get_all_page_stats(&self) -> StoreResult<Vec<PageStats>> {
+            self.check().await?;
+            Ok(self.stats.lock().unwrap().values().cloned().collect())
+        }
+
+        /// Every view counts on the last day asked for
+        async fn get_daily_views(
+            &self,
+            pages: &[PageId],
+            days: &[NaiveDate],
+        ) -> StoreResult<Vec<Vec<u64>>> {
+            self.check().await?;
+            let all = self.stats.lock().unwrap();
+            Ok(pages
+                .iter()
+                .map(|page| {
+                    let mut views = vec![0; days.len()];
+                    if let Some(last) = views.last_mut() {
+                        *last = all.get(page).map_or(0, |stats| stats.views);
+                    }
+                    views
+                })
+                .collect())
+        }
+
+        async fn increment_country(&self, page: &PageId, country: &str) -> StoreResult<()> {
+            self.check().await?;
+            let mut countries = self.countries.lock().unwrap();
+            *countries
+                .entry(page.clone())
+                .or_default()
+                .entry(country.to_string())
+                .or_default() += 1;
+            Ok(())
+        }
+
+        async fn get_countries(&self, page: &PageId) -> StoreResult<BTreeMap<String, u64>> {
+            self.check().await?;
+            Ok(self
+                .countries
+                .lock()
+                .unwrap()
+                .get(page)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        async fn increment_event(&self, page: &PageId, event: &Event) -> StoreResult<()> {
+            self.check().await?;
+            let mut events = self.events.lock().unwrap();
+            let counts = events.entry(page.clone()).or_default();
+            for field in event.fields() {
+                *counts.entry(field).or_default() += 1;
+            }
+            Ok(())
+        }
+
+        async fn get_events(&self, page: &PageId) -> StoreResult<BTreeMap<String, EventCount>> {
+            self.check().await?;
+            let fields = self.events.lock().unwrap().get(page).cloned();
+            Ok(crate::events::counts_from_fields(
+                fields.unwrap_or_default(),
+            ))
+        }
+
+        async fn increment_experiment(
+            &self,
+            page: &PageId,
+            variant: usize,
+            action: ExperimentAction,
+        ) -> StoreResult<()> {
+            self.check().await?;
+            let mut experiments = self.experiments.lock().unwrap();
+            *experiments
+                .entry(page.clone())
+                .or_default()
+                .entry(action.field(variant))
+                .or_default() += 1;
+            Ok(())
+        }
+
+        async fn get_experiments(
+            &self,
+            pages: &[PageId],
+        ) -> StoreResult<Vec<BTreeMap<usize, VariantCounts>>> {
+            self.check().await?;
+            let experiments = self.experiments.lock().unwrap();
+            Ok(pages
+                .iter()
+                .map(|page| {
+                    let fields = experiments.get(page).cloned().unwrap_or_default();
+                    crate::experiments::counts_from_fields(fields)
+                })
+                .collect())
+        }
+
+        async fn increment_client(&self, page: &PageId, client: &Client) -> StoreResult<()> {
+            self.check().await?;
+            let mut clients = self.clients.lock().unwrap();
+            for key in [Some(page.clone()), None] {
+                let counts = clients.entry(key).or_default();
+                for field in client.fields() {
+                    *counts.entry(field).or_default() += 1;
+                }
+            }
+            Ok(())
+        }
+
+        async fn get_clients(&self, pages: &[PageId]) -> StoreResult<Vec<ClientBreakdown>> {
+            self.check().await?;
+            let clients = self.clients.lock().unwrap();
+            Ok(pages
+                .iter()
+                .map(|page| {
+                    let fields = clients.get(&Some(page.clone())).cloned();
+                    ClientBreakdown::from_fields(fields.unwrap_or_default())
+                })
+                .collect())
+        }
+
+        async fn get_site_clients(&self) -> StoreResult<ClientBreakdown> {
+            self.check().await?;
+            let fields = self.clients.lock().unwrap().get(&None).cloned();
+            Ok(ClientBreakdown::from_fields(fields.unwrap_or_default()))
+        }
+
+        async fn increment_many(&self, increments: &[Increment]) -> StoreResult<Vec<PageStats>> {
+            self.check().await?;
+            let updated = increments
+                .iter()
+                .map(|increment| {
+                    self.update(&increment.page, |stats| {
+                        stats.views += increment.views;
+                        stats.likes += increment.likes;
+                    })
+                })
+                .collect();
+            self.answer(updated).await
+        }
+    }
+}
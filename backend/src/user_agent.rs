@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+/// Kind of device a page was viewed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Desktop,
+    Mobile,
+    Tablet,
+    /// Crawlers, link previews and command line clients
+    Bot,
+}
+
+impl Device {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Device::Desktop => "desktop",
+            Device::Mobile => "mobile",
+            Device::Tablet => "tablet",
+            Device::Bot => "bot",
+        }
+    }
+}
+
+/// Browser family, versions aren't kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Chrome,
+    Edge,
+    Firefox,
+    Opera,
+    Safari,
+    Samsung,
+    Other,
+}
+
+impl Browser {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Browser::Chrome => "chrome",
+            Browser::Edge => "edge",
+            Browser::Firefox => "firefox",
+            Browser::Opera => "opera",
+            Browser::Safari => "safari",
+            Browser::Samsung => "samsung",
+            Browser::Other => "other",
+        }
+    }
+}
+
+/// Operating system family, versions aren't kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)] // ChromeOs and MacOs are the names of the systems
+pub enum Os {
+    Android,
+    ChromeOs,
+    Ios,
+    Linux,
+    MacOs,
+    Windows,
+    Other,
+}
+
+impl Os {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Os::Android => "android",
+            Os::ChromeOs => "chromeos",
+            Os::Ios => "ios",
+            Os::Linux => "linux",
+            Os::MacOs => "macos",
+            Os::Windows => "windows",
+            Os::Other => "other",
+        }
+    }
+}
+
+/// Coarse description of a client, the only part of its User-Agent that is counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Client {
+    pub device: Device,
+    pub browser: Browser,
+    pub os: Os,
+}
+
+impl Client {
+    /// Classify a User-Agent header. Browsers copy each other's tokens, so the more
+    /// specific ones are checked first (Edge and Opera also claim to be Chrome and Safari).
+    pub fn parse(user_agent: &str) -> Self {
+        let has = |token: &str| user_agent.contains(token);
+        let lower = user_agent.to_ascii_lowercase();
+
+        let os = if has("Windows") {
+            Os::Windows
+        } else if has("iPhone") || has("iPad") || has("iPod") {
+            Os::Ios
+        } else if has("Android") {
+            Os::Android
+        } else if has("CrOS") {
+            Os::ChromeOs
+        } else if has("Macintosh") || has("Mac OS X") {
+            Os::MacOs
+        } else if has("Linux") {
+            Os::Linux
+        } else {
+            Os::Other
+        };
+
+        let browser = if has("Edg/") || has("EdgA/") || has("EdgiOS/") {
+            Browser::Edge
+        } else if has("OPR/") || has("Opera") {
+            Browser::Opera
+        } else if has("SamsungBrowser/") {
+            Browser::Samsung
+        } else if has("Firefox/") || has("FxiOS/") {
+            Browser::Firefox
+        } else if has("Chrome/") || has("CriOS/") {
+            Browser::Chrome
+        } else if has("Safari/") {
+            Browser::Safari
+        } else {
+            Browser::Other
+        };
+
+        let device = if ["bot", "crawl", "spider", "preview", "curl/", "wget/"]
+            .iter()
+            .any(|token| lower.contains(token))
+        {
+            Device::Bot
+        } else if has("iPad") || has("Tablet") || (os == Os::Android && !has("Mobile")) {
+            Device::Tablet
+        } else if has("Mobi") || has("iPhone") || has("iPod") {
+            Device::Mobile
+        } else {
+            Device::Desktop
+        };
+
+        Client {
+            device,
+            browser,
+            os,
+        }
+    }
+
+    /// Fields counted for this client in a breakdown hash
+    pub fn fields(&self) -> [String; 3] {
+        [
+            format!("device:{}", self.device.as_str()),
+            format!("browser:{}", self.browser.as_str()),
+            format!("os:{}", self.os.as_str()),
+        ]
+    }
+}
+
+/// Views per device type, browser family and OS family
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ClientBreakdown {
+    /// `desktop`, `mobile`, `tablet` or `bot`
+    pub devices: BTreeMap<String, u64>,
+    /// `chrome`, `edge`, `firefox`, `opera`, `safari`, `samsung` or `other`
+    pub browsers: BTreeMap<String, u64>,
+    /// `android`, `chromeos`, `ios`, `linux`, `macos`, `windows` or `other`
+    pub os: BTreeMap<String, u64>,
+}
+
+impl ClientBreakdown {
+    /// Build from the fields of a breakdown hash, see [`Client::fields`]
+    pub fn from_fields(fields: HashMap<String, u64>) -> Self {
+        let mut breakdown = Self::default();
+        for (field, count) in fields {
+            let Some((group, name)) = field.split_once(':') else {
+                continue;
+            };
+            let counts = match group {
+                "device" => &mut breakdown.devices,
+                "browser" => &mut breakdown.browsers,
+                "os" => &mut breakdown.os,
+                _ => continue,
+            };
+            counts.insert(name.to_string(), count);
+        }
+        breakdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_agents() {
+        let cases = [
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                (Device::Desktop, Browser::Chrome, Os::Windows),
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.2210.91",
+                (Device::Desktop, Browser::Edge, Os::Windows),
+            ),
+            (
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15",
+                (Device::Desktop, Browser::Safari, Os::MacOs),
+            ),
+            (
+                "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0",
+                (Device::Desktop, Browser::Firefox, Os::Linux),
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+                (Device::Mobile, Browser::Safari, Os::Ios),
+            ),
+            (
+                "Mozilla/5.0 (iPad; CPU OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/120.0.6099.119 Mobile/15E148 Safari/604.1",
+                (Device::Tablet, Browser::Chrome, Os::Ios),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/23.0 Chrome/115.0.0.0 Mobile Safari/537.36",
+                (Device::Mobile, Browser::Samsung, Os::Android),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                (Device::Tablet, Browser::Chrome, Os::Android),
+            ),
+            (
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+                (Device::Bot, Browser::Other, Os::Other),
+            ),
+            ("curl/8.4.0", (Device::Bot, Browser::Other, Os::Other)),
+        ];
+
+        for (user_agent, (device, browser, os)) in cases {
+            assert_eq!(
+                Client::parse(user_agent),
+                Client {
+                    device,
+                    browser,
+                    os
+                },
+                "{}",
+                user_agent
+            );
+        }
+    }
+
+    #[test]
+    fn test_breakdown_from_fields() {
+        let client = Client::parse("Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Firefox/121.0");
+        let mut fields: HashMap<String, u64> = client
+            .fields()
+            .into_iter()
+            .map(|field| (field, 2))
+            .collect();
+        fields.insert("unknown:field".to_string(), 1);
+
+        let breakdown = ClientBreakdown::from_fields(fields);
+        assert_eq!(
+            breakdown.devices,
+            BTreeMap::from([("desktop".to_string(), 2)])
+        );
+        assert_eq!(
+            breakdown.browsers,
+            BTreeMap::from([("firefox".to_string(), 2)])
+        );
+        assert_eq!(breakdown.os, BTreeMap::from([("linux".to_string(), 2)]));
+    }
+}
@@ -0,0 +1,374 @@
+//! Frontmatter of the blog's markdown posts, parsed as YAML. Shared by the frontend, its build
+//! script, the backend and meta-gen, and free of platform specific dependencies so it builds for
+//! wasm as well. [`structured_data`] describes posts and the site as schema.org JSON-LD, the
+//! `markdown` feature adds the rendering pipeline of post bodies.
+
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod structured_data;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Id of the element holding the HTML meta-gen pre-renders into a page for crawlers and readers
+/// without JavaScript, which the frontend removes once it renders the page itself
+pub const PRERENDERED_ID: &str = "prerendered";
+
+/// Frontmatter fields of a post
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct PostFrontmatter {
+    pub title: String,
+    /// Day the post was published, `YYYY-MM-DD` (or `YYYYMMDD`) in the file
+    #[serde(default, with = "date")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>, format = Date))]
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub author: String,
+    /// A YAML list, or the tags separated by commas
+    #[serde(default, deserialize_with = "list")]
+    pub tags: Vec<String>,
+    /// One or more categories separated by commas, see [`PostFrontmatter::categories`]
+    #[serde(default)]
+    pub category: String,
+    /// Drafts set this to `false`, they are left out of listings
+    #[serde(default = "published")]
+    pub published: bool,
+    /// Path of the header image, e.g. `/content/images/top.jpg`
+    #[serde(default)]
+    pub image: String,
+    /// Alternative titles tested against `title`
+    #[serde(default, deserialize_with = "list")]
+    pub title_variants: Vec<String>,
+    /// Alternative summaries tested against `summary`
+    #[serde(default, deserialize_with = "list")]
+    pub summary_variants: Vec<String>,
+}
+
+fn published() -> bool {
+    true
+}
+
+impl Default for PostFrontmatter {
+    fn default() -> Self {
+        Self {
+            title: "Untitled".to_string(),
+            date: None,
+            summary: String::new(),
+            author: String::new(),
+            tags: Vec::new(),
+            category: String::new(),
+            published: true,
+            image: String::new(),
+            title_variants: Vec::new(),
+            summary_variants: Vec::new(),
+        }
+    }
+}
+
+/// Why a frontmatter could not be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The markdown doesn't start with a block between `---` lines
+    MissingFrontmatter,
+    /// The block isn't valid YAML or a field has the wrong type
+    Yaml(String),
+    /// A field is missing or has a value that isn't allowed
+    Invalid {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::MissingFrontmatter => write!(f, "no frontmatter between --- lines"),
+            Error::Yaml(e) => write!(f, "invalid frontmatter: {}", e),
+            Error::Invalid { field, reason } => write!(f, "{} {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl PostFrontmatter {
+    /// Parse the YAML between the `---` lines
+    pub fn from_yaml(yaml: &str) -> Result<Self, Error> {
+        serde_norway::from_str(yaml).map_err(|e| Error::Yaml(e.to_string()))
+    }
+
+    /// Write the frontmatter back as YAML, lists as YAML lists and the date as `YYYY-MM-DD`
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        serde_norway::to_string(self).map_err(|e| Error::Yaml(e.to_string()))
+    }
+
+    /// Check what YAML can't: posts need a title and a date
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.title.trim().is_empty() {
+            return Err(Error::Invalid {
+                field: "title",
+                reason: "is empty",
+            });
+        }
+        if self.date.is_none() {
+            return Err(Error::Invalid {
+                field: "date",
+                reason: "is missing",
+            });
+        }
+        for (field, variants) in [
+            ("title_variants", &self.title_variants),
+            ("summary_variants", &self.summary_variants),
+        ] {
+            if variants.iter().any(|variant| variant.trim().is_empty()) {
+                return Err(Error::Invalid {
+                    field,
+                    reason: "has an empty variant",
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The date as `YYYY-MM-DD`, empty without a date
+    pub fn date_string(&self) -> String {
+        self.date.map(|date| date.to_string()).unwrap_or_default()
+    }
+
+    /// The categories listed in `category`
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.category
+            .split(',')
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+    }
+
+    /// Whether the post runs an experiment on its title or summary
+    pub fn has_variants(&self) -> bool {
+        !self.title_variants.is_empty() || !self.summary_variants.is_empty()
+    }
+}
+
+/// Split a markdown file into the YAML between its leading `---` lines and the body after them
+pub fn split(raw: &str) -> Option<(&str, &str)> {
+    let mut lines = raw.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim() != "---" {
+        return None;
+    }
+
+    let start = first.len();
+    let mut offset = start;
+    for line in lines {
+        if line.trim() == "---" {
+            return Some((&raw[start..offset], raw[offset + line.len()..].trim()));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Parse the frontmatter of a markdown file and return it with the body
+pub fn parse(raw: &str) -> Result<(PostFrontmatter, &str), Error> {
+    let (yaml, body) = split(raw).ok_or(Error::MissingFrontmatter)?;
+    Ok((PostFrontmatter::from_yaml(yaml)?, body))
+}
+
+/// Slugs (file names without `.md`) and paths of the markdown files in a directory, sorted
+pub fn markdown_files(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files: Vec<(String, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Read a day written as `YYYY-MM-DD` or `YYYYMMDD`
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d"))
+        .ok()
+}
+
+/// (De)serialize the optional date, unquoted `20240101` is a number in YAML
+mod date {
+    use chrono::NaiveDate;
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::Serializer;
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(date: &Option<NaiveDate>, s: S) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => s.collect_str(date),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<NaiveDate>, D::Error> {
+        d.deserialize_option(OptionalDate)
+    }
+
+    struct OptionalDate;
+
+    impl<'de> Visitor<'de> for OptionalDate {
+        type Value = Option<NaiveDate>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a YYYY-MM-DD date")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+            d.deserialize_any(Day).map(Some)
+        }
+    }
+
+    struct Day;
+
+    impl Visitor<'_> for Day {
+        type Value = NaiveDate;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a YYYY-MM-DD date")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<NaiveDate, E> {
+            super::parse_date(value)
+                .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<NaiveDate, E> {
+            self.visit_str(&value.to_string())
+        }
+    }
+}
+
+/// A YAML list of strings, or a single string with the items separated by commas
+fn list<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        Items(Vec<String>),
+        Commas(String),
+        Empty(()),
+    }
+
+    let items = match List::deserialize(d)? {
+        List::Items(items) => items,
+        List::Commas(value) => value.split(',').map(str::to_string).collect(),
+        List::Empty(()) => Vec::new(),
+    };
+    Ok(items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_post() {
+        let raw = "---\ntitle: \"Hello: World\"\ndate: 20240101\ntags: rust, wasm\ncategory: code, make\npublished: false\ntitle_variants: [\"Hi, there\", 'Quiet']\n---\n\nBody text\n";
+        let (frontmatter, body) = parse(raw).unwrap();
+
+        assert_eq!(frontmatter.title, "Hello: World");
+        assert_eq!(frontmatter.date_string(), "2024-01-01");
+        assert_eq!(frontmatter.tags, ["rust", "wasm"]);
+        assert_eq!(
+            frontmatter.categories().collect::<Vec<_>>(),
+            ["code", "make"]
+        );
+        assert!(!frontmatter.published);
+        assert_eq!(frontmatter.title_variants, ["Hi, there", "Quiet"]);
+        assert!(frontmatter.has_variants());
+        assert_eq!(body, "Body text");
+        assert!(frontmatter.validate().is_ok());
+    }
+
+    #[test]
+    fn test_multiline_values() {
+        let yaml = "title: Lines\ndate: 2024-01-01\nsummary: \"First line\n  second line\n\n  new paragraph\"\ntags:\n  - a\n  - b";
+        let frontmatter = PostFrontmatter::from_yaml(yaml).unwrap();
+
+        assert_eq!(frontmatter.summary, "First line second line\nnew paragraph");
+        assert_eq!(frontmatter.tags, ["a", "b"]);
+        assert!(frontmatter.published);
+    }
+
+    #[test]
+    fn test_invalid_frontmatter() {
+        assert_eq!(
+            parse("No frontmatter").unwrap_err(),
+            Error::MissingFrontmatter
+        );
+        assert!(matches!(
+            PostFrontmatter::from_yaml("title: Bad date\ndate: yesterday"),
+            Err(Error::Yaml(_))
+        ));
+        assert!(matches!(
+            PostFrontmatter::from_yaml("date: 2024-01-01"),
+            Err(Error::Yaml(_))
+        ));
+
+        let frontmatter = PostFrontmatter::from_yaml("title: No date").unwrap();
+        assert_eq!(
+            frontmatter.validate(),
+            Err(Error::Invalid {
+                field: "date",
+                reason: "is missing"
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let (frontmatter, _) = parse("---\ntitle: Json\ndate: 2024-01-01\n---\n").unwrap();
+        let json = serde_json::to_string(&frontmatter).unwrap();
+        assert!(json.contains("\"date\":\"2024-01-01\""));
+        assert_eq!(
+            serde_json::from_str::<PostFrontmatter>(&json).unwrap(),
+            frontmatter
+        );
+    }
+
+    /// Every post in the repository parses, validates and survives being written back
+    #[test]
+    fn test_round_trip_content_posts() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../content/posts");
+        let files = markdown_files(&dir).unwrap();
+        assert!(!files.is_empty());
+
+        for (slug, path) in files {
+            let raw = fs::read_to_string(&path).unwrap();
+            let (frontmatter, body) = parse(&raw).unwrap_or_else(|e| panic!("{}: {}", slug, e));
+            frontmatter
+                .validate()
+                .unwrap_or_else(|e| panic!("{}: {}", slug, e));
+            assert!(!body.is_empty(), "{} has no body", slug);
+
+            let yaml = frontmatter.to_yaml().unwrap();
+            let reparsed = PostFrontmatter::from_yaml(&yaml).unwrap();
+            assert_eq!(reparsed, frontmatter, "{} changed after a round trip", slug);
+        }
+    }
+}
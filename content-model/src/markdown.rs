@@ -0,0 +1,300 @@
+//! The markdown pipeline of posts and pages: HTML rendering with the site's code blocks, and the
+//! splitting of the markdown around embedded components such as `<Image path="..." />`. The
+//! frontend renders the components with Yew, meta-gen replaces them with static HTML.
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use std::collections::HashMap;
+
+// Lazy-compiled regex patterns for better performance
+// These are compiled once when first accessed and reused across all calls
+
+/// Regex to match self-closing component tags
+/// Pattern explanation:
+/// - `<([A-Z][a-zA-Z0-9]*)` - Component name starting with uppercase (capture group 1)
+/// - `((?:\s+[a-zA-Z_][a-zA-Z0-9_]*\s*=\s*(?:'[^']*'|"[^"]*"))*)?` - Optional attributes (capture group 2)
+/// - `\s*/>` - Optional whitespace and closing />
+static COMPONENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"<([A-Z][a-zA-Z0-9]*)((?:\s+[a-zA-Z_][a-zA-Z0-9_]*\s*=\s*(?:'[^']*'|"[^"]*"))*)\s*/>"#,
+    )
+    .expect("Component regex pattern should be valid")
+});
+
+/// Regex to match individual attributes within component tags
+/// Matches: attr="value" or attr='value'
+/// - `([a-zA-Z_][a-zA-Z0-9_]*)` - Attribute name (capture group 1)
+/// - `\s*=\s*` - Equals sign with optional whitespace
+/// - `(?:"([^"]*)"|'([^']*)')` - Quoted value, either double (group 2) or single (group 3) quotes
+static ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([a-zA-Z_][a-zA-Z0-9_]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .expect("Attribute regex pattern should be valid")
+});
+
+static COPY_BUTTON_SVG: &str = r#"
+    <button class="copy-code-button" aria-label="Copy code to clipboard" title="Copy code">
+        <svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+        <rect x="9" y="9" width="13" height="13" rx="2" ry="2"></rect>
+        <path d="M5 15H4a2 2 0 0 1-2-2V4a2 2 0 0 1 2-2h9a2 2 0 0 1 2 2v1"></path>
+        </svg>
+    </button>"#;
+
+fn get_options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options
+}
+
+/// Render markdown as the site shows it, with Prism classes and a copy button on code blocks
+pub fn render_markdown_to_html(markdown: &str) -> String {
+    render(markdown, COPY_BUTTON_SVG)
+}
+
+/// Render markdown like [`render_markdown_to_html`], without the copy buttons that only work
+/// with the site's scripts, e.g. for feed readers
+pub fn render_markdown_to_static_html(markdown: &str) -> String {
+    render(markdown, "")
+}
+
+fn render(markdown: &str, copy_button: &str) -> String {
+    let options = get_options();
+
+    let parser = Parser::new_ext(markdown, options);
+    let mut html_output = String::new();
+
+    // Process events and add Prism classes to code blocks
+    let mut events = Vec::new();
+
+    for event in parser {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
+                let code_language = if lang.is_empty() {
+                    "text".to_string()
+                } else {
+                    lang.to_string()
+                };
+                events.push(Event::Html(
+                    format!(
+                        r#"<div class="code-block-wrapper">{}<pre class="language-{}"><code class="language-{}">"#,
+                        copy_button, code_language, code_language
+                    )
+                    .into(),
+                ));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                events.push(Event::Html("</code></pre></div>".into()));
+            }
+            _ => {
+                events.push(event);
+            }
+        }
+    }
+
+    html::push_html(&mut html_output, events.into_iter());
+    html_output
+}
+
+// Structure to hold markdown content parts and component positions
+#[derive(Clone)]
+pub struct MarkdownPart {
+    pub content: String,
+    pub is_component: bool,
+    pub component_name: Option<String>,
+    pub attributes: HashMap<String, String>,
+}
+
+// Parse attributes from a component tag string using regex
+fn parse_component_attributes(tag_content: &str) -> (String, HashMap<String, String>) {
+    let mut attributes = HashMap::new();
+    let parts: Vec<&str> = tag_content.split_whitespace().collect();
+
+    if parts.is_empty() {
+        return (String::new(), attributes);
+    }
+
+    let component_name = parts[0].to_string();
+
+    // Use lazy static regex to parse attributes more robustly
+    // Join the remaining parts back into a string for regex parsing
+    let attr_string = if parts.len() > 1 {
+        parts[1..].join(" ")
+    } else {
+        String::new()
+    };
+
+    for cap in ATTRIBUTE_REGEX.captures_iter(&attr_string) {
+        let key = cap.get(1).unwrap().as_str().to_string();
+        // Check which quote group matched (group 2 for double quotes, group 3 for single quotes)
+        let value = if let Some(double_quoted) = cap.get(2) {
+            double_quoted.as_str().to_string()
+        } else if let Some(single_quoted) = cap.get(3) {
+            single_quoted.as_str().to_string()
+        } else {
+            String::new()
+        };
+
+        attributes.insert(key, value);
+    }
+
+    (component_name, attributes)
+}
+
+/// Parse markdown with component tags using regex and return structured parts
+///
+/// This function uses regex to detect component tags anywhere in the markdown content,
+/// not just on separate lines. It supports:
+/// - Simple components: `<ComponentName />`
+/// - Components with attributes: `<ComponentName attr="value" />`
+/// - Inline components within paragraphs
+/// - Multiple components on the same line
+///
+/// The regex pattern matches:
+/// - Component names starting with uppercase letter: `[A-Z][a-zA-Z0-9]*`
+/// - Optional attributes with quoted values: `attr="value"` or `attr='value'`
+/// - Self-closing syntax: `/>`
+pub fn parse_markdown_with_components(markdown: &str) -> Vec<MarkdownPart> {
+    let mut parts = Vec::new();
+    let mut last_end = 0;
+
+    // Use the lazy-compiled regex for better performance
+    // Find all component matches
+    for component_match in COMPONENT_REGEX.find_iter(markdown) {
+        let start = component_match.start();
+        let end = component_match.end();
+
+        // Add any markdown content before this component
+        if start > last_end {
+            let content = &markdown[last_end..start];
+            if !content.trim().is_empty() {
+                parts.push(MarkdownPart {
+                    content: content.to_string(),
+                    is_component: false,
+                    component_name: None,
+                    attributes: HashMap::new(),
+                });
+            }
+        }
+
+        // Parse the component
+        let full_tag = component_match.as_str();
+        let tag_content = &full_tag[1..full_tag.len() - 2]; // Remove < and />
+        let (component_name, attributes) = parse_component_attributes(tag_content);
+
+        parts.push(MarkdownPart {
+            content: String::new(),
+            is_component: true,
+            component_name: Some(component_name),
+            attributes,
+        });
+
+        last_end = end;
+    }
+
+    // Add any remaining markdown content after the last component
+    if last_end < markdown.len() {
+        let content = &markdown[last_end..];
+        if !content.trim().is_empty() {
+            parts.push(MarkdownPart {
+                content: content.to_string(),
+                is_component: false,
+                component_name: None,
+                attributes: HashMap::new(),
+            });
+        }
+    }
+
+    // If no components were found, add the entire markdown as content
+    if parts.is_empty() && !markdown.trim().is_empty() {
+        parts.push(MarkdownPart {
+            content: markdown.to_string(),
+            is_component: false,
+            component_name: None,
+            attributes: HashMap::new(),
+        });
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_component_detection() {
+        let markdown = "# Hello\n\n<Technologies />\n\nMore content";
+        let parts = parse_markdown_with_components(markdown);
+
+        assert_eq!(parts.len(), 3);
+        assert!(!parts[0].is_component);
+        assert_eq!(parts[0].content.trim(), "# Hello");
+
+        assert!(parts[1].is_component);
+        assert_eq!(parts[1].component_name.as_ref().unwrap(), "Technologies");
+        assert!(parts[1].attributes.is_empty());
+
+        assert!(!parts[2].is_component);
+        assert_eq!(parts[2].content.trim(), "More content");
+    }
+
+    #[test]
+    fn test_component_with_attributes() {
+        let markdown = r#"Before <Technologies type="tools" /> After"#;
+        let parts = parse_markdown_with_components(markdown);
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].content.trim(), "Before");
+
+        assert!(parts[1].is_component);
+        assert_eq!(parts[1].component_name.as_ref().unwrap(), "Technologies");
+        assert_eq!(parts[1].attributes.get("type").unwrap(), "tools");
+
+        assert_eq!(parts[2].content.trim(), "After");
+    }
+
+    #[test]
+    fn test_multiple_components_same_line() {
+        let markdown = r#"<Certifications /> and <OnlinePlaces />"#;
+        let parts = parse_markdown_with_components(markdown);
+
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].is_component);
+        assert_eq!(parts[0].component_name.as_ref().unwrap(), "Certifications");
+
+        assert_eq!(parts[1].content.trim(), "and");
+
+        assert!(parts[2].is_component);
+        assert_eq!(parts[2].component_name.as_ref().unwrap(), "OnlinePlaces");
+    }
+
+    #[test]
+    fn test_component_attribute_parsing() {
+        let (name, attrs) = parse_component_attributes(r#"Technologies type="languages""#);
+        assert_eq!(name, "Technologies");
+        assert_eq!(attrs.get("type").unwrap(), "languages");
+
+        let (name, attrs) = parse_component_attributes("Posts featured_only='true'");
+        assert_eq!(name, "Posts");
+        assert_eq!(attrs.get("featured_only").unwrap(), "true");
+
+        let (name, attrs) =
+            parse_component_attributes(r#"MyComponent attr1="value1" attr2='value2'"#);
+        assert_eq!(name, "MyComponent");
+        assert_eq!(attrs.get("attr1").unwrap(), "value1");
+        assert_eq!(attrs.get("attr2").unwrap(), "value2");
+    }
+
+    #[test]
+    fn test_static_html_has_no_copy_button() {
+        let markdown = "```rust\nfn main() {}\n```";
+        assert!(render_markdown_to_html(markdown).contains("copy-code-button"));
+
+        let html = render_markdown_to_static_html(markdown);
+        assert!(!html.contains("copy-code-button"));
+        assert!(html.contains(r#"<pre class="language-rust"><code class="language-rust">"#));
+    }
+}
@@ -0,0 +1,212 @@
+//! schema.org JSON-LD of the site and its posts, written into the static sidecars by meta-gen and
+//! into the document head by the frontend, so both describe a page the same way.
+
+use serde_json::{json, Value};
+
+use crate::PostFrontmatter;
+
+/// Name of the site, the `WebSite` and publisher of every post
+pub const SITE_NAME: &str = "gertjanassies.dev";
+
+/// Owner of the site, and the author of posts that don't name one
+pub const AUTHOR: &str = "Gertjan Assies";
+
+/// Id of the script element holding the JSON-LD, which the frontend replaces on navigation
+pub const SCRIPT_ID: &str = "structured-data";
+
+fn author(base_url: &str, name: &str) -> Value {
+    if name.is_empty() || name == AUTHOR {
+        json!({ "@type": "Person", "name": AUTHOR, "url": format!("{}/about", base_url) })
+    } else {
+        json!({ "@type": "Person", "name": name })
+    }
+}
+
+/// `BlogPosting` of a post, with the absolute URL of its (fallback) image and its word count
+pub fn blog_posting(
+    base_url: &str,
+    slug: &str,
+    frontmatter: &PostFrontmatter,
+    image: &str,
+    word_count: usize,
+) -> Value {
+    let url = format!("{}/post/{}", base_url, slug);
+    let mut posting = json!({
+        "@type": "BlogPosting",
+        "@id": format!("{}#article", url),
+        "mainEntityOfPage": url,
+        "url": url,
+        "headline": frontmatter.title,
+        "description": frontmatter.summary,
+        "author": author(base_url, &frontmatter.author),
+        "publisher": author(base_url, AUTHOR),
+        "image": image,
+        "keywords": frontmatter.tags,
+        "wordCount": word_count,
+        "inLanguage": "en",
+    });
+    if let Some(date) = frontmatter.date {
+        posting["datePublished"] = json!(date.to_string());
+        posting["dateModified"] = json!(date.to_string());
+    }
+    let categories: Vec<&str> = frontmatter.categories().collect();
+    if !categories.is_empty() {
+        posting["articleSection"] = json!(categories);
+    }
+    posting
+}
+
+/// `Person` of the site's author, `same_as` lists their profiles elsewhere
+pub fn person(base_url: &str, same_as: &[&str]) -> Value {
+    json!({
+        "@type": "Person",
+        "@id": format!("{}/about#person", base_url),
+        "name": AUTHOR,
+        "url": format!("{}/about", base_url),
+        "sameAs": same_as,
+    })
+}
+
+/// `WebSite` with a `SearchAction` for the search page
+pub fn website(base_url: &str) -> Value {
+    json!({
+        "@type": "WebSite",
+        "@id": format!("{}/#website", base_url),
+        "name": SITE_NAME,
+        "url": format!("{}/", base_url),
+        "inLanguage": "en",
+        "potentialAction": {
+            "@type": "SearchAction",
+            "target": {
+                "@type": "EntryPoint",
+                "urlTemplate": format!("{}/search?q={{search_term_string}}", base_url),
+            },
+            "query-input": "required name=search_term_string",
+        },
+    })
+}
+
+/// `BreadcrumbList` of the name and path of each step, from the home page down
+pub fn breadcrumbs(base_url: &str, trail: &[(&str, &str)]) -> Value {
+    let items: Vec<Value> = trail
+        .iter()
+        .enumerate()
+        .map(|(i, (name, path))| {
+            json!({
+                "@type": "ListItem",
+                "position": i + 1,
+                "name": name,
+                "item": format!("{}{}", base_url, path),
+            })
+        })
+        .collect();
+    json!({ "@type": "BreadcrumbList", "itemListElement": items })
+}
+
+/// JSON-LD of a markdown page in `content/pages`: the site on the home page, the author on the
+/// about page and breadcrumbs on the others
+pub fn page(base_url: &str, slug: &str, same_as: &[&str]) -> Value {
+    let path = format!("/{}", slug);
+    let mut name = slug.to_string();
+    if let Some(first) = name.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    let trail = breadcrumbs(base_url, &[("Home", "/"), (&name, &path)]);
+
+    let nodes = match slug {
+        "home" => vec![website(base_url)],
+        "about" => vec![person(base_url, same_as), trail],
+        _ => vec![trail],
+    };
+    graph(nodes)
+}
+
+/// One JSON-LD document holding the nodes describing a page
+pub fn graph(nodes: Vec<Value>) -> Value {
+    json!({ "@context": "https://schema.org", "@graph": nodes })
+}
+
+/// The document as JSON that can't end the script element it is embedded in
+pub fn to_script_json(document: &Value) -> String {
+    document.to_string().replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blog_posting() {
+        let frontmatter = PostFrontmatter::from_yaml(
+            "title: Hello\ndate: 2024-01-01\nsummary: Hi\ntags: [rust, wasm]\ncategory: code, make",
+        )
+        .unwrap();
+        let posting = blog_posting(
+            "https://example.com",
+            "hello",
+            &frontmatter,
+            "https://example.com/og.png",
+            120,
+        );
+
+        assert_eq!(posting["headline"], "Hello");
+        assert_eq!(posting["url"], "https://example.com/post/hello");
+        assert_eq!(posting["datePublished"], "2024-01-01");
+        assert_eq!(posting["author"]["name"], AUTHOR);
+        assert_eq!(posting["author"]["url"], "https://example.com/about");
+        assert_eq!(posting["keywords"], json!(["rust", "wasm"]));
+        assert_eq!(posting["articleSection"], json!(["code", "make"]));
+        assert_eq!(posting["wordCount"], 120);
+
+        let guest = PostFrontmatter {
+            author: "Someone Else".to_string(),
+            ..Default::default()
+        };
+        let posting = blog_posting("https://example.com", "guest", &guest, "", 0);
+        assert_eq!(
+            posting["author"],
+            json!({ "@type": "Person", "name": "Someone Else" })
+        );
+        assert!(posting.get("datePublished").is_none());
+    }
+
+    #[test]
+    fn test_graph_and_script_json() {
+        let document = graph(vec![
+            website("https://example.com"),
+            breadcrumbs("https://example.com", &[("Home", "/"), ("About", "/about")]),
+        ]);
+        assert_eq!(document["@context"], "https://schema.org");
+        assert_eq!(
+            document["@graph"][0]["potentialAction"]["target"]["urlTemplate"],
+            "https://example.com/search?q={search_term_string}"
+        );
+        assert_eq!(document["@graph"][1]["itemListElement"][1]["position"], 2);
+        assert_eq!(
+            document["@graph"][1]["itemListElement"][1]["item"],
+            "https://example.com/about"
+        );
+
+        let about = page(
+            "https://example.com",
+            "about",
+            &["https://github.com/example"],
+        );
+        assert_eq!(
+            about["@graph"][0]["sameAs"][0],
+            "https://github.com/example"
+        );
+        assert_eq!(about["@graph"][1]["itemListElement"][1]["name"], "About");
+        assert_eq!(
+            page("https://example.com", "home", &[])["@graph"][0]["@type"],
+            "WebSite"
+        );
+
+        let json = to_script_json(&json!({ "headline": "</script><script>" }));
+        assert!(!json.contains("</script>"));
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap()["headline"],
+            "</script><script>"
+        );
+    }
+}
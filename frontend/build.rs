@@ -10,28 +10,30 @@ fn main() {
     // Path to the posts directory
     let posts_dir = Path::new("../content/posts");
 
-    let mut slugs = Vec::new();
-
-    if posts_dir.exists() {
-        // Read all .md files from the posts directory
-        if let Ok(entries) = fs::read_dir(posts_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(extension) = path.extension() {
-                    if extension == "md" {
-                        if let Some(file_stem) = path.file_stem() {
-                            if let Some(slug) = file_stem.to_str() {
-                                slugs.push(slug.to_string());
-                            }
-                        }
-                    }
-                }
-            }
+    let files = if posts_dir.exists() {
+        content_model::markdown_files(posts_dir).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    // Fail the build on a post the frontend couldn't show properly
+    let mut invalid = Vec::new();
+    for (slug, path) in &files {
+        let raw = fs::read_to_string(path).unwrap();
+        let checked =
+            content_model::parse(&raw).and_then(|(frontmatter, _)| frontmatter.validate());
+        if let Err(e) = checked {
+            invalid.push(format!("{}: {}", slug, e));
         }
     }
+    if !invalid.is_empty() {
+        panic!(
+            "Invalid frontmatter in content/posts:\n{}",
+            invalid.join("\n")
+        );
+    }
 
-    // Sort slugs for consistent ordering
-    slugs.sort();
+    let slugs: Vec<&str> = files.iter().map(|(slug, _)| slug.as_str()).collect();
 
     // Generate the Rust code
     let mut file = fs::File::create(&dest_path).unwrap();
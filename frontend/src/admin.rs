@@ -0,0 +1,25 @@
+use web_sys::window;
+
+/// localStorage key holding the token of the admin endpoints
+const ADMIN_TOKEN_KEY: &str = "admin_token";
+
+/// The admin token stored on this device, if any
+pub fn admin_token() -> Option<String> {
+    window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(ADMIN_TOKEN_KEY).ok().flatten())
+        .filter(|token| !token.is_empty())
+}
+
+/// Store (or forget) the admin token in localStorage
+pub fn set_admin_token(token: Option<&str>) {
+    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+        let result = match token {
+            Some(token) => storage.set_item(ADMIN_TOKEN_KEY, token),
+            None => storage.remove_item(ADMIN_TOKEN_KEY),
+        };
+        if let Err(e) = result {
+            web_sys::console::error_1(&format!("Failed to store admin token: {:?}", e).into());
+        }
+    }
+}
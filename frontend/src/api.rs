@@ -0,0 +1,124 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, RequestMode, Response};
+
+/// Base path of the versioned backend API
+pub const API_BASE: &str = "/api/v1";
+
+/// JSON body the backend returns for every failed request
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApiError {
+    pub code: String,
+    pub message: String,
+    pub request_id: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (request id {})", self.message, self.request_id)
+    }
+}
+
+/// GET a JSON resource from the API
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, String> {
+    fetch_json(url, "GET", None, None).await
+}
+
+/// GET a JSON resource from an admin endpoint, sending the admin token as bearer token
+pub async fn get_admin_json<T: DeserializeOwned>(url: &str, token: &str) -> Result<T, String> {
+    fetch_json(url, "GET", None, Some(token)).await
+}
+
+/// POST a JSON body to the API and parse the JSON response
+pub async fn post_json<T: DeserializeOwned>(
+    url: &str,
+    payload: &serde_json::Value,
+) -> Result<T, String> {
+    fetch_json(url, "POST", Some(payload.to_string()), None).await
+}
+
+/// POST a JSON body to the API without waiting for the answer. The request is sent with
+/// `keepalive`, so it still arrives when the page is left or reloaded right after.
+pub fn send_json(url: &str, payload: &serde_json::Value) -> Result<(), String> {
+    let window = web_sys::window().ok_or("No global window exists")?;
+
+    let opts = RequestInit::new();
+    opts.set_method("POST");
+    opts.set_mode(RequestMode::SameOrigin);
+    opts.set_body(&wasm_bindgen::JsValue::from_str(&payload.to_string()));
+    // Not exposed by web-sys' RequestInit yet
+    web_sys::js_sys::Reflect::set(&opts, &"keepalive".into(), &true.into())
+        .map_err(|e| format!("Failed to set keepalive: {:?}", e))?;
+
+    let headers =
+        web_sys::Headers::new().map_err(|e| format!("Failed to create headers: {:?}", e))?;
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(|e| format!("Failed to set headers: {:?}", e))?;
+    opts.set_headers(&headers);
+
+    let request = Request::new_with_str_and_init(url, &opts)
+        .map_err(|e| format!("Failed to create request: {:?}", e))?;
+    // The promise is dropped, failures only show up in the browser's network log
+    let _ = window.fetch_with_request(&request);
+    Ok(())
+}
+
+async fn fetch_json<T: DeserializeOwned>(
+    url: &str,
+    method: &str,
+    body: Option<String>,
+    token: Option<&str>,
+) -> Result<T, String> {
+    let window = web_sys::window().ok_or("No global window exists")?;
+
+    let opts = RequestInit::new();
+    opts.set_method(method);
+    opts.set_mode(RequestMode::SameOrigin);
+
+    let headers =
+        web_sys::Headers::new().map_err(|e| format!("Failed to create headers: {:?}", e))?;
+    if let Some(token) = token {
+        headers
+            .set("Authorization", &format!("Bearer {}", token))
+            .map_err(|e| format!("Failed to set headers: {:?}", e))?;
+    }
+    if let Some(body) = body {
+        headers
+            .set("Content-Type", "application/json")
+            .map_err(|e| format!("Failed to set headers: {:?}", e))?;
+        opts.set_body(&wasm_bindgen::JsValue::from_str(&body));
+    }
+    opts.set_headers(&headers);
+
+    let request = Request::new_with_str_and_init(url, &opts)
+        .map_err(|e| format!("Failed to create request: {:?}", e))?;
+
+    let resp_value = JsFuture::from(window.fetch_with_request(&request))
+        .await
+        .map_err(|e| format!("Network error: {:?}", e))?;
+
+    let resp: Response = resp_value
+        .dyn_into()
+        .map_err(|e| format!("Failed to cast to Response: {:?}", e))?;
+
+    let json_promise = resp
+        .json()
+        .map_err(|e| format!("Failed to read response: {:?}", e))?;
+    let json = JsFuture::from(json_promise).await;
+
+    if !resp.ok() {
+        // Surface the message from the backend's error body when there is one
+        return Err(json
+            .ok()
+            .and_then(|json| serde_wasm_bindgen::from_value::<ApiError>(json).ok())
+            .map(|error| error.to_string())
+            .unwrap_or_else(|| format!("HTTP error: {}", resp.status())));
+    }
+
+    let json = json.map_err(|e| format!("Failed to parse JSON: {:?}", e))?;
+    serde_wasm_bindgen::from_value::<T>(json)
+        .map_err(|e| format!("Failed to deserialize response: {:?}", e))
+}
@@ -5,6 +5,9 @@ use crate::components::header::Header;
 use crate::components::homepage::HomePage;
 use crate::components::notfoundpage::NotFoundPage;
 use crate::components::posts::PostView;
+use crate::components::privacypage::PrivacyPage;
+use crate::components::searchpage::SearchPage;
+use crate::components::statspage::StatsPage;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -16,6 +19,12 @@ pub enum Route {
     Blog,
     #[at("/about")]
     About,
+    #[at("/privacy")]
+    Privacy,
+    #[at("/search")]
+    Search,
+    #[at("/stats")]
+    Stats,
     #[at("/post/:slug")]
     Post { slug: String },
     #[not_found]
@@ -28,6 +37,9 @@ fn switch(routes: Route) -> Html {
         Route::Home => html! { <HomePage /> },
         Route::Blog => html! { <BlogPage /> },
         Route::About => html! { <AboutPage /> },
+        Route::Privacy => html! { <PrivacyPage /> },
+        Route::Search => html! { <SearchPage /> },
+        Route::Stats => html! { <StatsPage /> },
         Route::Post { slug } => html! {
             <PostView slug={slug} />
         },
@@ -1,6 +1,7 @@
 pub mod aboutpage;
 pub mod blogpage;
 pub mod certifications;
+pub mod charts;
 pub mod footer;
 pub mod header;
 pub mod homepage;
@@ -10,6 +11,9 @@ pub mod onlineplaces;
 pub mod page;
 pub mod page_stats_display;
 pub mod posts;
+pub mod privacypage;
+pub mod searchpage;
+pub mod statspage;
 
 pub mod technologies;
 
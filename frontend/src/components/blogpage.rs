@@ -1,3 +1,4 @@
+use super::page::page_structured_data;
 use super::Page;
 use crate::hooks::{use_meta_tags, MetaData};
 use yew::prelude::*;
@@ -7,6 +8,7 @@ pub fn blog_page() -> Html {
     let meta_data = MetaData {
         title: "Blog - gertjanassies.dev".to_string(),
         url: Some("https://gertjanassies.dev/blog".to_string()),
+        structured_data: Some(page_structured_data("blog")),
         ..Default::default()
     };
     use_meta_tags(meta_data);
@@ -0,0 +1,112 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct BarChartProps {
+    pub values: Vec<u64>,
+    /// Label of each bar, shown as its tooltip
+    pub labels: Vec<String>,
+    #[prop_or(600)]
+    pub width: u32,
+    #[prop_or(160)]
+    pub height: u32,
+}
+
+/// Bar chart drawn as inline SVG, scaled to the largest value
+#[function_component(BarChart)]
+pub fn bar_chart(props: &BarChartProps) -> Html {
+    let max = props.values.iter().copied().max().unwrap_or(0);
+    let slot = f64::from(props.width) / props.values.len().max(1) as f64;
+    let bar_width = (slot * 0.8).max(1.0);
+    let height = f64::from(props.height);
+
+    html! {
+        <svg class="chart bar-chart" role="img"
+            viewBox={format!("0 0 {} {}", props.width, props.height)}
+            preserveAspectRatio="none">
+            { for props.values.iter().enumerate().map(|(i, value)| {
+                let bar_height = scale(*value, max, height);
+                let label = props.labels.get(i).cloned().unwrap_or_default();
+                html! {
+                    <rect
+                        x={format!("{:.1}", i as f64 * slot + (slot - bar_width) / 2.0)}
+                        y={format!("{:.1}", height - bar_height)}
+                        width={format!("{:.1}", bar_width)}
+                        height={format!("{:.1}", bar_height)}>
+                        <title>{ format!("{}: {}", label, value) }</title>
+                    </rect>
+                }
+            }) }
+        </svg>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SparklineProps {
+    pub values: Vec<u64>,
+    #[prop_or(100)]
+    pub width: u32,
+    #[prop_or(24)]
+    pub height: u32,
+}
+
+/// Small line chart of a series, for use inside table cells and text
+#[function_component(Sparkline)]
+pub fn sparkline(props: &SparklineProps) -> Html {
+    let points = sparkline_points(
+        &props.values,
+        f64::from(props.width),
+        f64::from(props.height),
+    );
+
+    html! {
+        <svg class="chart sparkline" role="img"
+            width={props.width.to_string()} height={props.height.to_string()}
+            viewBox={format!("0 0 {} {}", props.width, props.height)}>
+            <polyline points={points} fill="none" />
+        </svg>
+    }
+}
+
+/// Height of a value in a chart of `height` whose tallest value is `max`
+fn scale(value: u64, max: u64, height: f64) -> f64 {
+    if max == 0 {
+        0.0
+    } else {
+        value as f64 / max as f64 * height
+    }
+}
+
+/// SVG `points` of a line through the values, spread over the width with the largest
+/// value at the top
+fn sparkline_points(values: &[u64], width: f64, height: f64) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    let step = width / values.len().saturating_sub(1).max(1) as f64;
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            format!(
+                "{:.1},{:.1}",
+                i as f64 * step,
+                height - scale(*value, max, height)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline_points() {
+        assert_eq!(
+            sparkline_points(&[0, 5, 10], 100.0, 20.0),
+            "0.0,20.0 50.0,10.0 100.0,0.0"
+        );
+        assert_eq!(sparkline_points(&[0, 0], 10.0, 10.0), "0.0,10.0 10.0,10.0");
+        assert_eq!(sparkline_points(&[], 10.0, 10.0), "");
+    }
+}
@@ -1,7 +1,21 @@
+use crate::app::Route;
+use crate::privacy::{is_stats_opt_out, set_stats_opt_out};
 use yew::prelude::*;
+use yew_router::prelude::*;
 
 #[function_component(Footer)]
 pub fn footer() -> Html {
+    let opted_out = use_state(is_stats_opt_out);
+
+    let on_toggle_stats = {
+        let opted_out = opted_out.clone();
+        Callback::from(move |_: MouseEvent| {
+            let opt_out = !*opted_out;
+            set_stats_opt_out(opt_out);
+            opted_out.set(opt_out);
+        })
+    };
+
     html! {
         <footer>
             <div class="footer">
@@ -10,6 +24,18 @@ pub fn footer() -> Html {
             { "This blog is licensed under a " }
             <a rel="license" href="http://creativecommons.org/licenses/by/4.0/"> { "Creative Commons Attribution 4.0 International License." }
             </a>{ " © 2023-2025 by Gertjan Assies" }
+            <br/>
+            <span class="footer-privacy">
+                <Link<Route> to={Route::Privacy}>{ "privacy" }</Link<Route>>
+                { " • " }
+                <button class="stats-opt-out" onclick={on_toggle_stats}>
+                    if *opted_out {
+                        { "Your visits are not counted, count them again" }
+                    } else {
+                        { "Don't count my visits" }
+                    }
+                </button>
+            </span>
             </div>
         </footer>
     }
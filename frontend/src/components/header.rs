@@ -1,9 +1,31 @@
 use crate::app::Route;
+use crate::components::searchpage::SearchParams;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
 #[function_component(Header)]
 pub fn header() -> Html {
+    let navigator = use_navigator();
+    let search_input = use_node_ref();
+
+    let on_search = {
+        let search_input = search_input.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let Some(input) = search_input.cast::<HtmlInputElement>() else {
+                return;
+            };
+            let q = input.value().trim().to_string();
+            if q.is_empty() {
+                return;
+            }
+            if let Some(navigator) = &navigator {
+                let _ = navigator.push_with_query(&Route::Search, &SearchParams { q });
+            }
+        })
+    };
+
     html! {
         <header>
             <div class="header">
@@ -24,6 +46,9 @@ pub fn header() -> Html {
                     <a href="/blog?category=make">{ "make" }</a>
                     <a href="/blog?category=tooling">{ "tooling" }</a>
                     <a href="/blog?category=life">{ "life" }</a>
+                    <form class="header-search" role="search" onsubmit={on_search}>
+                        <input ref={search_input} type="search" name="q" placeholder="search" aria-label="Search posts" />
+                    </form>
                 </nav>
                 </div>
             </div>
@@ -1,3 +1,4 @@
+use crate::events::track_event;
 use crate::traits::MarkdownRenderable;
 use std::collections::HashMap;
 use yew::prelude::*;
@@ -30,9 +31,10 @@ impl Component for Image {
         Self { modal_open: false }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             ImageMsg::OpenModal => {
+                track_event("image_open", &[("image", &ctx.props().path)]);
                 self.modal_open = true;
                 true
             }
@@ -8,50 +8,56 @@ struct OnlinePlace {
     svg: &'static str,
 }
 
+/// Profiles of the author elsewhere, also the `sameAs` of the author's structured data
+const ONLINE_PLACES: &[OnlinePlace] = &[
+    OnlinePlace {
+        name: "LinkedIn",
+        url: "https://www.linkedin.com/in/gertjanassies",
+        svg: r##"<svg xmlns="http://www.w3.org/2000/svg" width="3em" height="3em" viewBox="0 0 256 256"><path d="M218.123 218.127h-37.931v-59.403c0-14.165-.253-32.4-19.728-32.4c-19.756 0-22.779 15.434-22.779 31.369v60.43h-37.93V95.967h36.413v16.694h.51a39.907 39.907 0 0 1 35.928-19.733c38.445 0 45.533 25.288 45.533 58.186l-.016 67.013ZM56.955 79.27c-12.157.002-22.014-9.852-22.016-22.009c-.002-12.157 9.851-22.014 22.008-22.016c12.157-.003 22.014 9.851 22.016 22.008A22.013 22.013 0 0 1 56.955 79.27m18.966 138.858H37.95V95.967h37.97v122.16ZM237.033.018H18.89C8.58-.098.125 8.161-.001 18.471v219.053c.122 10.315 8.576 18.582 18.89 18.474h218.144c10.336.128 18.823-8.139 18.966-18.474V18.454c-.147-10.33-8.635-18.588-18.966-18.453"></path></svg>"##,
+    },
+    OnlinePlace {
+        name: "GitHub",
+        url: "https://github.com/gertjana",
+        svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>GitHub</title><path d="M12 .297c-6.63 0-12 5.373-12 12 0 5.303 3.438 9.8 8.205 11.385.6.113.82-.258.82-.577 0-.285-.01-1.04-.015-2.04-3.338.724-4.042-1.61-4.042-1.61C4.422 18.07 3.633 17.7 3.633 17.7c-1.087-.744.084-.729.084-.729 1.205.084 1.838 1.236 1.838 1.236 1.07 1.835 2.809 1.305 3.495.998.108-.776.417-1.305.76-1.605-2.665-.3-5.466-1.332-5.466-5.93 0-1.31.465-2.38 1.235-3.22-.135-.303-.54-1.523.105-3.176 0 0 1.005-.322 3.3 1.23.96-.267 1.98-.399 3-.405 1.02.006 2.04.138 3 .405 2.28-1.552 3.285-1.23 3.285-1.23.645 1.653.24 2.873.12 3.176.765.84 1.23 1.91 1.23 3.22 0 4.61-2.805 5.625-5.475 5.92.42.36.81 1.096.81 2.22 0 1.606-.015 2.896-.015 3.286 0 .315.21.69.825.57C20.565 22.092 24 17.592 24 12.297c0-6.627-5.373-12-12-12"/></svg>"##,
+    },
+    OnlinePlace {
+        name: "GitLab",
+        url: "https://gitlab.com/gertjana",
+        svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>GitLab</title><path d="m23.6004 9.5927-.0337-.0862L20.3.9814a.851.851 0 0 0-.3362-.405.8748.8748 0 0 0-.9997.0539.8748.8748 0 0 0-.29.4399l-2.2055 6.748H7.5375l-2.2057-6.748a.8573.8573 0 0 0-.29-.4412.8748.8748 0 0 0-.9997-.0537.8585.8585 0 0 0-.3362.4049L.4332 9.5015l-.0325.0862a6.0657 6.0657 0 0 0 2.0119 7.0105l.0113.0087.03.0213 4.976 3.7264 2.462 1.8633 1.4995 1.1321a1.0085 1.0085 0 0 0 1.2197 0l1.4995-1.1321 2.4619-1.8633 5.006-3.7489.0125-.01a6.0682 6.0682 0 0 0 2.0094-7.003z"/></svg>"##,
+    },
+    OnlinePlace {
+        name: "X (formerly Twitter)",
+        url: "https://x.com/major7",
+        svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>X</title><path d="M14.234 10.162 22.977 0h-2.072l-7.591 8.824L7.251 0H.258l9.168 13.343L.258 24H2.33l8.016-9.318L16.749 24h6.993zm-2.837 3.299-.929-1.329L3.076 1.56h3.182l5.965 8.532.929 1.329 7.754 11.09h-3.182z"/></svg>"##,
+    },
+    OnlinePlace {
+        name: "Keybase",
+        url: "https://keybase.io/gertjan",
+        svg: r##"<svg xmlns="http://www.w3.org/2000/svg" width="3em" height="3em" viewBox="0 0 472 512"><path fill-rule="evenodd" d="M150.308 182.819c-7.814-15.421-10.87-33.354-8.88-52.529c-51.56.435-56.84-20.355-57.715-40.875l1.854-29.93c1.215-19.6 18.697-34.919 38.338-34.919c41.422 1.62 45.335 2.598 56.818 15.284L209.103 0l23.498 13.825c-15.545 33.091-10.105 41.39-10.046 41.458c71.697 1.631 113.497 60.152 89.407 123.388C456.277 233.618 507.277 377.208 446.714 512H415.48c27.055-45.44 34.305-102.158 25.54-153.485c-72.846 107.605-171.848-71.037-366.178 90.307l21.625-67.794l-45.546 48.305c5.46 30.528 17.9 58.664 35.651 82.667H53.58a224.483 224.483 0 0 1-25.328-58.626l-27.2 28.848c-7.765-144.584 26.024-244.466 149.256-299.403zm56.376 273.007c0-18.139-19.77-29.537-35.502-20.468s-15.732 31.866 0 40.935s35.502-2.329 35.502-20.467zm126.412 0c0-18.139-19.771-29.537-35.503-20.468s-15.731 31.866 0 40.935s35.503-2.329 35.503-20.467zM162.187 69.553c-6.145 11.102-11.254 22.234-14.946 33.664l-26.94-1.667c-5.425-.332-9.576-5.017-9.26-10.442l1.854-29.928c.324-5.225 4.658-9.268 9.866-9.268c.197 0 30.513 1.87 30.513 1.87c8.238 1.306 10.363 6.505 8.913 15.771zm73.157 113.457l77.43 95.3c6.872 8.423-5.703 18.684-12.576 10.261l-7.71-9.505l-32.092 26.12l-20.398-24.869l32.225-26.23l-11.242-13.857l-15.472 12.697l-10.374-13.122l15.31-12.564l-27.676-33.97c-6.61-8.1 5.966-18.36 12.575-10.261z" clip-rule="evenodd"/></svg>"##,
+    },
+    OnlinePlace {
+        name: "Threads",
+        url: "https://www.threads.com/@gertjan_assies",
+        svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>Threads</title><path d="M12.186 24h-.007c-3.581-.024-6.334-1.205-8.184-3.509C2.35 18.44 1.5 15.586 1.472 12.01v-.017c.03-3.579.879-6.43 2.525-8.482C5.845 1.205 8.6.024 12.18 0h.014c2.746.02 5.043.725 6.826 2.098 1.677 1.29 2.858 3.13 3.509 5.467l-2.04.569c-1.104-3.96-3.898-5.984-8.304-6.015-2.91.022-5.11.936-6.54 2.717C4.307 6.504 3.616 8.914 3.589 12c.027 3.086.718 5.496 2.057 7.164 1.43 1.783 3.631 2.698 6.54 2.717 2.623-.02 4.358-.631 5.8-2.045 1.647-1.613 1.618-3.593 1.09-4.798-.31-.71-.873-1.3-1.634-1.75-.192 1.352-.622 2.446-1.284 3.272-.886 1.102-2.14 1.704-3.73 1.79-1.202.065-2.361-.218-3.259-.801-1.063-.689-1.685-1.74-1.752-2.964-.065-1.19.408-2.285 1.33-3.082.88-.76 2.119-1.207 3.583-1.291a13.853 13.853 0 0 1 3.02.142c-.126-.742-.375-1.332-.75-1.757-.513-.586-1.308-.883-2.359-.89h-.029c-.844 0-1.992.232-2.721 1.32L7.734 7.847c.98-1.454 2.568-2.256 4.478-2.256h.044c3.194.02 5.097 1.975 5.287 5.388.108.046.216.094.321.142 1.49.7 2.58 1.761 3.154 3.07.797 1.82.871 4.79-1.548 7.158-1.85 1.81-4.094 2.628-7.277 2.65Zm1.003-11.69c-.242 0-.487.007-.739.021-1.836.103-2.98.946-2.916 2.143.067 1.256 1.452 1.839 2.784 1.767 1.224-.065 2.818-.543 3.086-3.71a10.5 10.5 0 0 0-2.215-.221z"/></svg>"##,
+    },
+    OnlinePlace {
+        name: "Thingiverse",
+        url: "https://www.thingiverse.com/major7/",
+        svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>Thingiverse</title><path d="M11.955.005C5.425-.152-.091 5.485.007 11.805c-.235 6.756 5.537 12.25 12.052 12.196C18.621 23.9 23.912 18.595 24 12.03 24.031 5.483 18.505-.18 11.955.005zm-.047 1.701a10.276 10.276 0 0 1 7.36 17.529 10.275 10.275 0 0 1-17.556-7.287C1.71 6.308 6.268 1.728 11.907 1.706zm-5.55 4.781c-.322 0-.358.033-.358.361v2.248c0 .351.04.391.398.391h3.823c.274 0 .274.004.274.265v9.736a.176.176 0 0 0 .051.146c.04.038.093.059.148.053h2.555c.247-.003.283-.035.283-.28v-9.32c0-.124.004-.239 0-.39s.055-.21.218-.21h3.9c.319.004.35-.032.35-.344V6.855c0-.34-.024-.363-.37-.363h-5.626z"/></svg>"##,
+    },
+];
+
+/// URLs of the author's profiles
+pub fn profile_urls() -> Vec<&'static str> {
+    ONLINE_PLACES.iter().map(|place| place.url).collect()
+}
+
 #[function_component(OnlinePlaces)]
 pub fn online_places() -> Html {
-    let online_places: Vec<OnlinePlace> = vec![
-        OnlinePlace {
-            name: "LinkedIn",
-            url: "https://www.linkedin.com/in/gertjanassies",
-            svg: r##"<svg xmlns="http://www.w3.org/2000/svg" width="3em" height="3em" viewBox="0 0 256 256"><path d="M218.123 218.127h-37.931v-59.403c0-14.165-.253-32.4-19.728-32.4c-19.756 0-22.779 15.434-22.779 31.369v60.43h-37.93V95.967h36.413v16.694h.51a39.907 39.907 0 0 1 35.928-19.733c38.445 0 45.533 25.288 45.533 58.186l-.016 67.013ZM56.955 79.27c-12.157.002-22.014-9.852-22.016-22.009c-.002-12.157 9.851-22.014 22.008-22.016c12.157-.003 22.014 9.851 22.016 22.008A22.013 22.013 0 0 1 56.955 79.27m18.966 138.858H37.95V95.967h37.97v122.16ZM237.033.018H18.89C8.58-.098.125 8.161-.001 18.471v219.053c.122 10.315 8.576 18.582 18.89 18.474h218.144c10.336.128 18.823-8.139 18.966-18.474V18.454c-.147-10.33-8.635-18.588-18.966-18.453"></path></svg>"##,
-        },
-        OnlinePlace {
-            name: "GitHub",
-            url: "https://github.com/gertjana",
-            svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>GitHub</title><path d="M12 .297c-6.63 0-12 5.373-12 12 0 5.303 3.438 9.8 8.205 11.385.6.113.82-.258.82-.577 0-.285-.01-1.04-.015-2.04-3.338.724-4.042-1.61-4.042-1.61C4.422 18.07 3.633 17.7 3.633 17.7c-1.087-.744.084-.729.084-.729 1.205.084 1.838 1.236 1.838 1.236 1.07 1.835 2.809 1.305 3.495.998.108-.776.417-1.305.76-1.605-2.665-.3-5.466-1.332-5.466-5.93 0-1.31.465-2.38 1.235-3.22-.135-.303-.54-1.523.105-3.176 0 0 1.005-.322 3.3 1.23.96-.267 1.98-.399 3-.405 1.02.006 2.04.138 3 .405 2.28-1.552 3.285-1.23 3.285-1.23.645 1.653.24 2.873.12 3.176.765.84 1.23 1.91 1.23 3.22 0 4.61-2.805 5.625-5.475 5.92.42.36.81 1.096.81 2.22 0 1.606-.015 2.896-.015 3.286 0 .315.21.69.825.57C20.565 22.092 24 17.592 24 12.297c0-6.627-5.373-12-12-12"/></svg>"##,
-        },
-        OnlinePlace {
-            name: "GitLab",
-            url: "https://gitlab.com/gertjana",
-            svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>GitLab</title><path d="m23.6004 9.5927-.0337-.0862L20.3.9814a.851.851 0 0 0-.3362-.405.8748.8748 0 0 0-.9997.0539.8748.8748 0 0 0-.29.4399l-2.2055 6.748H7.5375l-2.2057-6.748a.8573.8573 0 0 0-.29-.4412.8748.8748 0 0 0-.9997-.0537.8585.8585 0 0 0-.3362.4049L.4332 9.5015l-.0325.0862a6.0657 6.0657 0 0 0 2.0119 7.0105l.0113.0087.03.0213 4.976 3.7264 2.462 1.8633 1.4995 1.1321a1.0085 1.0085 0 0 0 1.2197 0l1.4995-1.1321 2.4619-1.8633 5.006-3.7489.0125-.01a6.0682 6.0682 0 0 0 2.0094-7.003z"/></svg>"##,
-        },
-        OnlinePlace {
-            name: "X (formerly Twitter)",
-            url: "https://x.com/major7",
-            svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>X</title><path d="M14.234 10.162 22.977 0h-2.072l-7.591 8.824L7.251 0H.258l9.168 13.343L.258 24H2.33l8.016-9.318L16.749 24h6.993zm-2.837 3.299-.929-1.329L3.076 1.56h3.182l5.965 8.532.929 1.329 7.754 11.09h-3.182z"/></svg>"##,
-        },
-        OnlinePlace {
-            name: "Keybase",
-            url: "https://keybase.io/gertjan",
-            svg: r##"<svg xmlns="http://www.w3.org/2000/svg" width="3em" height="3em" viewBox="0 0 472 512"><path fill-rule="evenodd" d="M150.308 182.819c-7.814-15.421-10.87-33.354-8.88-52.529c-51.56.435-56.84-20.355-57.715-40.875l1.854-29.93c1.215-19.6 18.697-34.919 38.338-34.919c41.422 1.62 45.335 2.598 56.818 15.284L209.103 0l23.498 13.825c-15.545 33.091-10.105 41.39-10.046 41.458c71.697 1.631 113.497 60.152 89.407 123.388C456.277 233.618 507.277 377.208 446.714 512H415.48c27.055-45.44 34.305-102.158 25.54-153.485c-72.846 107.605-171.848-71.037-366.178 90.307l21.625-67.794l-45.546 48.305c5.46 30.528 17.9 58.664 35.651 82.667H53.58a224.483 224.483 0 0 1-25.328-58.626l-27.2 28.848c-7.765-144.584 26.024-244.466 149.256-299.403zm56.376 273.007c0-18.139-19.77-29.537-35.502-20.468s-15.732 31.866 0 40.935s35.502-2.329 35.502-20.467zm126.412 0c0-18.139-19.771-29.537-35.503-20.468s-15.731 31.866 0 40.935s35.503-2.329 35.503-20.467zM162.187 69.553c-6.145 11.102-11.254 22.234-14.946 33.664l-26.94-1.667c-5.425-.332-9.576-5.017-9.26-10.442l1.854-29.928c.324-5.225 4.658-9.268 9.866-9.268c.197 0 30.513 1.87 30.513 1.87c8.238 1.306 10.363 6.505 8.913 15.771zm73.157 113.457l77.43 95.3c6.872 8.423-5.703 18.684-12.576 10.261l-7.71-9.505l-32.092 26.12l-20.398-24.869l32.225-26.23l-11.242-13.857l-15.472 12.697l-10.374-13.122l15.31-12.564l-27.676-33.97c-6.61-8.1 5.966-18.36 12.575-10.261z" clip-rule="evenodd"/></svg>"##,
-        },
-        OnlinePlace {
-            name: "Threads",
-            url: "https://www.threads.com/@gertjan_assies",
-            svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>Threads</title><path d="M12.186 24h-.007c-3.581-.024-6.334-1.205-8.184-3.509C2.35 18.44 1.5 15.586 1.472 12.01v-.017c.03-3.579.879-6.43 2.525-8.482C5.845 1.205 8.6.024 12.18 0h.014c2.746.02 5.043.725 6.826 2.098 1.677 1.29 2.858 3.13 3.509 5.467l-2.04.569c-1.104-3.96-3.898-5.984-8.304-6.015-2.91.022-5.11.936-6.54 2.717C4.307 6.504 3.616 8.914 3.589 12c.027 3.086.718 5.496 2.057 7.164 1.43 1.783 3.631 2.698 6.54 2.717 2.623-.02 4.358-.631 5.8-2.045 1.647-1.613 1.618-3.593 1.09-4.798-.31-.71-.873-1.3-1.634-1.75-.192 1.352-.622 2.446-1.284 3.272-.886 1.102-2.14 1.704-3.73 1.79-1.202.065-2.361-.218-3.259-.801-1.063-.689-1.685-1.74-1.752-2.964-.065-1.19.408-2.285 1.33-3.082.88-.76 2.119-1.207 3.583-1.291a13.853 13.853 0 0 1 3.02.142c-.126-.742-.375-1.332-.75-1.757-.513-.586-1.308-.883-2.359-.89h-.029c-.844 0-1.992.232-2.721 1.32L7.734 7.847c.98-1.454 2.568-2.256 4.478-2.256h.044c3.194.02 5.097 1.975 5.287 5.388.108.046.216.094.321.142 1.49.7 2.58 1.761 3.154 3.07.797 1.82.871 4.79-1.548 7.158-1.85 1.81-4.094 2.628-7.277 2.65Zm1.003-11.69c-.242 0-.487.007-.739.021-1.836.103-2.98.946-2.916 2.143.067 1.256 1.452 1.839 2.784 1.767 1.224-.065 2.818-.543 3.086-3.71a10.5 10.5 0 0 0-2.215-.221z"/></svg>"##,
-        },
-        OnlinePlace {
-            name: "Thingiverse",
-            url: "https://www.thingiverse.com/major7/",
-            svg: r##"<svg role="img" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><title>Thingiverse</title><path d="M11.955.005C5.425-.152-.091 5.485.007 11.805c-.235 6.756 5.537 12.25 12.052 12.196C18.621 23.9 23.912 18.595 24 12.03 24.031 5.483 18.505-.18 11.955.005zm-.047 1.701a10.276 10.276 0 0 1 7.36 17.529 10.275 10.275 0 0 1-17.556-7.287C1.71 6.308 6.268 1.728 11.907 1.706zm-5.55 4.781c-.322 0-.358.033-.358.361v2.248c0 .351.04.391.398.391h3.823c.274 0 .274.004.274.265v9.736a.176.176 0 0 0 .051.146c.04.038.093.059.148.053h2.555c.247-.003.283-.035.283-.28v-9.32c0-.124.004-.239 0-.39s.055-.21.218-.21h3.9c.319.004.35-.032.35-.344V6.855c0-.34-.024-.363-.37-.363h-5.626z"/></svg>"##,
-        },
-    ];
-
     html! {
         <div class="online-places">
             {
-                for online_places.iter().map(|place| {
+                for ONLINE_PLACES.iter().map(|place| {
                     let icon = Html::from_html_unchecked(place.svg.into());
                     html! {
                         <div class="online-place">
@@ -1,13 +1,20 @@
+use content_model::structured_data;
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
+use super::onlineplaces::profile_urls;
 use super::page_stats_display::PageStatsDisplay;
+use crate::events::track_content_click;
 use crate::hooks::{use_meta_tags, MetaData};
 use crate::markdown::{
     load_markdown_content, parse_markdown_with_components, render_component_by_name,
     render_markdown_to_html,
 };
-use crate::reading_time::calculate_reading_time;
+
+/// JSON-LD of a markdown page, with the author's profiles on the about page
+pub fn page_structured_data(content: &str) -> serde_json::Value {
+    structured_data::page("https://gertjanassies.dev", content, &profile_urls())
+}
 
 #[derive(Properties, PartialEq)]
 pub struct PageProps {
@@ -91,6 +98,7 @@ pub fn page(props: &PageProps) -> Html {
         MetaData {
             title: format!("{} - gertjanassies.dev", props.content),
             url: Some(page_url),
+            structured_data: Some(page_structured_data(&props.content)),
             ..Default::default()
         }
     } else if *loading {
@@ -132,7 +140,7 @@ pub fn page(props: &PageProps) -> Html {
     html! {
         <div class="page">
             if !markdown_parts.is_empty() {
-                <div class="markdown-content">
+                <div class="markdown-content" onclick={track_content_click}>
                     {
                         markdown_parts.iter().map(|part| {
                             if part.is_component {
@@ -151,7 +159,29 @@ pub fn page(props: &PageProps) -> Html {
                 </div>
             }
             // Add page stats display at the bottom
-            <PageStatsDisplay slug={props.content.clone()} track_view={true} reading_time_seconds={calculate_reading_time(&markdown_content) as u32} />
+            <PageStatsDisplay slug={props.content.clone()} kind="page" track_view={true} />
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_structured_data() {
+        let home = page_structured_data("home");
+        assert_eq!(home["@graph"][0]["@type"], "WebSite");
+
+        let about = page_structured_data("about");
+        assert_eq!(about["@graph"][0]["@type"], "Person");
+        assert_eq!(
+            about["@graph"][0]["sameAs"][1],
+            "https://github.com/gertjana"
+        );
+        assert_eq!(about["@graph"][1]["itemListElement"][1]["name"], "About");
+
+        let privacy = page_structured_data("privacy");
+        assert_eq!(privacy["@graph"][0]["@type"], "BreadcrumbList");
+    }
+}
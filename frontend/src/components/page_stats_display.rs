@@ -1,17 +1,19 @@
 use serde::{Deserialize, Serialize};
 use serde_json;
-use serde_wasm_bindgen;
 use std::error::Error;
-use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
-use wasm_bindgen_futures::JsFuture;
 use web_sys::console;
-use web_sys::{Request, RequestInit, RequestMode, Response};
 use yew::prelude::*;
 
+use crate::api::{get_json, post_json, API_BASE};
+use crate::privacy::is_stats_opt_out;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct PageStats {
     pub slug: String,
+    /// `post`, `page` or `note`, stats from before kinds existed are posts
+    #[serde(default)]
+    pub kind: String,
     pub reads: u64,
     pub views: u64,
     pub likes: u64,
@@ -21,65 +23,53 @@ pub struct PageStats {
 #[derive(Properties, PartialEq)]
 pub struct PageStatsDisplayProps {
     pub slug: AttrValue,
+    /// Content kind of the page, `post` for blog posts and `page` for standalone pages
+    #[prop_or(AttrValue::Static("post"))]
+    pub kind: AttrValue,
     #[prop_or(false)]
     pub track_view: bool,
-    #[prop_or(0)]
-    pub reading_time_seconds: u32,
-    #[prop_or(true)]
-    pub published: bool,
 }
 
 #[function_component(PageStatsDisplay)]
 pub fn page_stats_display(props: &PageStatsDisplayProps) -> Html {
     let stats = use_state(|| None::<PageStats>);
     let loading = use_state(|| true);
-    let error = use_state(|| false);
+    let error = use_state(|| None::<String>);
 
-    let slug = props.slug.clone();
-    let track_view = props.track_view;
-    let reading_time_seconds = props.reading_time_seconds;
-    let published = props.published;
+    let page = stats_path(&props.kind, &props.slug);
+    // Readers who opted out in the footer still see stats, their view just isn't counted
+    let track_view = props.track_view && !is_stats_opt_out();
 
     // Load and optionally track view on component mount
     {
         let stats = stats.clone();
         let loading = loading.clone();
         let error = error.clone();
-        let slug = slug.clone();
-
-        use_effect_with(
-            (slug.clone(), track_view, reading_time_seconds, published),
-            move |(slug, track_view, reading_time, published)| {
-                let stats = stats.clone();
-                let loading = loading.clone();
-                let error = error.clone();
-                let slug = slug.clone();
-                let track_view = *track_view;
-                let reading_time = *reading_time;
-                let published = *published;
-
-                spawn_local(async move {
-                    // Only calculate reading time for published articles
-                    let reading_time_for_calc = if published { reading_time } else { 0 };
-
-                    match load_page_stats_from_server(&slug, track_view, reading_time_for_calc)
-                        .await
-                    {
-                        Ok(page_stats) => {
-                            stats.set(Some(page_stats));
-                            loading.set(false);
-                        }
-                        Err(err) => {
-                            console::error_1(&format!("Failed to load page stats: {}", err).into());
-                            error.set(true);
-                            loading.set(false);
-                        }
+        let page = page.clone();
+
+        use_effect_with((page, track_view), move |(page, track_view)| {
+            let stats = stats.clone();
+            let loading = loading.clone();
+            let error = error.clone();
+            let page = page.clone();
+            let track_view = *track_view;
+
+            spawn_local(async move {
+                match load_page_stats_from_server(&page, track_view).await {
+                    Ok(page_stats) => {
+                        stats.set(Some(page_stats));
+                        loading.set(false);
+                    }
+                    Err(err) => {
+                        console::error_1(&format!("Failed to load page stats: {}", err).into());
+                        error.set(Some(err.to_string()));
+                        loading.set(false);
                     }
-                });
+                }
+            });
 
-                || ()
-            },
-        );
+            || ()
+        });
     }
 
     if *loading {
@@ -90,32 +80,29 @@ pub fn page_stats_display(props: &PageStatsDisplayProps) -> Html {
         };
     }
 
-    if *error {
+    if let Some(message) = (*error).as_ref() {
         return html! {
             <div class="page-stats">
-                <span class="stats-error">{"Stats unavailable"}</span>
+                <span class="stats-error" title={message.clone()}>{"Stats unavailable"}</span>
             </div>
         };
     }
 
     match stats.as_ref() {
         Some(page_stats) => {
-            let slug = props.slug.clone();
-            let stats_clone = stats.clone();
-
             let on_like = {
-                let slug = slug.clone();
-                let stats = stats_clone.clone();
+                let page = page.clone();
+                let stats = stats.clone();
                 Callback::from(move |_| {
-                    let slug = slug.clone();
+                    let page = page.clone();
                     let stats = stats.clone();
                     spawn_local(async move {
-                        if let Err(e) = increment_stat(&slug, "likes").await {
+                        if let Err(e) = increment_stat(&page, "likes").await {
                             console::error_1(&format!("Failed to increment likes: {}", e).into());
                         } else {
                             // Reload stats to get updated counts
                             if let Ok(updated_stats) =
-                                load_page_stats_from_server(&slug, false, 0).await
+                                load_page_stats_from_server(&page, false).await
                             {
                                 stats.set(Some(updated_stats));
                             }
@@ -152,7 +139,7 @@ pub fn page_stats_display(props: &PageStatsDisplayProps) -> Html {
 }
 
 // Helper function to format time in a human-readable way
-fn format_time(seconds: u64) -> String {
+pub fn format_time(seconds: u64) -> String {
     if seconds < 60 {
         format!("{}s", seconds)
     } else if seconds < 3600 {
@@ -174,134 +161,42 @@ fn format_time(seconds: u64) -> String {
     }
 }
 
-// Increment a specific stat type
-async fn increment_stat(slug: &str, stat_type: &str) -> Result<PageStats, Box<dyn Error>> {
-    let window = web_sys::window().unwrap();
+/// Path of a page's stats below `/stats`, e.g. `post/my_post`
+pub fn stats_path(kind: &str, slug: &str) -> String {
+    format!("{}/{}", kind, slug)
+}
 
-    let increment_url = format!("/api/stats/{}/increment", slug);
+// Increment a specific stat type
+async fn increment_stat(page: &str, stat_type: &str) -> Result<PageStats, Box<dyn Error>> {
+    let increment_url = format!("{}/stats/{}/increment", API_BASE, page);
 
     // Create JSON payload
     let payload = serde_json::json!({
         "increment_type": stat_type
     });
 
-    let opts = RequestInit::new();
-    opts.set_method("POST");
-    opts.set_mode(RequestMode::SameOrigin);
-
-    // Set content type header
-    let headers = web_sys::Headers::new().unwrap();
-    headers.set("Content-Type", "application/json").unwrap();
-    opts.set_headers(&headers);
-
-    // Set body
-    opts.set_body(&wasm_bindgen::JsValue::from_str(&payload.to_string()));
-
-    let request = Request::new_with_str_and_init(&increment_url, &opts)
-        .map_err(|e| format!("Failed to create request: {:?}", e))?;
-
-    let resp_value = JsFuture::from(window.fetch_with_request(&request))
+    post_json(&increment_url, &payload)
         .await
-        .map_err(|e| format!("Network error: {:?}", e))?;
-
-    let resp: Response = resp_value.dyn_into().unwrap();
-
-    if resp.ok() {
-        let json = JsFuture::from(resp.json().unwrap())
-            .await
-            .map_err(|e| format!("Failed to parse JSON: {:?}", e))?;
-
-        serde_wasm_bindgen::from_value::<PageStats>(json).map_err(|e| -> Box<dyn Error> {
-            format!("Failed to deserialize stats: {:?}", e).into()
-        })
-    } else {
-        Err(format!("Failed to increment {}: HTTP {}", stat_type, resp.status()).into())
-    }
+        .map_err(|e| format!("Failed to increment {}: {}", stat_type, e).into())
 }
 
-// Load page stats from the Rust server API
+// Load page stats from the Rust server API, the reading time is calculated by the server
 async fn load_page_stats_from_server(
-    slug: &str,
+    page: &str,
     track_view: bool,
-    reading_time_seconds: u32,
 ) -> Result<PageStats, Box<dyn Error>> {
-    let window = web_sys::window().unwrap();
-
-    // First, get stats (with optional view tracking)
     let get_url = if track_view {
-        format!("/api/stats/{}?track_view=true", slug)
+        format!("{}/stats/{}?track_view=true", API_BASE, page)
     } else {
-        format!("/api/stats/{}", slug)
+        format!("{}/stats/{}", API_BASE, page)
     };
 
-    let opts = RequestInit::new();
-    opts.set_method("GET");
-    opts.set_mode(RequestMode::SameOrigin);
-
-    let request = Request::new_with_str_and_init(&get_url, &opts)
-        .map_err(|e| format!("Failed to create request: {:?}", e))?;
-
-    let resp_value = JsFuture::from(window.fetch_with_request(&request))
-        .await
-        .map_err(|e| format!("Network error: {:?}", e))?;
-
-    let resp: Response = resp_value.dyn_into().unwrap();
-
-    let mut stats = if resp.ok() {
-        let json = JsFuture::from(resp.json().unwrap())
-            .await
-            .map_err(|e| format!("Failed to parse JSON: {:?}", e))?;
-
-        serde_wasm_bindgen::from_value::<PageStats>(json)
-            .map_err(|e| format!("Failed to deserialize stats: {:?}", e))?
-    } else {
-        console::error_1(&format!("Failed to load stats: HTTP {}", resp.status()).into());
-        PageStats {
-            slug: slug.to_string(),
-            views: 0,
-            reads: 0,
-            likes: 0,
-            time: 0,
-        }
-    };
-
-    // Check if reading time needs to be calculated and saved
-    if stats.time == 0 && reading_time_seconds > 0 {
-        console::log_1(
-            &format!(
-                "Reading time not yet calculated for '{}', calculating and saving {} seconds",
-                slug, reading_time_seconds
-            )
-            .into(),
-        );
-
-        // Save the calculated reading time to the server
-        if let Ok(updated_stats) = save_reading_time_to_server(slug, reading_time_seconds).await {
-            stats = updated_stats;
-            console::log_1(
-                &format!(
-                    "Successfully saved reading time for '{}': {} seconds",
-                    slug, stats.time
-                )
-                .into(),
-            );
-        } else {
-            // If saving fails, still use the calculated time for display
-            stats.time = reading_time_seconds as u64;
-            console::warn_1(
-                &format!(
-                    "Failed to save reading time for '{}', using calculated time for display only",
-                    slug
-                )
-                .into(),
-            );
-        }
-    }
+    let stats: PageStats = get_json(&get_url).await?;
 
     console::log_1(
         &format!(
             "Final stats for '{}': {} views, {} reads, {} likes, {} seconds reading time",
-            slug, stats.views, stats.reads, stats.likes, stats.time
+            page, stats.views, stats.reads, stats.likes, stats.time
         )
         .into(),
     );
@@ -309,54 +204,6 @@ async fn load_page_stats_from_server(
     Ok(stats)
 }
 
-// Save reading time to the server
-async fn save_reading_time_to_server(
-    slug: &str,
-    reading_time_seconds: u32,
-) -> Result<PageStats, Box<dyn Error>> {
-    let window = web_sys::window().unwrap();
-
-    let time_url = format!("/api/stats/{}/reading-time", slug);
-
-    // Create JSON payload
-    let payload = serde_json::json!({
-        "seconds": reading_time_seconds as u64
-    });
-
-    let opts = RequestInit::new();
-    opts.set_method("POST");
-    opts.set_mode(RequestMode::SameOrigin);
-
-    // Set content type header
-    let headers = web_sys::Headers::new().unwrap();
-    headers.set("Content-Type", "application/json").unwrap();
-    opts.set_headers(&headers);
-
-    // Set body
-    opts.set_body(&wasm_bindgen::JsValue::from_str(&payload.to_string()));
-
-    let request = Request::new_with_str_and_init(&time_url, &opts)
-        .map_err(|e| format!("Failed to create request: {:?}", e))?;
-
-    let resp_value = JsFuture::from(window.fetch_with_request(&request))
-        .await
-        .map_err(|e| format!("Network error: {:?}", e))?;
-
-    let resp: Response = resp_value.dyn_into().unwrap();
-
-    if resp.ok() {
-        let json = JsFuture::from(resp.json().unwrap())
-            .await
-            .map_err(|e| format!("Failed to parse JSON: {:?}", e))?;
-
-        serde_wasm_bindgen::from_value::<PageStats>(json).map_err(|e| -> Box<dyn Error> {
-            format!("Failed to deserialize stats: {:?}", e).into()
-        })
-    } else {
-        Err(format!("Failed to save reading time: HTTP {}", resp.status()).into())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,4 +217,10 @@ mod tests {
         assert_eq!(format_time(3660), "1h 1m");
         assert_eq!(format_time(3690), "1h 1m");
     }
+
+    #[test]
+    fn test_stats_path() {
+        assert_eq!(stats_path("post", "my_post"), "post/my_post");
+        assert_eq!(stats_path("page", "about"), "page/about");
+    }
 }
@@ -1,5 +1,6 @@
 use crate::traits::MarkdownRenderable;
-use serde::{Deserialize, Serialize};
+use content_model::structured_data;
+use serde::Deserialize;
 use std::collections::HashMap;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{console, window};
@@ -7,13 +8,15 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 
 use super::page_stats_display::PageStatsDisplay;
+use crate::api::{get_json, API_BASE};
 use crate::app::Route;
-use crate::hooks::{use_meta_tags, MetaData};
+use crate::events::{track_content_click, track_event};
+use crate::hooks::use_experiment::AssignedVariant;
+use crate::hooks::{track_experiment_click, use_experiment, use_meta_tags, MetaData};
 use crate::markdown::{
     load_markdown_content, parse_markdown_with_components, render_component_by_name,
     render_markdown_to_html,
 };
-use crate::reading_time::calculate_reading_time;
 
 #[allow(dead_code)]
 #[derive(Properties, PartialEq)]
@@ -28,42 +31,9 @@ pub struct PostsProps {
     pub featured_only: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub struct PostFrontmatter {
-    #[serde(default)]
-    pub title: String,
-    #[serde(default)]
-    pub date: String,
-    #[serde(default)]
-    pub summary: String,
-    #[serde(default)]
-    pub author: String,
-    #[serde(default)]
-    pub tags: Vec<String>,
-    #[serde(default)]
-    pub category: String,
-    #[serde(default)]
-    pub published: bool,
-    #[serde(default)]
-    pub image: String,
-}
-
-impl Default for PostFrontmatter {
-    fn default() -> Self {
-        Self {
-            title: "Untitled".to_string(),
-            date: "".to_string(),
-            summary: "".to_string(),
-            author: "".to_string(),
-            tags: vec![],
-            category: "".to_string(),
-            published: true,
-            image: "".to_string(),
-        }
-    }
-}
+pub use content_model::PostFrontmatter;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct PostSummary {
     pub slug: String,
     pub frontmatter: PostFrontmatter,
@@ -77,6 +47,7 @@ pub struct Post {
 }
 
 fn update_url_with_filter(key: &str, value: &str) {
+    track_event("posts_filter", &[(key, value)]);
     if let Some(window) = window() {
         // Always navigate to /blog when filtering, regardless of current page
         let new_url = format!("/blog?{}={}", key, value);
@@ -99,13 +70,21 @@ pub fn posts(props: &PostsProps) -> Html {
         let loading = loading.clone();
         let error = error.clone();
 
-        use_effect_with((), move |_| {
+        use_effect_with(props.featured_only, move |featured_only| {
             let posts_data = posts_data.clone();
             let loading = loading.clone();
             let error = error.clone();
+            let mut filters = get_url_filters();
+            if *featured_only {
+                filters = PostFilters {
+                    tag: Some("featured".to_string()),
+                    draft: filters.draft,
+                    ..Default::default()
+                };
+            }
 
             spawn_local(async move {
-                match load_all_posts().await {
+                match load_all_posts(&filters).await {
                     Ok(all_posts) => {
                         posts_data.set(all_posts);
                         loading.set(false);
@@ -137,6 +116,8 @@ pub fn posts(props: &PostsProps) -> Html {
         };
     }
 
+    // The API filtered on tag, category and draft already, filtering again covers `featured`
+    // and posts read from the markdown files
     let filters = get_url_filters();
 
     let has_filters =
@@ -166,13 +147,11 @@ pub fn posts(props: &PostsProps) -> Html {
             }
 
             if let Some(ref required_category) = filters.category {
-                let post_categories: Vec<&str> = post
+                if !post
                     .frontmatter
-                    .category
-                    .split(',')
-                    .map(|cat| cat.trim())
-                    .collect();
-                if !post_categories.contains(&required_category.as_str()) {
+                    .categories()
+                    .any(|category| category == required_category)
+                {
                     return false;
                 }
             }
@@ -187,7 +166,7 @@ pub fn posts(props: &PostsProps) -> Html {
         })
         .collect();
 
-    filtered_posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
+    filtered_posts.sort_by_key(|post| std::cmp::Reverse(post.frontmatter.date));
 
     html! {
         <div class="posts-container">
@@ -220,6 +199,27 @@ impl MarkdownRenderable for Posts {
     }
 }
 
+/// Title and summary to show for a post, those of the visitor's variant once it is known
+fn shown_title_and_summary<'a>(
+    post: &'a PostSummary,
+    experiment: Option<&'a AssignedVariant>,
+) -> (&'a str, &'a str) {
+    match experiment {
+        Some(variant) => (&variant.title, &variant.summary),
+        None => (&post.frontmatter.title, &post.frontmatter.summary),
+    }
+}
+
+/// Count clicks on a post's title as clicks on the visitor's variant, when it has one
+fn experiment_click_callback(slug: &str, in_experiment: bool) -> Callback<MouseEvent> {
+    let slug = slug.to_string();
+    Callback::from(move |_: MouseEvent| {
+        if in_experiment {
+            track_experiment_click(&slug);
+        }
+    })
+}
+
 #[allow(dead_code)]
 #[derive(Properties, PartialEq)]
 pub struct PostCardProps {
@@ -229,6 +229,9 @@ pub struct PostCardProps {
 #[function_component(PostCard)]
 pub fn post_card(props: &PostCardProps) -> Html {
     let post = &props.post;
+    let experiment = use_experiment(&post.slug, post.frontmatter.has_variants(), true);
+    let (title, summary) = shown_title_and_summary(post, experiment.as_ref());
+    let on_title_click = experiment_click_callback(&post.slug, experiment.is_some());
 
     html! {
         <article class="post-card">
@@ -239,13 +242,13 @@ pub fn post_card(props: &PostCardProps) -> Html {
             }
 
             <div class="post-card-content">
-                <h2 class="post-title">
-                    <Link<Route> to={Route::Post { slug: post.slug.clone() }}>{ &post.frontmatter.title }</Link<Route>>
+                <h2 class="post-title" onclick={on_title_click}>
+                    <Link<Route> to={Route::Post { slug: post.slug.clone() }}>{ title }</Link<Route>>
                 </h2>
 
                 <div class="post-meta">
-                    if !post.frontmatter.date.is_empty() {
-                        <time>{ &post.frontmatter.date }</time>
+                    if post.frontmatter.date.is_some() {
+                        <time>{ post.frontmatter.date_string() }</time>
                     }
                     if !post.frontmatter.author.is_empty() {
                         <span class="author">{ format!("By {}", &post.frontmatter.author) }</span>
@@ -255,8 +258,8 @@ pub fn post_card(props: &PostCardProps) -> Html {
                     }
                 </div>
 
-                if !post.frontmatter.summary.is_empty() {
-                    <p class="post-summary">{ &post.frontmatter.summary }</p>
+                if !summary.is_empty() {
+                    <p class="post-summary">{ summary }</p>
                 }
 
                 if !post.frontmatter.tags.is_empty() {
@@ -282,6 +285,9 @@ pub struct PostListItemProps {
 #[function_component(PostListItem)]
 pub fn post_list_item(props: &PostListItemProps) -> Html {
     let post = &props.post;
+    let experiment = use_experiment(&post.slug, post.frontmatter.has_variants(), true);
+    let (title, summary) = shown_title_and_summary(post, experiment.as_ref());
+    let on_title_click = experiment_click_callback(&post.slug, experiment.is_some());
 
     html! {
         <article class="post-list-item">
@@ -295,21 +301,21 @@ pub fn post_list_item(props: &PostListItemProps) -> Html {
                     }
                 </div>
                 <div class="post-meta">
-                    <time class="post-date">{ &post.frontmatter.date }</time>
+                    <time class="post-date">{ post.frontmatter.date_string() }</time>
                     <span class="post-author">{ format!("By {}", &post.frontmatter.author) }</span>
                 </div>
             </div>
 
             <div class="post-content">
                 <div class="post-header">
-                    <h2 class="post-title">
-                        <Link<Route> to={Route::Post { slug: post.slug.clone() }}>{ &post.frontmatter.title }</Link<Route>>
+                    <h2 class="post-title" onclick={on_title_click}>
+                        <Link<Route> to={Route::Post { slug: post.slug.clone() }}>{ title }</Link<Route>>
                     </h2>
 
                     // Categories and tags next to the title
                     <div class="post-taxonomy">
                         // Split categories by comma and show as separate items
-                        { for post.frontmatter.category.split(',').map(|cat| cat.trim()).filter(|cat| !cat.is_empty()).map(|category| {
+                        { for post.frontmatter.categories().map(|category| {
                             let category_str = category.to_string();
                             let onclick = {
                                 let category = category_str.clone();
@@ -341,8 +347,8 @@ pub fn post_list_item(props: &PostListItemProps) -> Html {
                     </div>
                 </div>
 
-                if !post.frontmatter.summary.is_empty() {
-                    <p class="post-summary">{ &post.frontmatter.summary }</p>
+                if !summary.is_empty() {
+                    <p class="post-summary">{ summary }</p>
                 }
             </div>
         </article>
@@ -433,24 +439,14 @@ pub fn post_view(props: &PostViewProps) -> Html {
         let post_image = if !post.frontmatter.image.is_empty() {
             Some(format!("{}{}", base_url, post.frontmatter.image))
         } else {
-            Some(format!("{}/static/logo_ga.svg", base_url))
+            // The card meta-gen renders for posts without an image
+            Some(format!("{}/og/{}.png", base_url, post.slug))
         };
 
-        // Parse date to ISO format if possible
-        let iso_date = if !post.frontmatter.date.is_empty() {
-            // Assuming date is in YYYYMMDD format, convert to ISO
-            if post.frontmatter.date.len() == 8 {
-                let year = &post.frontmatter.date[0..4];
-                let month = &post.frontmatter.date[4..6];
-                let day = &post.frontmatter.date[6..8];
-                let date = format!("{}-{}-{}", year, month, day);
-                Some(format!("{date}T00:00:00Z"))
-            } else {
-                Some(format!("{}T00:00:00Z", post.frontmatter.date))
-            }
-        } else {
-            None
-        };
+        let iso_date = post
+            .frontmatter
+            .date
+            .map(|date| format!("{}T00:00:00Z", date));
 
         MetaData {
             title: format!(
@@ -466,7 +462,7 @@ pub fn post_view(props: &PostViewProps) -> Html {
                 )
             },
             url: Some(post_url),
-            image: post_image,
+            image: post_image.clone(),
             article_author: if !post.frontmatter.author.is_empty() {
                 Some(post.frontmatter.author.clone())
             } else {
@@ -479,6 +475,23 @@ pub fn post_view(props: &PostViewProps) -> Html {
                 None
             },
             twitter_card_type: Some("summary_large_image".to_string()),
+            structured_data: Some(structured_data::graph(vec![
+                structured_data::blog_posting(
+                    base_url,
+                    &post.slug,
+                    &post.frontmatter,
+                    post_image.as_deref().unwrap_or_default(),
+                    reading_time::count_words(&post.content),
+                ),
+                structured_data::breadcrumbs(
+                    base_url,
+                    &[
+                        ("Home", "/"),
+                        ("Blog", "/blog"),
+                        (&post.frontmatter.title, &format!("/post/{}", post.slug)),
+                    ],
+                ),
+            ])),
         }
     } else if *loading {
         MetaData {
@@ -494,6 +507,12 @@ pub fn post_view(props: &PostViewProps) -> Html {
 
     use_meta_tags(meta_data);
 
+    // Readers keep seeing the title they clicked on, this doesn't count as an impression
+    let in_experiment = (*post_data)
+        .as_ref()
+        .is_some_and(|post| post.frontmatter.has_variants());
+    let experiment = use_experiment(&props.slug, in_experiment, false);
+
     if *loading {
         return html! {
             <div class="posts-container">
@@ -520,16 +539,20 @@ pub fn post_view(props: &PostViewProps) -> Html {
 
     // Parse markdown content for components
     let markdown_parts = parse_markdown_with_components(&post.content);
+    let (title, summary) = match &experiment {
+        Some(variant) => (&variant.title, &variant.summary),
+        None => (&post.frontmatter.title, &post.frontmatter.summary),
+    };
 
     html! {
         <div class="posts-container">
             <div class="post-view-container">
                 // Full width title
-                <h1 class="post-view-title">{ &post.frontmatter.title }</h1>
+                <h1 class="post-view-title">{ title }</h1>
 
                 // Meta information below title
                 <div class="post-view-meta">
-                    <time class="post-date">{ &post.frontmatter.date }</time>
+                    <time class="post-date">{ post.frontmatter.date_string() }</time>
                     <span class="post-author">{ format!("By {}", &post.frontmatter.author) }</span>
                     if post.frontmatter.tags.contains(&"featured".to_string()) {
                         <span class="featured-badge">{ "Featured" }</span>
@@ -539,7 +562,7 @@ pub fn post_view(props: &PostViewProps) -> Html {
                 // Taxonomy (categories and tags)
                 <div class="post-view-taxonomy">
                     // Categories
-                    { for post.frontmatter.category.split(',').map(|cat| cat.trim()).filter(|cat| !cat.is_empty()).map(|category| {
+                    { for post.frontmatter.categories().map(|category| {
                         let category_str = category.to_string();
                         let onclick = {
                             let category = category_str.clone();
@@ -572,8 +595,8 @@ pub fn post_view(props: &PostViewProps) -> Html {
                 </div>
 
                 // Summary if available
-                if !post.frontmatter.summary.is_empty() {
-                    <p class="post-view-summary">{ &post.frontmatter.summary }</p>
+                if !summary.is_empty() {
+                    <p class="post-view-summary">{ summary }</p>
                 }
 
                 // Content area with floating image
@@ -583,7 +606,7 @@ pub fn post_view(props: &PostViewProps) -> Html {
                     }
 
                     // Render the markdown content with component support
-                    <div class="post-markdown-content">
+                    <div class="post-markdown-content" onclick={track_content_click}>
                         {
                             markdown_parts.iter().map(|part| {
                                 if part.is_component {
@@ -603,13 +626,13 @@ pub fn post_view(props: &PostViewProps) -> Html {
                 </div>
 
                 // Add page stats display at the bottom of the post
-                <PageStatsDisplay slug={AttrValue::from(post.slug.clone())} track_view={true} reading_time_seconds={calculate_reading_time(&post.content) as u32} published={post.frontmatter.published} />
+                <PageStatsDisplay slug={AttrValue::from(post.slug.clone())} track_view={true} />
             </div>
         </div>
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct PostFilters {
     pub featured: Option<bool>,
     pub category: Option<String>,
@@ -676,57 +699,64 @@ pub async fn load_post(slug: &str) -> Result<Post, String> {
     Ok(parse_full_post(&raw_content, slug))
 }
 
-// Parse full post including frontmatter and content
+/// Split a post into frontmatter and markdown, a post without or with invalid frontmatter
+/// keeps the default frontmatter so it can still be read
 fn parse_full_post(raw_content: &str, slug: &str) -> Post {
-    if raw_content.starts_with("---\n") {
-        let lines: Vec<&str> = raw_content.lines().collect();
-        let mut frontmatter_end = None;
-
-        // Find the second occurrence of "---" line
-        let mut found_first = false;
-        for (i, line) in lines.iter().enumerate() {
-            if line.trim() == "---" {
-                if found_first {
-                    frontmatter_end = Some(i);
-                    break;
-                } else {
-                    found_first = true;
-                }
-            }
-        }
-
-        if let Some(end_line) = frontmatter_end {
-            // Extract frontmatter lines (skip first --- line)
-            let frontmatter_lines = &lines[1..end_line];
-            let frontmatter_str = frontmatter_lines.join("\n");
-            let frontmatter = parse_yaml_frontmatter(&frontmatter_str);
-
-            // Extract content lines (skip frontmatter and closing --- line)
-            let content_lines = if end_line + 1 < lines.len() {
-                &lines[end_line + 1..]
-            } else {
-                &[]
-            };
-            let content = content_lines.join("\n").trim().to_string();
-
-            return Post {
-                slug: slug.to_string(),
-                frontmatter,
-                content,
-            };
+    let (frontmatter, content) = match content_model::parse(raw_content) {
+        Ok(parsed) => parsed,
+        Err(content_model::Error::MissingFrontmatter) => (PostFrontmatter::default(), raw_content),
+        Err(e) => {
+            console::log_1(&format!("Invalid frontmatter in {}: {}", slug, e).into());
+            let content = content_model::split(raw_content).map_or(raw_content, |(_, body)| body);
+            (PostFrontmatter::default(), content)
         }
-    }
+    };
 
-    // No frontmatter found, treat whole content as markdown
     Post {
         slug: slug.to_string(),
-        frontmatter: PostFrontmatter::default(),
-        content: raw_content.to_string(),
+        frontmatter,
+        content: content.to_string(),
     }
 }
 
-// Load all posts from markdown files
-async fn load_all_posts() -> Result<Vec<PostSummary>, String> {
+/// One page of posts from `GET /api/v1/posts`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct PostList {
+    posts: Vec<PostSummary>,
+    /// Number of posts matching the filters over all pages
+    total: usize,
+}
+
+/// Most posts the API returns at once
+const POSTS_PER_PAGE: usize = 100;
+
+/// API url of one page of the posts matching the filters. The values come straight from the
+/// page's query string, so they are already percent-encoded.
+fn posts_api_url(filters: &PostFilters, page: usize) -> String {
+    let mut url = format!(
+        "{}/posts?page={}&per_page={}",
+        API_BASE, page, POSTS_PER_PAGE
+    );
+    if let Some(tag) = &filters.tag {
+        url.push_str(&format!("&tag={}", tag));
+    }
+    if let Some(category) = &filters.category {
+        url.push_str(&format!("&category={}", category));
+    }
+    if filters.draft == Some(true) {
+        url.push_str("&draft=true");
+    }
+    url
+}
+
+/// Load the frontmatter of the posts matching the filters, newest first, from the API.
+/// Without the backend (e.g. `trunk serve`) every markdown file is read instead.
+pub async fn load_all_posts(filters: &PostFilters) -> Result<Vec<PostSummary>, String> {
+    match load_api_posts(filters).await {
+        Ok(posts) => return Ok(posts),
+        Err(e) => console::log_1(&format!("Posts API unavailable, reading files: {}", e).into()),
+    }
+
     let mut posts = Vec::new();
     let slugs = get_all_post_slugs();
 
@@ -741,101 +771,71 @@ async fn load_all_posts() -> Result<Vec<PostSummary>, String> {
     }
 
     // Sort posts by date (newest first)
-    posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
+    posts.sort_by_key(|post| std::cmp::Reverse(post.frontmatter.date));
 
     Ok(posts)
 }
 
+/// Request page after page of `GET /api/v1/posts` until all `total` posts are loaded
+async fn load_api_posts(filters: &PostFilters) -> Result<Vec<PostSummary>, String> {
+    let mut posts = Vec::new();
+    let mut page = 1;
+    loop {
+        let list = get_json::<PostList>(&posts_api_url(filters, page)).await?;
+        let last = list.posts.is_empty() || posts.len() + list.posts.len() >= list.total;
+        posts.extend(list.posts);
+        if last {
+            return Ok(posts);
+        }
+        page += 1;
+    }
+}
+
 // Load just the frontmatter from a post (not the full content)
 async fn load_post_frontmatter(slug: &str) -> Result<PostSummary, String> {
     let url = format!("/content/posts/{}.md", slug);
     let raw_content = load_markdown_content(&url).await?;
-    Ok(parse_post_frontmatter_only(&raw_content, slug))
+    let post = parse_full_post(&raw_content, slug);
+    Ok(PostSummary {
+        slug: post.slug,
+        frontmatter: post.frontmatter,
+    })
 }
 
-// Parse just the frontmatter from markdown content
-fn parse_post_frontmatter_only(raw_content: &str, slug: &str) -> PostSummary {
-    if raw_content.starts_with("---\n") {
-        // Look for the closing --- pattern, which might not be exactly "\n---\n"
-        let lines: Vec<&str> = raw_content.lines().collect();
-        let mut frontmatter_end = None;
-
-        // Find the second occurrence of "---" line
-        let mut found_first = false;
-        for (i, line) in lines.iter().enumerate() {
-            if line.trim() == "---" {
-                if found_first {
-                    frontmatter_end = Some(i);
-                    break;
-                } else {
-                    found_first = true;
-                }
-            }
-        }
-
-        if let Some(end_line) = frontmatter_end {
-            // Extract frontmatter lines (skip first --- line)
-            let frontmatter_lines = &lines[1..end_line];
-            let frontmatter_str = frontmatter_lines.join("\n");
-            let frontmatter = parse_yaml_frontmatter(&frontmatter_str);
-
-            return PostSummary {
-                slug: slug.to_string(),
-                frontmatter,
-            };
-        }
-    }
-
-    // No frontmatter found, return default
-    PostSummary {
-        slug: slug.to_string(),
-        frontmatter: PostFrontmatter::default(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posts_api_url() {
+        assert_eq!(
+            posts_api_url(&PostFilters::default(), 1),
+            "/api/v1/posts?page=1&per_page=100"
+        );
+        let filters = PostFilters {
+            tag: Some("rust".to_string()),
+            category: Some("code".to_string()),
+            draft: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            posts_api_url(&filters, 2),
+            "/api/v1/posts?page=2&per_page=100&tag=rust&category=code&draft=true"
+        );
     }
-}
-
-// Simple YAML parser for common frontmatter fields
-
-fn parse_yaml_frontmatter(yaml_str: &str) -> PostFrontmatter {
-    let mut frontmatter = PostFrontmatter::default();
 
-    for line in yaml_str.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        if let Some((key, value)) = line.split_once(':') {
-            let key = key.trim();
-            let value = value.trim().trim_matches('"').trim_matches('\'');
-
-            match key {
-                "title" => frontmatter.title = value.to_string(),
-                "date" => frontmatter.date = value.to_string(),
-                "summary" => frontmatter.summary = value.to_string(),
-                "author" => frontmatter.author = value.to_string(),
-                "category" => frontmatter.category = value.to_string(),
-                "image" => frontmatter.image = value.to_string(),
-                "published" => frontmatter.published = value.to_lowercase() == "true",
-                "tags" => {
-                    if value.starts_with('[') && value.ends_with(']') {
-                        let tags_str = &value[1..value.len() - 1];
-                        frontmatter.tags = tags_str
-                            .split(',')
-                            .map(|tag| tag.trim().trim_matches('"').trim_matches('\'').to_string())
-                            .filter(|tag| !tag.is_empty())
-                            .collect();
-                    } else {
-                        frontmatter.tags = value
-                            .split(',')
-                            .map(|tag| tag.trim().to_string())
-                            .filter(|tag| !tag.is_empty())
-                            .collect();
-                    }
-                }
-                _ => {}
-            }
-        }
+    #[test]
+    fn test_parse_full_post() {
+        let post = parse_full_post(
+            "---\ntitle: Original\ndate: 20240101\ntitle_variants: [\"Hello, world\", 'Quiet']\n---\n\n# Body",
+            "test",
+        );
+        assert_eq!(post.frontmatter.date_string(), "2024-01-01");
+        assert_eq!(post.frontmatter.title_variants, ["Hello, world", "Quiet"]);
+        assert_eq!(post.content, "# Body");
+
+        let post = parse_full_post("Just markdown", "plain");
+        assert_eq!(post.frontmatter, PostFrontmatter::default());
+        assert_eq!(post.content, "Just markdown");
     }
-
-    frontmatter
 }
@@ -0,0 +1,21 @@
+use super::page::page_structured_data;
+use super::Page;
+use crate::hooks::{use_meta_tags, MetaData};
+use yew::prelude::*;
+
+#[function_component(PrivacyPage)]
+pub fn privacy_page() -> Html {
+    let meta_data = MetaData {
+        title: "Privacy - gertjanassies.dev".to_string(),
+        url: Some("https://gertjanassies.dev/privacy".to_string()),
+        structured_data: Some(page_structured_data("privacy")),
+        ..Default::default()
+    };
+    use_meta_tags(meta_data);
+
+    html! {
+        <div class="privacy-page">
+            <Page content="privacy" />
+        </div>
+    }
+}
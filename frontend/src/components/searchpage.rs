@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::api::{get_json, API_BASE};
+use crate::app::Route;
+use crate::hooks::{use_meta_tags, MetaData};
+
+/// Query string of the /search route, e.g. `/search?q=rust`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    pub q: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SearchResult {
+    pub slug: String,
+    pub title: String,
+    pub score: f64,
+    pub snippet: String, // HTML-escaped by the server, matches wrapped in <mark>
+}
+
+#[function_component(SearchPage)]
+pub fn search_page() -> Html {
+    let query = use_location()
+        .and_then(|location| location.query::<SearchParams>().ok())
+        .unwrap_or_default()
+        .q;
+
+    let results = use_state(Vec::<SearchResult>::new);
+    let loading = use_state(|| false);
+    let error = use_state(|| None::<String>);
+
+    {
+        let results = results.clone();
+        let loading = loading.clone();
+        let error = error.clone();
+
+        use_effect_with(query.clone(), move |query| {
+            let query = query.trim().to_string();
+            if query.is_empty() {
+                results.set(Vec::new());
+            } else {
+                loading.set(true);
+                error.set(None);
+                spawn_local(async move {
+                    match search_posts(&query).await {
+                        Ok(found) => results.set(found),
+                        Err(err) => error.set(Some(err)),
+                    }
+                    loading.set(false);
+                });
+            }
+
+            || ()
+        });
+    }
+
+    use_meta_tags(MetaData {
+        title: "Search - gertjanassies.dev".to_string(),
+        url: Some("https://gertjanassies.dev/search".to_string()),
+        ..Default::default()
+    });
+
+    html! {
+        <div class="posts-container search-page">
+            <h1>{ "Search" }</h1>
+
+            if *loading {
+                <div class="loading">{ "Searching..." }</div>
+            } else if let Some(err) = (*error).as_ref() {
+                <div class="error">{ format!("Search failed: {}", err) }</div>
+            } else if query.trim().is_empty() {
+                <p>{ "Type something in the search box to find posts." }</p>
+            } else if results.is_empty() {
+                <div class="no-posts">
+                    <p>{ format!("No posts found for \"{}\".", query) }</p>
+                </div>
+            } else {
+                <div class="search-results">
+                    { for results.iter().map(|result| html! {
+                        <article class="search-result">
+                            <h2 class="post-title">
+                                <Link<Route> to={Route::Post { slug: result.slug.clone() }}>{ &result.title }</Link<Route>>
+                            </h2>
+                            <p class="search-snippet">
+                                { Html::from_html_unchecked(AttrValue::from(result.snippet.clone())) }
+                            </p>
+                        </article>
+                    }) }
+                </div>
+            }
+        </div>
+    }
+}
+
+// Query the backend search API
+async fn search_posts(query: &str) -> Result<Vec<SearchResult>, String> {
+    let url = format!(
+        "{}/search?q={}",
+        API_BASE,
+        String::from(web_sys::js_sys::encode_uri_component(query))
+    );
+    get_json(&url).await
+}
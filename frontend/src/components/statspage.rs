@@ -0,0 +1,508 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use super::charts::{BarChart, Sparkline};
+use super::page_stats_display::{format_time, PageStats};
+use super::posts::{load_all_posts, PostFilters, PostSummary};
+use crate::admin::{admin_token, set_admin_token};
+use crate::api::{get_admin_json, API_BASE};
+use crate::app::Route;
+use crate::hooks::{use_meta_tags, MetaData};
+
+/// Days of daily views fetched for the charts, the last half is compared with the first
+/// half to find the top movers
+const HISTORY_DAYS: usize = 14;
+
+/// Number of top movers shown
+const TOP_MOVERS: usize = 5;
+
+/// Response of `GET /api/v1/admin/export`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StatsExport {
+    pub days: Vec<String>,
+    #[serde(default)]
+    pub clients: ClientBreakdown,
+    pub pages: Vec<PageExport>,
+}
+
+/// Views of the whole site per device, browser and OS family
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ClientBreakdown {
+    pub devices: BTreeMap<String, u64>,
+    pub browsers: BTreeMap<String, u64>,
+    pub os: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PageExport {
+    pub stats: PageStats,
+    pub daily_views: Vec<u64>,
+}
+
+/// Response of `GET /api/v1/admin/experiments`, one per post with variants
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExperimentReport {
+    pub slug: String,
+    pub variants: Vec<VariantReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VariantReport {
+    pub variant: usize,
+    pub title: String,
+    pub impressions: u64,
+    pub clicks: u64,
+    pub ctr: f64,
+    pub p_value: Option<f64>,
+}
+
+/// Significance of a variant against the original, `-` for the original itself and for
+/// variants that weren't shown yet
+pub fn format_p_value(p_value: Option<f64>) -> String {
+    match p_value {
+        Some(p) if p < 0.001 => "p < 0.001".to_string(),
+        Some(p) => format!("p = {:.3}", p),
+        None => "-".to_string(),
+    }
+}
+
+/// One line of the stats table, the page's stats joined with its post's title
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsRow {
+    pub slug: String,
+    pub title: String,
+    pub is_post: bool,
+    pub views: u64,
+    pub likes: u64,
+    pub time: u64,
+    pub daily_views: Vec<u64>,
+}
+
+impl StatsRow {
+    /// Views in the second half of the history minus views in the first half
+    pub fn trend(&self) -> i64 {
+        let (previous, recent) = self.daily_views.split_at(self.daily_views.len() / 2);
+        recent.iter().sum::<u64>() as i64 - previous.iter().sum::<u64>() as i64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Title,
+    Views,
+    Likes,
+    Time,
+    Trend,
+}
+
+/// Join the exported stats of posts with their frontmatter, other pages and posts that no
+/// longer exist keep their slug as title
+pub fn build_rows(export: &StatsExport, posts: &[PostSummary]) -> Vec<StatsRow> {
+    let titles: HashMap<&str, &str> = posts
+        .iter()
+        .map(|post| (post.slug.as_str(), post.frontmatter.title.as_str()))
+        .collect();
+
+    export
+        .pages
+        .iter()
+        .map(|page| {
+            let title = (page.stats.kind == "post")
+                .then(|| titles.get(page.stats.slug.as_str()).copied())
+                .flatten();
+            StatsRow {
+                slug: page.stats.slug.clone(),
+                title: title.unwrap_or(&page.stats.slug).to_string(),
+                is_post: title.is_some(),
+                views: page.stats.views,
+                likes: page.stats.likes,
+                time: page.stats.time,
+                daily_views: page.daily_views.clone(),
+            }
+        })
+        .collect()
+}
+
+pub fn sort_rows(rows: &mut [StatsRow], key: SortKey, descending: bool) {
+    rows.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::Views => a.views.cmp(&b.views),
+            SortKey::Likes => a.likes.cmp(&b.likes),
+            SortKey::Time => a.time.cmp(&b.time),
+            SortKey::Trend => a.trend().cmp(&b.trend()),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Pages whose views changed the most, either way, largest change first
+pub fn top_movers(rows: &[StatsRow], count: usize) -> Vec<&StatsRow> {
+    let mut movers: Vec<&StatsRow> = rows.iter().filter(|row| row.trend() != 0).collect();
+    movers.sort_by_key(|row| std::cmp::Reverse(row.trend().unsigned_abs()));
+    movers.truncate(count);
+    movers
+}
+
+/// Share of the views of each name in percent, most views first
+pub fn shares(counts: &BTreeMap<String, u64>) -> Vec<(&str, u64)> {
+    let total: u64 = counts.values().sum();
+    let mut shares: Vec<(&str, u64)> = counts
+        .iter()
+        .filter(|(_, views)| **views > 0)
+        .map(|(name, views)| (name.as_str(), views * 100 / total))
+        .collect();
+    shares.sort_by_key(|(_, share)| std::cmp::Reverse(*share));
+    shares
+}
+
+#[function_component(StatsPage)]
+pub fn stats_page() -> Html {
+    let token = use_state(admin_token);
+    let export = use_state(|| None::<StatsExport>);
+    let experiments = use_state(Vec::<ExperimentReport>::new);
+    let posts = use_state(Vec::<PostSummary>::new);
+    let loading = use_state(|| false);
+    let error = use_state(|| None::<String>);
+    let sort = use_state(|| (SortKey::Views, true));
+    let token_input = use_node_ref();
+
+    use_meta_tags(MetaData {
+        title: "Stats - gertjanassies.dev".to_string(),
+        url: Some("https://gertjanassies.dev/stats".to_string()),
+        ..Default::default()
+    });
+
+    {
+        let export = export.clone();
+        let experiments = experiments.clone();
+        let posts = posts.clone();
+        let loading = loading.clone();
+        let error = error.clone();
+
+        use_effect_with((*token).clone(), move |token| {
+            if let Some(token) = token.clone() {
+                loading.set(true);
+                error.set(None);
+                spawn_local(async move {
+                    let url = format!("{}/admin/export?days={}", API_BASE, HISTORY_DAYS);
+                    match get_admin_json::<StatsExport>(&url, &token).await {
+                        Ok(data) => export.set(Some(data)),
+                        Err(err) => error.set(Some(err)),
+                    }
+                    let url = format!("{}/admin/experiments", API_BASE);
+                    match get_admin_json::<Vec<ExperimentReport>>(&url, &token).await {
+                        Ok(reports) => experiments.set(reports),
+                        Err(err) => error.set(Some(err)),
+                    }
+                    // Pages without a post still show up by slug when titles fail to load
+                    let filters = PostFilters {
+                        draft: Some(true),
+                        ..Default::default()
+                    };
+                    posts.set(load_all_posts(&filters).await.unwrap_or_default());
+                    loading.set(false);
+                });
+            }
+
+            || ()
+        });
+    }
+
+    let on_token_submit = {
+        let token = token.clone();
+        let token_input = token_input.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let Some(input) = token_input.cast::<HtmlInputElement>() else {
+                return;
+            };
+            let value = input.value().trim().to_string();
+            if !value.is_empty() {
+                set_admin_token(Some(&value));
+                token.set(Some(value));
+            }
+        })
+    };
+
+    let on_forget_token = {
+        let token = token.clone();
+        let export = export.clone();
+        let error = error.clone();
+        Callback::from(move |_: MouseEvent| {
+            set_admin_token(None);
+            token.set(None);
+            export.set(None);
+            error.set(None);
+        })
+    };
+
+    if token.is_none() {
+        return html! {
+            <div class="posts-container stats-page">
+                <h1>{ "Stats" }</h1>
+                <form class="admin-token-form" onsubmit={on_token_submit}>
+                    <label for="admin-token">{ "Admin token" }</label>
+                    <input ref={token_input} id="admin-token" type="password" autocomplete="current-password" />
+                    <button type="submit">{ "Show stats" }</button>
+                </form>
+            </div>
+        };
+    }
+
+    let content = if *loading {
+        html! { <div class="loading">{ "Loading stats..." }</div> }
+    } else if let Some(err) = (*error).as_ref() {
+        html! { <div class="error">{ format!("Could not load stats: {}", err) }</div> }
+    } else if let Some(export) = (*export).as_ref() {
+        let (sort_key, descending) = *sort;
+        let mut rows = build_rows(export, &posts);
+        sort_rows(&mut rows, sort_key, descending);
+        let movers = top_movers(&rows, TOP_MOVERS);
+
+        let daily_totals: Vec<u64> = (0..export.days.len())
+            .map(|day| rows.iter().filter_map(|row| row.daily_views.get(day)).sum())
+            .collect();
+        let total_views: u64 = rows.iter().map(|row| row.views).sum();
+        let total_likes: u64 = rows.iter().map(|row| row.likes).sum();
+        let recent_views: u64 = daily_totals.iter().sum();
+
+        let header = |label: &str, key: SortKey| {
+            let sort = sort.clone();
+            let onclick = Callback::from(move |_: MouseEvent| {
+                let (current, descending) = *sort;
+                // Clicking the sorted column flips the direction, another column sorts
+                // descending first
+                sort.set((key, if current == key { !descending } else { true }));
+            });
+            let marker = match (sort_key == key, descending) {
+                (true, true) => " ▼",
+                (true, false) => " ▲",
+                (false, _) => "",
+            };
+            html! {
+                <th>
+                    <button class="sort-button" {onclick}>{ format!("{}{}", label, marker) }</button>
+                </th>
+            }
+        };
+
+        html! {
+            <>
+                <div class="stats-totals">
+                    <div class="stats-total"><span>{ rows.len() }</span>{ " pages" }</div>
+                    <div class="stats-total"><span>{ total_views }</span>{ " views" }</div>
+                    <div class="stats-total"><span>{ total_likes }</span>{ " likes" }</div>
+                    <div class="stats-total">
+                        <span>{ recent_views }</span>{ format!(" views in {} days", export.days.len()) }
+                    </div>
+                </div>
+
+                <h2>{ "Views per day" }</h2>
+                <BarChart values={daily_totals} labels={export.days.clone()} />
+
+                <h2>{ "Top movers" }</h2>
+                if movers.is_empty() {
+                    <p>{ "No changes in views." }</p>
+                } else {
+                    <ul class="stats-movers">
+                        { for movers.iter().map(|row| html! {
+                            <li>
+                                <Sparkline values={row.daily_views.clone()} />
+                                <span class={if row.trend() > 0 { "trend up" } else { "trend down" }}>
+                                    { format!("{:+}", row.trend()) }
+                                </span>
+                                { &row.title }
+                            </li>
+                        }) }
+                    </ul>
+                }
+
+                <h2>{ "Clients" }</h2>
+                <div class="stats-clients">
+                    { for [
+                        ("Devices", &export.clients.devices),
+                        ("Browsers", &export.clients.browsers),
+                        ("Operating systems", &export.clients.os),
+                    ].into_iter().map(|(label, counts)| html! {
+                        <div>
+                            <h3>{ label }</h3>
+                            <ul>
+                                { for shares(counts).into_iter().map(|(name, share)| html! {
+                                    <li>{ format!("{} {}%", name, share) }</li>
+                                }) }
+                            </ul>
+                        </div>
+                    }) }
+                </div>
+
+                if !experiments.is_empty() {
+                    <h2>{ "Experiments" }</h2>
+                    { for experiments.iter().map(|experiment| html! {
+                        <table class="stats-table stats-experiment">
+                            <caption>
+                                <Link<Route> to={Route::Post { slug: experiment.slug.clone() }}>{ &experiment.slug }</Link<Route>>
+                            </caption>
+                            <thead>
+                                <tr>
+                                    <th>{ "Variant" }</th>
+                                    <th>{ "Impressions" }</th>
+                                    <th>{ "Clicks" }</th>
+                                    <th>{ "CTR" }</th>
+                                    <th>{ "Significance" }</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                { for experiment.variants.iter().map(|variant| html! {
+                                    <tr>
+                                        <td>{ format!("{}. {}", variant.variant, variant.title) }</td>
+                                        <td>{ variant.impressions }</td>
+                                        <td>{ variant.clicks }</td>
+                                        <td>{ format!("{:.1}%", variant.ctr * 100.0) }</td>
+                                        <td>{ format_p_value(variant.p_value) }</td>
+                                    </tr>
+                                }) }
+                            </tbody>
+                        </table>
+                    }) }
+                }
+
+                <h2>{ "All pages" }</h2>
+                <table class="stats-table">
+                    <thead>
+                        <tr>
+                            { header("Title", SortKey::Title) }
+                            { header("Views", SortKey::Views) }
+                            { header("Likes", SortKey::Likes) }
+                            { header("Reading time", SortKey::Time) }
+                            { header("Trend", SortKey::Trend) }
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for rows.iter().map(|row| html! {
+                            <tr>
+                                <td>
+                                    if row.is_post {
+                                        <Link<Route> to={Route::Post { slug: row.slug.clone() }}>{ &row.title }</Link<Route>>
+                                    } else {
+                                        { &row.title }
+                                    }
+                                </td>
+                                <td>{ row.views }</td>
+                                <td>{ row.likes }</td>
+                                <td>{ format_time(row.time) }</td>
+                                <td><Sparkline values={row.daily_views.clone()} /></td>
+                            </tr>
+                        }) }
+                    </tbody>
+                </table>
+            </>
+        }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <div class="posts-container stats-page">
+            <h1>{ "Stats" }</h1>
+            { content }
+            <button class="stats-forget-token" onclick={on_forget_token}>{ "Forget admin token" }</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::posts::PostFrontmatter;
+
+    fn page(kind: &str, slug: &str, views: u64, daily_views: Vec<u64>) -> PageExport {
+        PageExport {
+            stats: PageStats {
+                slug: slug.to_string(),
+                kind: kind.to_string(),
+                views,
+                ..Default::default()
+            },
+            daily_views,
+        }
+    }
+
+    fn rows() -> Vec<StatsRow> {
+        let export = StatsExport {
+            days: vec!["2024-03-01".to_string(), "2024-03-02".to_string()],
+            clients: ClientBreakdown::default(),
+            pages: vec![
+                page("page", "home", 10, vec![5, 5]),
+                page("post", "rising_post", 3, vec![0, 3]),
+                page("post", "falling_post", 7, vec![6, 1]),
+            ],
+        };
+        let posts = ["home", "rising_post", "falling_post"].map(|slug| PostSummary {
+            slug: slug.to_string(),
+            frontmatter: PostFrontmatter {
+                title: slug.replace('_', " "),
+                ..Default::default()
+            },
+        });
+        build_rows(&export, &posts)
+    }
+
+    #[test]
+    fn test_rows_are_joined_with_post_titles() {
+        let rows = rows();
+        assert_eq!(rows[0].title, "home");
+        assert!(!rows[0].is_post);
+        assert_eq!(rows[1].title, "rising post");
+        assert!(rows[1].is_post);
+    }
+
+    #[test]
+    fn test_sort_rows() {
+        let mut rows = rows();
+        sort_rows(&mut rows, SortKey::Views, true);
+        assert_eq!(rows[0].slug, "home");
+
+        sort_rows(&mut rows, SortKey::Trend, false);
+        assert_eq!(rows[0].slug, "falling_post");
+
+        sort_rows(&mut rows, SortKey::Title, false);
+        assert_eq!(rows[0].slug, "falling_post");
+    }
+
+    #[test]
+    fn test_top_movers() {
+        let rows = rows();
+        let movers: Vec<(&str, i64)> = top_movers(&rows, 5)
+            .iter()
+            .map(|row| (row.slug.as_str(), row.trend()))
+            .collect();
+        assert_eq!(movers, [("falling_post", -5), ("rising_post", 3)]);
+    }
+
+    #[test]
+    fn test_shares() {
+        let counts = BTreeMap::from([
+            ("desktop".to_string(), 1),
+            ("mobile".to_string(), 3),
+            ("tablet".to_string(), 0),
+        ]);
+        assert_eq!(shares(&counts), [("mobile", 75), ("desktop", 25)]);
+        assert!(shares(&BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_format_p_value() {
+        assert_eq!(format_p_value(None), "-");
+        assert_eq!(format_p_value(Some(0.0123)), "p = 0.012");
+        assert_eq!(format_p_value(Some(0.00001)), "p < 0.001");
+    }
+}
@@ -0,0 +1,129 @@
+use serde_json::json;
+use web_sys::window;
+use yew::{MouseEvent, TargetCast};
+use yew_router::Routable;
+
+use crate::api::{send_json, API_BASE};
+use crate::app::Route;
+use crate::privacy::is_stats_opt_out;
+
+/// Count an interaction on the page the reader is on with `POST /api/v1/events`. Nothing
+/// is sent when the reader opted out of stats, or on pages without stats (search, stats).
+/// The name must be in the backend's `EVENT_NAMES`.
+pub fn track_event(name: &str, props: &[(&str, &str)]) {
+    if is_stats_opt_out() {
+        return;
+    }
+    let Some(path) = window().and_then(|w| w.location().pathname().ok()) else {
+        return;
+    };
+    let Some((kind, slug)) = Route::recognize(&path).and_then(|route| page_of_route(&route)) else {
+        return;
+    };
+
+    let props: serde_json::Map<String, serde_json::Value> = props
+        .iter()
+        .map(|(key, value)| (key.to_string(), json!(value)))
+        .collect();
+    let payload = json!({ "kind": kind, "slug": slug, "name": name, "props": props });
+    if let Err(e) = send_json(&format!("{}/events", API_BASE), &payload) {
+        web_sys::console::error_1(&format!("Failed to track {}: {}", name, e).into());
+    }
+}
+
+/// Kind and slug the stats of a route are kept under
+pub fn page_of_route(route: &Route) -> Option<(&'static str, String)> {
+    match route {
+        Route::Home => Some(("page", "home".to_string())),
+        Route::Blog => Some(("page", "blog".to_string())),
+        Route::About => Some(("page", "about".to_string())),
+        Route::Privacy => Some(("page", "privacy".to_string())),
+        Route::Post { slug } => Some(("post", slug.clone())),
+        Route::Search | Route::Stats | Route::NotFound => None,
+    }
+}
+
+/// Host of an absolute http(s) link to another site, `None` for links within this one
+pub fn outbound_host(href: &str, own_host: &str) -> Option<String> {
+    let rest = href
+        .strip_prefix("https://")
+        .or_else(|| href.strip_prefix("http://"))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    (!host.is_empty() && !host.eq_ignore_ascii_case(own_host)).then(|| host.to_ascii_lowercase())
+}
+
+/// Track clicks on the copy buttons of code blocks and on outbound links inside rendered
+/// markdown, meant as the `onclick` of the element holding it
+pub fn track_content_click(e: MouseEvent) {
+    let Some(target) = e.target_dyn_into::<web_sys::Element>() else {
+        return;
+    };
+
+    if let Ok(Some(button)) = target.closest(".copy-code-button") {
+        let language = button
+            .next_element_sibling()
+            .and_then(|pre| pre.get_attribute("class"))
+            .and_then(|class| {
+                class
+                    .split_whitespace()
+                    .find_map(|class| class.strip_prefix("language-"))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "text".to_string());
+        track_event("copy_code", &[("language", &language)]);
+    } else if let Ok(Some(link)) = target.closest("a[href]") {
+        let own_host = window()
+            .and_then(|w| w.location().host().ok())
+            .unwrap_or_default();
+        if let Some(host) = link
+            .get_attribute("href")
+            .and_then(|href: String| outbound_host(&href, &own_host))
+        {
+            track_event("outbound_link", &[("host", &host)]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_of_route() {
+        assert_eq!(
+            page_of_route(&Route::Home),
+            Some(("page", "home".to_string()))
+        );
+        assert_eq!(
+            page_of_route(&Route::Post {
+                slug: "my_post".to_string()
+            }),
+            Some(("post", "my_post".to_string()))
+        );
+        assert_eq!(page_of_route(&Route::Stats), None);
+    }
+
+    #[test]
+    fn test_outbound_host() {
+        assert_eq!(
+            outbound_host(
+                "https://GitHub.com/gertjanassies?tab=repositories",
+                "gertjanassies.dev"
+            ),
+            Some("github.com".to_string())
+        );
+        assert_eq!(
+            outbound_host("http://example.com", "gertjanassies.dev"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            outbound_host("https://gertjanassies.dev/about", "gertjanassies.dev"),
+            None
+        );
+        assert_eq!(outbound_host("/post/my_post", "gertjanassies.dev"), None);
+        assert_eq!(
+            outbound_host("mailto:me@example.com", "gertjanassies.dev"),
+            None
+        );
+    }
+}
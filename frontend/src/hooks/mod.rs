@@ -1,5 +1,7 @@
 pub mod use_document_title;
+pub mod use_experiment;
 pub mod use_meta_tags;
 
 // pub use use_document_title::use_document_title;
+pub use use_experiment::{track_experiment_click, use_experiment};
 pub use use_meta_tags::{use_meta_tags, MetaData};
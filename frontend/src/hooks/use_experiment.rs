@@ -0,0 +1,100 @@
+use serde::Deserialize;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{console, window};
+use yew::prelude::*;
+
+use crate::api::{get_json, send_json, API_BASE};
+use crate::privacy::is_stats_opt_out;
+
+/// localStorage key holding the random id the backend assigns experiment variants by
+const VISITOR_ID_KEY: &str = "experiment_visitor";
+
+/// Random id of this browser, created on first use, so the reader keeps their variants when
+/// their address changes. Empty when localStorage isn't available, the backend then falls
+/// back to the address and User-Agent.
+fn visitor_id() -> String {
+    let Some(window) = window() else {
+        return String::new();
+    };
+    let Some(storage) = window.local_storage().ok().flatten() else {
+        return String::new();
+    };
+    if let Some(id) = storage.get_item(VISITOR_ID_KEY).ok().flatten() {
+        return id;
+    }
+
+    let Ok(crypto) = window.crypto() else {
+        return String::new();
+    };
+    let id = crypto.random_uuid();
+    if let Err(e) = storage.set_item(VISITOR_ID_KEY, &id) {
+        console::error_1(&format!("Failed to store visitor id: {:?}", e).into());
+    }
+    id
+}
+
+/// Title and summary of a post the backend assigned to this visitor
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AssignedVariant {
+    pub variant: usize,
+    pub title: String,
+    pub summary: String,
+}
+
+/// The visitor's variant of a post with `title_variants` or `summary_variants`, `None` until
+/// it is loaded or when `enabled` is false. With `track_impression` the request counts as an
+/// impression, unless the reader opted out of stats.
+#[hook]
+pub fn use_experiment(
+    slug: &str,
+    enabled: bool,
+    track_impression: bool,
+) -> Option<AssignedVariant> {
+    let assigned = use_state(|| None::<AssignedVariant>);
+    let track_impression = track_impression && !is_stats_opt_out();
+
+    {
+        let assigned = assigned.clone();
+        use_effect_with(
+            (slug.to_string(), enabled, track_impression),
+            move |(slug, enabled, track_impression)| {
+                if *enabled {
+                    let url = format!(
+                        "{}/experiments/{}?track_impression={}&visitor={}",
+                        API_BASE,
+                        slug,
+                        track_impression,
+                        visitor_id()
+                    );
+                    spawn_local(async move {
+                        match get_json::<AssignedVariant>(&url).await {
+                            Ok(variant) => assigned.set(Some(variant)),
+                            Err(err) => console::error_1(
+                                &format!("Failed to load experiment: {}", err).into(),
+                            ),
+                        }
+                    });
+                }
+                || ()
+            },
+        );
+    }
+
+    (*assigned).clone()
+}
+
+/// Count a click on the visitor's variant of a post, sent while the post opens
+pub fn track_experiment_click(slug: &str) {
+    if is_stats_opt_out() {
+        return;
+    }
+    let url = format!(
+        "{}/experiments/{}/click?visitor={}",
+        API_BASE,
+        slug,
+        visitor_id()
+    );
+    if let Err(e) = send_json(&url, &serde_json::Value::Null) {
+        console::error_1(&format!("Failed to track click on {}: {}", slug, e).into());
+    }
+}
@@ -1,3 +1,4 @@
+use content_model::structured_data::{self, SCRIPT_ID};
 use web_sys::window;
 use yew::prelude::*;
 
@@ -11,6 +12,8 @@ pub struct MetaData {
     pub article_published_time: Option<String>,
     pub article_tag: Option<Vec<String>>,
     pub twitter_card_type: Option<String>,
+    /// schema.org JSON-LD of the page, see [`structured_data::graph`]
+    pub structured_data: Option<serde_json::Value>,
 }
 
 impl Default for MetaData {
@@ -27,6 +30,7 @@ impl Default for MetaData {
             article_published_time: None,
             article_tag: None,
             twitter_card_type: Some("summary".to_string()),
+            structured_data: None,
         }
     }
 }
@@ -126,6 +130,26 @@ pub fn use_meta_tags(meta_data: MetaData) {
             if let Some(ref image) = meta_data.image {
                 update_meta_tag("twitter:image", None, image);
             }
+
+            // Replace the JSON-LD of the previous page, pages without any remove it
+            let existing = document.get_element_by_id(SCRIPT_ID);
+            match (&meta_data.structured_data, existing) {
+                (Some(data), Some(script)) => {
+                    script.set_text_content(Some(&structured_data::to_script_json(data)));
+                }
+                (Some(data), None) => {
+                    if let Ok(script) = document.create_element("script") {
+                        script.set_attribute("type", "application/ld+json").ok();
+                        script.set_id(SCRIPT_ID);
+                        script.set_text_content(Some(&structured_data::to_script_json(data)));
+                        if let Some(head) = document.head() {
+                            head.append_child(&script).ok();
+                        }
+                    }
+                }
+                (None, Some(script)) => script.remove(),
+                (None, None) => {}
+            }
         }
 
         || { /* cleanup: restore default meta tags when component unmounts */ }
@@ -1,12 +1,23 @@
+mod admin;
+mod api;
 mod app;
 mod components;
+mod events;
 mod hooks;
 mod markdown;
-mod reading_time;
+mod privacy;
 mod traits;
 
 use app::App;
+use content_model::PRERENDERED_ID;
 
 fn main() {
+    // The app renders the page again, in place of the static copy meta-gen wrote into it
+    if let Some(prerendered) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(PRERENDERED_ID))
+    {
+        prerendered.remove();
+    }
     yew::Renderer::<App>::new().render();
 }
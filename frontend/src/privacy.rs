@@ -0,0 +1,27 @@
+use web_sys::window;
+
+/// localStorage key holding the reader's analytics opt-out
+const STATS_OPT_OUT_KEY: &str = "stats_opt_out";
+
+/// Returns true when the reader opted out of view tracking on this device
+pub fn is_stats_opt_out() -> bool {
+    window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(STATS_OPT_OUT_KEY).ok().flatten())
+        .map(|value| value == "true")
+        .unwrap_or(false)
+}
+
+/// Store (or clear) the reader's analytics opt-out in localStorage
+pub fn set_stats_opt_out(opt_out: bool) {
+    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+        let result = if opt_out {
+            storage.set_item(STATS_OPT_OUT_KEY, "true")
+        } else {
+            storage.remove_item(STATS_OPT_OUT_KEY)
+        };
+        if let Err(e) = result {
+            web_sys::console::error_1(&format!("Failed to store opt-out: {:?}", e).into());
+        }
+    }
+}
@@ -6,8 +6,8 @@ function initCopyButtons() {
     document.querySelectorAll('.copy-code-button:not(.initialized)').forEach(button => {
         button.classList.add('initialized');
         button.addEventListener('click', async function(e) {
+            // Let the click bubble up, the page counts it as a copy_code event
             e.preventDefault();
-            e.stopPropagation();
 
             // The button is inside .code-block-wrapper, and the pre is the next sibling of the button
             const pre = this.nextElementSibling;
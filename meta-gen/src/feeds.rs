@@ -0,0 +1,406 @@
+//! Atom (`feed.xml`), RSS 2.0 (`rss.xml`) and JSON Feed 1.1 (`feed.json`) documents of the
+//! published posts, for the whole site and for every category and tag.
+//!
+//! Entries carry the full post, rendered with the site's markdown pipeline. Feed readers don't
+//! run the SPA, so components are replaced by static HTML and root-relative URLs made absolute.
+
+use chrono::NaiveDate;
+use content_model::markdown::{
+    parse_markdown_with_components, render_markdown_to_static_html, MarkdownPart,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::{escape_html, Post, AUTHOR, SITE_DESCRIPTION, SITE_NAME};
+
+/// The posts of one feed, newest first
+pub struct Feed<'a> {
+    pub title: String,
+    /// Directory of the feed files relative to `dist`, empty for the site feed
+    pub dir: String,
+    /// Page of the site listing the same posts, relative to the base URL
+    pub home_path: String,
+    pub posts: Vec<&'a Post>,
+}
+
+impl Feed<'_> {
+    /// Absolute URL of one of the feed's files
+    fn url(&self, base_url: &str, file: &str) -> String {
+        if self.dir.is_empty() {
+            format!("{}/{}", base_url, file)
+        } else {
+            format!("{}/{}/{}", base_url, self.dir, file)
+        }
+    }
+
+    /// Date of the newest post, feeds require one even when there are no posts
+    fn updated(&self) -> NaiveDate {
+        self.posts
+            .iter()
+            .filter_map(|post| post.frontmatter.date)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Directory name of a category or tag, lowercase with dashes for anything but letters and
+/// digits, e.g. `c-plus-plus` for `C++`
+fn path_segment(name: &str) -> String {
+    name.to_lowercase()
+        .replace('+', " plus ")
+        .replace('#', " sharp ")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The site feed followed by a feed per category and per tag, drafts left out
+pub fn feeds(posts: &[Post]) -> Vec<Feed<'_>> {
+    let published: Vec<&Post> = posts
+        .iter()
+        .filter(|post| post.frontmatter.published)
+        .collect();
+
+    let mut categories: BTreeMap<&str, Vec<&Post>> = BTreeMap::new();
+    let mut tags: BTreeMap<&str, Vec<&Post>> = BTreeMap::new();
+    for post in &published {
+        for category in post.frontmatter.categories() {
+            categories.entry(category).or_default().push(post);
+        }
+        for tag in &post.frontmatter.tags {
+            tags.entry(tag).or_default().push(post);
+        }
+    }
+
+    let mut feeds = vec![Feed {
+        title: SITE_NAME.to_string(),
+        dir: String::new(),
+        home_path: "/".to_string(),
+        posts: published,
+    }];
+    for (kind, listings) in [("category", categories), ("tag", tags)] {
+        feeds.extend(listings.into_iter().map(|(name, posts)| Feed {
+            title: format!("{} - {} {}", SITE_NAME, kind, name),
+            dir: format!("feeds/{}/{}", kind, path_segment(name)),
+            home_path: format!(
+                "/blog?{}={}",
+                kind,
+                crate::sitemap::encode_query_value(name)
+            ),
+            posts,
+        }));
+    }
+    feeds
+}
+
+/// Make a root-relative path absolute, leaving full and protocol-relative URLs alone
+fn absolute_url(base_url: &str, path: &str) -> String {
+    if path.starts_with('/') && !path.starts_with("//") {
+        format!("{}{}", base_url, path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// Make the root-relative `src` and `href` attributes in rendered HTML absolute
+fn absolute_urls(base_url: &str, html: &str) -> String {
+    let mut html = html.to_string();
+    for attribute in ["src", "href"] {
+        for quote in ['"', '\''] {
+            let relative = format!("{}={}/", attribute, quote);
+            let absolute = format!("{}={}{}/", attribute, quote, base_url);
+            html = html
+                .split(&relative)
+                .enumerate()
+                .map(|(i, rest)| match i {
+                    0 => rest.to_string(),
+                    // `//host` is protocol-relative, not a path on this site
+                    _ if rest.starts_with('/') => format!("{}{}", relative, rest),
+                    _ => format!("{}{}", absolute, rest),
+                })
+                .collect();
+        }
+    }
+    html
+}
+
+/// Static HTML in place of a component, which needs the SPA to render
+fn component_fallback(base_url: &str, post_url: &str, part: &MarkdownPart) -> String {
+    let attribute = |name: &str| part.attributes.get(name).map_or("", String::as_str);
+    match part.component_name.as_deref() {
+        Some("Image") => format!(
+            "<p><img src=\"{}\" alt=\"{}\" /></p>\n",
+            escape_html(&absolute_url(base_url, attribute("path"))),
+            escape_html(attribute("alt"))
+        ),
+        Some("Posts") => format!(
+            "<p><a href=\"{}/blog\">Read the posts on {}</a></p>\n",
+            base_url, SITE_NAME
+        ),
+        _ => format!(
+            "<p><em>This part is interactive, <a href=\"{}\">view it on {}</a>.</em></p>\n",
+            escape_html(post_url),
+            SITE_NAME
+        ),
+    }
+}
+
+/// Full HTML of a post for feed readers, with its header image and absolute URLs
+pub fn entry_html(base_url: &str, post: &Post) -> String {
+    let post_url = format!("{}/post/{}", base_url, post.slug);
+    let mut html = String::new();
+
+    if !post.frontmatter.image.is_empty() {
+        html.push_str(&format!(
+            "<p><img src=\"{}\" alt=\"{}\" /></p>\n",
+            escape_html(&absolute_url(base_url, &post.frontmatter.image)),
+            escape_html(&post.frontmatter.title)
+        ));
+    }
+    for part in parse_markdown_with_components(&post.body) {
+        if part.is_component {
+            html.push_str(&component_fallback(base_url, &post_url, &part));
+        } else {
+            html.push_str(&render_markdown_to_static_html(&part.content));
+        }
+    }
+
+    absolute_urls(base_url, &html)
+}
+
+fn author(post: &Post) -> &str {
+    if post.frontmatter.author.is_empty() {
+        AUTHOR
+    } else {
+        &post.frontmatter.author
+    }
+}
+
+fn rfc3339(date: NaiveDate) -> String {
+    format!("{}T00:00:00Z", date)
+}
+
+fn rfc822(date: NaiveDate) -> String {
+    date.format("%a, %d %b %Y 00:00:00 +0000").to_string()
+}
+
+/// Atom 1.0 document of a feed
+pub fn atom(base_url: &str, feed: &Feed) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <title>{}</title>\n  <subtitle>{}</subtitle>\n  <link href=\"{}\" rel=\"self\" type=\"application/atom+xml\" />\n  <link href=\"{}\" rel=\"alternate\" type=\"text/html\" />\n  <id>{}</id>\n  <updated>{}</updated>\n  <author>\n    <name>{}</name>\n  </author>\n",
+        escape_html(&feed.title),
+        escape_html(SITE_DESCRIPTION),
+        escape_html(&feed.url(base_url, "feed.xml")),
+        escape_html(&format!("{}{}", base_url, feed.home_path)),
+        escape_html(&feed.url(base_url, "feed.xml")),
+        rfc3339(feed.updated()),
+        escape_html(AUTHOR),
+    );
+
+    for post in &feed.posts {
+        let url = escape_html(&format!("{}/post/{}", base_url, post.slug));
+        let date = rfc3339(post.frontmatter.date.unwrap_or_default());
+        let categories: String = post
+            .frontmatter
+            .tags
+            .iter()
+            .map(|tag| format!("    <category term=\"{}\" />\n", escape_html(tag)))
+            .collect();
+        xml.push_str(&format!(
+            "  <entry>\n    <title>{}</title>\n    <link href=\"{url}\" rel=\"alternate\" type=\"text/html\" />\n    <id>{url}</id>\n    <published>{date}</published>\n    <updated>{date}</updated>\n    <author>\n      <name>{}</name>\n    </author>\n    <summary>{}</summary>\n{}    <content type=\"html\">{}</content>\n  </entry>\n",
+            escape_html(&post.frontmatter.title),
+            escape_html(author(post)),
+            escape_html(&post.frontmatter.summary),
+            categories,
+            escape_html(&entry_html(base_url, post)),
+        ));
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// RSS 2.0 document of a feed, with the full post in `content:encoded`
+pub fn rss(base_url: &str, feed: &Feed) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n  <channel>\n    <title>{}</title>\n    <link>{}</link>\n    <description>{}</description>\n    <language>en</language>\n    <lastBuildDate>{}</lastBuildDate>\n    <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\" />\n",
+        escape_html(&feed.title),
+        escape_html(&format!("{}{}", base_url, feed.home_path)),
+        escape_html(SITE_DESCRIPTION),
+        rfc822(feed.updated()),
+        escape_html(&feed.url(base_url, "rss.xml")),
+    );
+
+    for post in &feed.posts {
+        let url = escape_html(&format!("{}/post/{}", base_url, post.slug));
+        let categories: String = post
+            .frontmatter
+            .tags
+            .iter()
+            .map(|tag| format!("      <category>{}</category>\n", escape_html(tag)))
+            .collect();
+        xml.push_str(&format!(
+            "    <item>\n      <title>{}</title>\n      <link>{url}</link>\n      <guid isPermaLink=\"true\">{url}</guid>\n      <pubDate>{}</pubDate>\n      <description>{}</description>\n{}      <content:encoded>{}</content:encoded>\n    </item>\n",
+            escape_html(&post.frontmatter.title),
+            rfc822(post.frontmatter.date.unwrap_or_default()),
+            escape_html(&post.frontmatter.summary),
+            categories,
+            escape_html(&entry_html(base_url, post)),
+        ));
+    }
+
+    xml.push_str("  </channel>\n</rss>\n");
+    xml
+}
+
+/// JSON Feed 1.1 document of a feed
+pub fn json_feed(base_url: &str, feed: &Feed) -> String {
+    let items: Vec<serde_json::Value> = feed
+        .posts
+        .iter()
+        .map(|post| {
+            let url = format!("{}/post/{}", base_url, post.slug);
+            let mut item = json!({
+                "id": url,
+                "url": url,
+                "title": post.frontmatter.title,
+                "summary": post.frontmatter.summary,
+                "content_html": entry_html(base_url, post),
+                "date_published": rfc3339(post.frontmatter.date.unwrap_or_default()),
+                "authors": [{ "name": author(post) }],
+                "tags": post.frontmatter.tags,
+            });
+            if !post.frontmatter.image.is_empty() {
+                item["image"] = json!(absolute_url(base_url, &post.frontmatter.image));
+            }
+            item
+        })
+        .collect();
+
+    let document = json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": feed.title,
+        "home_page_url": format!("{}{}", base_url, feed.home_path),
+        "feed_url": feed.url(base_url, "feed.json"),
+        "description": SITE_DESCRIPTION,
+        "language": "en",
+        "authors": [{ "name": AUTHOR }],
+        "items": items,
+    });
+    serde_json::to_string_pretty(&document).expect("JSON values always serialize") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use content_model::PostFrontmatter;
+
+    fn post(slug: &str, yaml: &str, body: &str) -> Post {
+        Post {
+            slug: slug.to_string(),
+            frontmatter: PostFrontmatter::from_yaml(yaml).unwrap(),
+            body: body.to_string(),
+        }
+    }
+
+    fn posts() -> Vec<Post> {
+        vec![
+            post(
+                "new",
+                "title: New & shiny\ndate: 2024-02-01\ntags: [rust, C++]\ncategory: code\nimage: /content/images/top.jpg",
+                "Intro ![diagram](/content/images/d.png)\n\n<Image path=\"/static/images/a.png\" alt=\"A\" />\n\n<OnlinePlaces />\n\n```rust\nfn main() {}\n```",
+            ),
+            post(
+                "old",
+                "title: Old\ndate: 2021-06-09\ntags: rust\ncategory: make",
+                "[external](https://example.org) and [site](/about)",
+            ),
+            post(
+                "draft",
+                "title: Draft\ndate: 2025-01-01\ntags: secret\npublished: false",
+                "Secret",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_feeds_per_category_and_tag() {
+        let posts = posts();
+        let feeds = feeds(&posts);
+        let dirs: Vec<&str> = feeds.iter().map(|feed| feed.dir.as_str()).collect();
+        assert_eq!(
+            dirs,
+            [
+                "",
+                "feeds/category/code",
+                "feeds/category/make",
+                "feeds/tag/c-plus-plus",
+                "feeds/tag/rust"
+            ]
+        );
+        assert_eq!(feeds[0].posts.len(), 2);
+        assert_eq!(feeds[3].home_path, "/blog?tag=C%2B%2B");
+        assert_eq!(feeds[4].posts.len(), 2);
+        assert_eq!(
+            feeds[1].url("https://example.com", "feed.xml"),
+            "https://example.com/feeds/category/code/feed.xml"
+        );
+    }
+
+    #[test]
+    fn test_entry_html() {
+        let posts = posts();
+        let html = entry_html("https://example.com", &posts[0]);
+
+        assert!(html.starts_with(
+            "<p><img src=\"https://example.com/content/images/top.jpg\" alt=\"New &amp; shiny\" />"
+        ));
+        assert!(html.contains("src=\"https://example.com/content/images/d.png\""));
+        assert!(html.contains("<img src=\"https://example.com/static/images/a.png\" alt=\"A\" />"));
+        assert!(html.contains("<a href=\"https://example.com/post/new\">view it on"));
+        assert!(html.contains("<pre class=\"language-rust\">"));
+        assert!(!html.contains("copy-code-button"));
+        assert!(!html.contains("<OnlinePlaces"));
+
+        let html = entry_html("https://example.com", &posts[1]);
+        assert!(html.contains("href=\"https://example.org\""));
+        assert!(html.contains("href=\"https://example.com/about\""));
+        assert_eq!(
+            absolute_urls("https://example.com", "<a href=\"//cdn.example.org/x\">"),
+            "<a href=\"//cdn.example.org/x\">"
+        );
+    }
+
+    #[test]
+    fn test_feed_documents() {
+        let posts = posts();
+        let feeds = feeds(&posts);
+        let base_url = "https://example.com";
+
+        let atom = atom(base_url, &feeds[0]);
+        assert!(atom.contains("<link href=\"https://example.com/feed.xml\" rel=\"self\""));
+        assert!(atom.contains("<updated>2024-02-01T00:00:00Z</updated>"));
+        assert!(atom.contains("<title>New &amp; shiny</title>"));
+        assert!(atom.contains("<content type=\"html\">&lt;p&gt;&lt;img src=&quot;https://example.com/content/images/top.jpg"));
+        assert!(!atom.contains("Draft"));
+        assert!(atom.ends_with("</feed>\n"));
+
+        let rss = rss(base_url, &feeds[1]);
+        assert!(rss.contains("<link>https://example.com/blog?category=code</link>"));
+        assert!(rss.contains("<pubDate>Thu, 01 Feb 2024 00:00:00 +0000</pubDate>"));
+        assert!(rss.contains("<atom:link href=\"https://example.com/feeds/category/code/rss.xml\""));
+        assert!(!rss.contains("<title>Old</title>"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&json_feed(base_url, &feeds[0])).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["feed_url"], "https://example.com/feed.json");
+        assert_eq!(
+            json["items"][0]["image"],
+            "https://example.com/content/images/top.jpg"
+        );
+        assert_eq!(json["items"][1]["date_published"], "2021-06-09T00:00:00Z");
+        assert!(json["items"][1].get("image").is_none());
+    }
+}
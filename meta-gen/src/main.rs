@@ -1,23 +1,51 @@
-//! meta-gen: Generates static HTML sidecar pages for social sharing.
+//! meta-gen: Pre-renders the site's pages as static HTML, for crawlers, social
+//! sharing and readers without JavaScript.
 //!
 //! For each post in `content/posts/*.md`, writes a file at
-//! `dist/post/{slug}/index.html` containing the correct OG and Twitter Card
-//! meta tags derived from the post's YAML frontmatter, plus a JS redirect so
-//! real browsers are immediately sent to the SPA route `/post/{slug}`.
+//! `dist/post/{slug}/index.html`: Trunk's `index.html` with the post's title,
+//! OG and Twitter Card meta tags derived from its YAML frontmatter, schema.org
+//! JSON-LD (`BlogPosting` and `BreadcrumbList`), and the post rendered with
+//! the site's markdown pipeline. The pages in `content/pages/*.md` go to
+//! `dist/{slug}/index.html` the same way, the home page to `dist/index.html`.
+//! The SPA starts on these pages as on any other, see [`prerender`].
+//!
+//! It also writes `dist/sitemap.xml` with the published posts, the pages and
+//! the tag and category listings, and a `dist/robots.txt` pointing to it.
+//!
+//! Feeds of the published posts go to `dist/feed.xml` (Atom), `dist/rss.xml`
+//! and `dist/feed.json` (JSON Feed), with the same three files per category
+//! and tag under `dist/feeds/category/{name}/` and `dist/feeds/tag/{name}/`.
+//!
+//! Drafts are pre-rendered too, marked `noindex` so search engines leave them
+//! out, and are left out of everything else.
+//!
+//! Posts without an `image` in their frontmatter get an Open Graph card at
+//! `dist/og/{slug}.png` as their `og:image`.
 //!
 //! Usage:
-//!   meta-gen [--content-dir <path>] [--dist-dir <path>]
+//!   meta-gen [--content-dir <path>] [--dist-dir <path>] [--base-url <url>]
 //!
 //! Defaults:
 //!   --content-dir  content
 //!   --dist-dir     dist
+//!   --base-url     https://gertjanassies.dev
+
+mod feeds;
+mod og_image;
+mod prerender;
+mod sitemap;
 
+use content_model::structured_data::{self, AUTHOR, SITE_NAME};
+use content_model::PostFrontmatter;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const BASE_URL: &str = "https://gertjanassies.dev";
 const TWITTER_HANDLE: &str = "@major7";
-const FALLBACK_IMAGE: &str = "/static/logo_ga.svg";
+const SITE_DESCRIPTION: &str =
+    "Gertjan Assies personal blog, articles about coding and the maker space";
+/// `og:image` of the pages
+const LOGO_PATH: &str = "/static/logo_ga.svg";
 
 // ---------------------------------------------------------------------------
 // CLI argument parsing (no external deps)
@@ -26,6 +54,7 @@ const FALLBACK_IMAGE: &str = "/static/logo_ga.svg";
 struct Args {
     content_dir: PathBuf,
     dist_dir: PathBuf,
+    base_url: String,
 }
 
 impl Args {
@@ -33,6 +62,7 @@ impl Args {
         let args: Vec<String> = std::env::args().collect();
         let mut content_dir = PathBuf::from("content");
         let mut dist_dir = PathBuf::from("dist");
+        let mut base_url = BASE_URL.to_string();
 
         let mut i = 1;
         while i < args.len() {
@@ -49,6 +79,12 @@ impl Args {
                         dist_dir = PathBuf::from(v);
                     }
                 }
+                "--base-url" => {
+                    i += 1;
+                    if let Some(v) = args.get(i) {
+                        base_url = v.trim_end_matches('/').to_string();
+                    }
+                }
                 other => {
                     eprintln!("Unknown argument: {other}");
                     std::process::exit(1);
@@ -60,112 +96,94 @@ impl Args {
         Args {
             content_dir,
             dist_dir,
+            base_url,
         }
     }
 }
 
 // ---------------------------------------------------------------------------
-// Frontmatter types — mirrors frontend/src/components/posts.rs
+// Content
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Default)]
-struct PostFrontmatter {
-    title: String,
-    date: String,
-    summary: String,
-    author: String,
-    tags: Vec<String>,
-    image: String,
-    published: bool,
+/// A post read from `content/posts`, drafts included
+struct Post {
+    slug: String,
+    frontmatter: PostFrontmatter,
+    /// Markdown after the frontmatter
+    body: String,
 }
 
-// ---------------------------------------------------------------------------
-// YAML frontmatter parser — mirrors the hand-rolled parser in the frontend
-// ---------------------------------------------------------------------------
-
-fn parse_yaml_frontmatter(yaml_str: &str) -> PostFrontmatter {
-    let mut fm = PostFrontmatter {
-        published: true,
-        ..Default::default()
-    };
-
-    for line in yaml_str.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+/// Read the posts with valid frontmatter, newest first
+fn load_posts(posts_dir: &Path) -> Vec<Post> {
+    let entries = content_model::markdown_files(posts_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", posts_dir.display(), e);
+        std::process::exit(1);
+    });
 
-        if let Some((key, value)) = line.split_once(':') {
-            let key = key.trim();
-            // A value may contain colons (e.g. in titles), so we split on the
-            // first colon only and reassemble the rest.
-            let value = value.trim().trim_matches('"').trim_matches('\'');
-
-            match key {
-                "title" => fm.title = value.to_string(),
-                "date" => fm.date = value.trim_matches('"').trim_matches('\'').to_string(),
-                "summary" => fm.summary = value.to_string(),
-                "author" => fm.author = value.to_string(),
-                "image" => fm.image = value.to_string(),
-                "published" => fm.published = value.to_lowercase() == "true",
-                "tags" => {
-                    let raw = if value.starts_with('[') && value.ends_with(']') {
-                        &value[1..value.len() - 1]
-                    } else {
-                        value
-                    };
-                    fm.tags = raw
-                        .split(',')
-                        .map(|t| t.trim().trim_matches('"').trim_matches('\'').to_string())
-                        .filter(|t| !t.is_empty())
-                        .collect();
-                }
-                _ => {}
+    let mut posts = Vec::new();
+    for (slug, path) in entries {
+        let raw = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("  [WARN] Could not read {}: {}", path.display(), e);
+                continue;
             }
+        };
+
+        let checked = content_model::parse(&raw)
+            .and_then(|(fm, body)| fm.validate().map(|_| (fm, body.to_string())));
+        match checked {
+            Ok((frontmatter, body)) => posts.push(Post {
+                slug,
+                frontmatter,
+                body,
+            }),
+            Err(e) => eprintln!("  [WARN] Skipping {}: {}", path.display(), e),
         }
     }
 
-    fm
+    posts.sort_by(|a, b| {
+        b.frontmatter
+            .date
+            .cmp(&a.frontmatter.date)
+            .then_with(|| a.slug.cmp(&b.slug))
+    });
+    posts
 }
 
-fn parse_post(raw: &str) -> Option<PostFrontmatter> {
-    let lines: Vec<&str> = raw.lines().collect();
-
-    // Require the first line (with any line ending) to be a frontmatter delimiter.
-    if lines.first().map(|l| l.trim()) != Some("---") {
-        return None;
-    }
-    let mut frontmatter_end = None;
-    let mut found_first = false;
-
-    for (i, line) in lines.iter().enumerate() {
-        if line.trim() == "---" {
-            if found_first {
-                frontmatter_end = Some(i);
-                break;
-            } else {
-                found_first = true;
+/// Slug and markdown of the pages in `content/pages`, which have no frontmatter
+fn load_pages(pages_dir: &Path) -> Vec<(String, String)> {
+    content_model::markdown_files(pages_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(slug, path)| match fs::read_to_string(&path) {
+            Ok(markdown) => Some((slug, markdown)),
+            Err(e) => {
+                eprintln!("  [WARN] Could not read {}: {}", path.display(), e);
+                None
             }
-        }
-    }
-
-    frontmatter_end.map(|end| {
-        let fm_str = lines[1..end].join("\n");
-        parse_yaml_frontmatter(&fm_str)
-    })
+        })
+        .collect()
 }
 
-// ---------------------------------------------------------------------------
-// Date normalisation: "20240101" → "2024-01-01", passthrough if already ISO
-// ---------------------------------------------------------------------------
+/// Write a generated file, creating its directory first
+fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> bool {
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("  [WARN] Could not create {}: {}", dir.display(), e);
+            return false;
+        }
+    }
 
-fn normalise_date(date: &str) -> String {
-    let d = date.trim().trim_matches('"').trim_matches('\'');
-    if d.len() == 8 && d.chars().all(|c| c.is_ascii_digit()) {
-        // YYYYMMDD → YYYY-MM-DD
-        format!("{}-{}-{}", &d[0..4], &d[4..6], &d[6..8])
-    } else {
-        d.to_string()
+    match fs::write(path, contents) {
+        Ok(_) => {
+            println!("  [OK]   {}", path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("  [WARN] Could not write {}: {}", path.display(), e);
+            false
+        }
     }
 }
 
@@ -181,101 +199,167 @@ fn escape_html(s: &str) -> String {
         .replace('\'', "&#39;")
 }
 
-fn generate_html(slug: &str, fm: &PostFrontmatter) -> String {
-    let title = escape_html(&fm.title);
-    let full_title = if fm.title.is_empty() {
-        "gertjanassies.dev".to_string()
-    } else {
-        format!("{} - gertjanassies.dev", title)
-    };
-
-    let description = escape_html(&fm.summary);
-    let url = escape_html(&format!("{}/post/{}", BASE_URL, slug));
-
-    let image = if fm.image.is_empty() {
-        escape_html(&format!("{}{}", BASE_URL, FALLBACK_IMAGE))
-    } else {
-        escape_html(&format!("{}{}", BASE_URL, fm.image))
-    };
-
-    let author = escape_html(&fm.author);
-    let date_iso = normalise_date(&fm.date);
-    let published_time = if date_iso.is_empty() {
-        String::new()
-    } else {
-        format!("{}T00:00:00Z", date_iso)
-    };
-
-    // Build article:tag lines
-    let tag_metas: String = fm
-        .tags
-        .iter()
-        .map(|t| {
-            format!(
-                "  <meta property=\"article:tag\" content=\"{}\" />\n",
-                escape_html(t)
-            )
-        })
-        .collect();
+/// JSON-LD of a post page: the post itself and its place under the blog
+fn post_structured_data(base_url: &str, post: &Post, image: &str) -> String {
+    let fm = &post.frontmatter;
+    let word_count = reading_time::count_words(&post.body);
+    let path = format!("/post/{}", post.slug);
+    structured_data::to_script_json(&structured_data::graph(vec![
+        structured_data::blog_posting(base_url, &post.slug, fm, image, word_count),
+        structured_data::breadcrumbs(
+            base_url,
+            &[("Home", "/"), ("Blog", "/blog"), (&fm.title, &path)],
+        ),
+    ]))
+}
 
-    let published_meta = if published_time.is_empty() {
-        String::new()
-    } else {
-        format!(
-            "  <meta property=\"article:published_time\" content=\"{}\" />\n",
-            published_time
-        )
-    };
+/// What the head of a pre-rendered page describes, the text already escaped
+struct Head {
+    title: String,
+    description: String,
+    url: String,
+    image: String,
+    /// `og:type`, `article` or `website`
+    kind: &'static str,
+    twitter_card: &'static str,
+    /// `robots` meta tag, drafts are not indexed
+    robots: &'static str,
+    /// Open Graph tags particular to the page, one per line
+    open_graph: String,
+    /// JSON-LD, see [`structured_data::to_script_json`]
+    structured_data: String,
+}
 
-    let author_meta = if fm.author.is_empty() {
-        String::new()
-    } else {
-        format!(
-            "  <meta property=\"article:author\" content=\"{}\" />\n",
-            author
-        )
-    };
+/// Title, description, canonical URL, Open Graph and Twitter Card tags and JSON-LD of a page,
+/// which replace those of the SPA's `index.html`
+fn head_html(head: &Head) -> String {
+    let Head {
+        title,
+        description,
+        url,
+        image,
+        kind,
+        twitter_card,
+        robots,
+        open_graph,
+        structured_data,
+    } = head;
+    let script_id = structured_data::SCRIPT_ID;
 
     format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-  <meta charset="UTF-8" />
-  <meta name="viewport" content="width=device-width, initial-scale=1.0" />
-  <title>{full_title}</title>
+        r#"  <title>{title}</title>
   <meta name="description" content="{description}" />
-  <meta name="robots" content="index, follow" />
+  <meta name="robots" content="{robots}" />
   <link rel="canonical" href="{url}" />
 
   <!-- Open Graph -->
-  <meta property="og:title" content="{full_title}" />
+  <meta property="og:title" content="{title}" />
   <meta property="og:description" content="{description}" />
   <meta property="og:image" content="{image}" />
   <meta property="og:url" content="{url}" />
-  <meta property="og:type" content="article" />
-  <meta property="og:site_name" content="gertjanassies.dev" />
-{author_meta}{published_meta}{tag_metas}
+  <meta property="og:type" content="{kind}" />
+  <meta property="og:site_name" content="{SITE_NAME}" />
+{open_graph}
   <!-- Twitter Card -->
-  <meta name="twitter:card" content="summary_large_image" />
+  <meta name="twitter:card" content="{twitter_card}" />
   <meta name="twitter:creator" content="{TWITTER_HANDLE}" />
   <meta name="twitter:site" content="{TWITTER_HANDLE}" />
-  <meta name="twitter:title" content="{full_title}" />
+  <meta name="twitter:title" content="{title}" />
   <meta name="twitter:description" content="{description}" />
   <meta name="twitter:image" content="{image}" />
 
-  <!-- Redirect real browsers to the SPA immediately -->
-  <script>window.location.replace(window.location.pathname.replace(/\/index\.html?$/, ''));</script>
-</head>
-<body>
-  <noscript>
-    <p><a href=".">Read: {title}</a></p>
-  </noscript>
-</body>
-</html>
+  <!-- schema.org structured data -->
+  <script type="application/ld+json" id="{script_id}">{structured_data}</script>
 "#
     )
 }
 
+fn post_head(base_url: &str, post: &Post) -> String {
+    let (slug, fm) = (&post.slug, &post.frontmatter);
+    let title = if fm.title.is_empty() {
+        SITE_NAME.to_string()
+    } else {
+        format!("{} - {}", escape_html(&fm.title), SITE_NAME)
+    };
+
+    // Posts without an image of their own use their generated card
+    let (image_url, mut open_graph) = if fm.image.is_empty() {
+        let size = format!(
+            "  <meta property=\"og:image:width\" content=\"{}\" />\n  <meta property=\"og:image:height\" content=\"{}\" />\n",
+            og_image::WIDTH,
+            og_image::HEIGHT
+        );
+        (format!("{}{}", base_url, og_image::card_path(slug)), size)
+    } else {
+        (format!("{}{}", base_url, fm.image), String::new())
+    };
+
+    if !fm.author.is_empty() {
+        open_graph.push_str(&format!(
+            "  <meta property=\"article:author\" content=\"{}\" />\n",
+            escape_html(&fm.author)
+        ));
+    }
+    if let Some(date) = fm.date {
+        open_graph.push_str(&format!(
+            "  <meta property=\"article:published_time\" content=\"{}T00:00:00Z\" />\n",
+            date
+        ));
+    }
+    for tag in &fm.tags {
+        open_graph.push_str(&format!(
+            "  <meta property=\"article:tag\" content=\"{}\" />\n",
+            escape_html(tag)
+        ));
+    }
+
+    head_html(&Head {
+        title,
+        description: escape_html(&fm.summary),
+        url: escape_html(&format!("{}/post/{}", base_url, slug)),
+        structured_data: post_structured_data(base_url, post, &image_url),
+        image: escape_html(&image_url),
+        kind: "article",
+        twitter_card: "summary_large_image",
+        robots: if fm.published {
+            "index, follow"
+        } else {
+            "noindex, nofollow"
+        },
+        open_graph,
+    })
+}
+
+/// Head of a page in `content/pages`
+fn page_head(base_url: &str, slug: &str) -> String {
+    let title = match slug {
+        "home" => SITE_NAME.to_string(),
+        slug => {
+            let mut name = escape_html(slug);
+            if let Some(first) = name.get_mut(..1) {
+                first.make_ascii_uppercase();
+            }
+            format!("{} - {}", name, SITE_NAME)
+        }
+    };
+    // The author's profiles are listed with their icons in the frontend, which adds them to the
+    // about page's JSON-LD once it runs
+    let structured_data =
+        structured_data::to_script_json(&structured_data::page(base_url, slug, &[]));
+
+    head_html(&Head {
+        title,
+        description: escape_html(SITE_DESCRIPTION),
+        url: escape_html(&format!("{}{}", base_url, sitemap::page_path(slug))),
+        image: escape_html(&format!("{}{}", base_url, LOGO_PATH)),
+        kind: "website",
+        twitter_card: "summary",
+        robots: "index, follow",
+        open_graph: String::new(),
+        structured_data,
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Main
 // ---------------------------------------------------------------------------
@@ -293,80 +377,82 @@ fn main() {
         std::process::exit(1);
     }
 
-    let mut entries: Vec<PathBuf> = fs::read_dir(&posts_dir)
-        .unwrap_or_else(|e| {
-            eprintln!("Failed to read {}: {}", posts_dir.display(), e);
-            std::process::exit(1);
-        })
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| p.extension().map(|ext| ext == "md").unwrap_or(false))
-        .collect();
-
-    entries.sort();
+    let posts = load_posts(&posts_dir);
+    let pages = load_pages(&args.content_dir.join("pages"));
+    // Read before the home page is pre-rendered over it
+    let template = prerender::Template::load(&args.dist_dir);
 
     let mut generated = 0usize;
-    let mut skipped = 0usize;
-
-    for path in &entries {
-        let slug = match path.file_stem().and_then(|s| s.to_str()) {
-            Some(s) => s.to_string(),
-            None => continue,
-        };
-
-        let raw = match fs::read_to_string(path) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("  [WARN] Could not read {}: {}", path.display(), e);
-                continue;
-            }
-        };
+    let mut drafts = 0usize;
 
-        let fm = match parse_post(&raw) {
-            Some(f) => f,
-            None => {
-                eprintln!("  [WARN] No frontmatter in {}", path.display());
-                continue;
-            }
-        };
-
-        if !fm.published {
-            println!("  [SKIP] {} (draft)", slug);
-            skipped += 1;
-            continue;
+    for post in &posts {
+        if !post.frontmatter.published {
+            println!("  [DRAFT] {} (noindex)", post.slug);
+            drafts += 1;
         }
 
-        let out_dir = dist_post_dir.join(&slug);
-        if let Err(e) = fs::create_dir_all(&out_dir) {
-            eprintln!("  [WARN] Could not create {}: {}", out_dir.display(), e);
-            continue;
+        if post.frontmatter.image.is_empty() {
+            let card = args
+                .dist_dir
+                .join(og_image::card_path(&post.slug).trim_start_matches('/'));
+            write_file(&card, og_image::render(post));
         }
 
-        let html = generate_html(&slug, &fm);
-        let out_file = out_dir.join("index.html");
+        let html = template.render(
+            &post_head(&args.base_url, post),
+            &prerender::post(post, &posts),
+        );
+        let out_file = dist_post_dir.join(&post.slug).join("index.html");
+        if write_file(&out_file, &html) {
+            generated += 1;
+        }
+    }
 
-        match fs::write(&out_file, html) {
-            Ok(_) => {
-                println!("  [OK]   {}", out_file.display());
-                generated += 1;
-            }
-            Err(e) => {
-                eprintln!("  [WARN] Could not write {}: {}", out_file.display(), e);
-            }
+    for (slug, markdown) in &pages {
+        let html = template.render(
+            &page_head(&args.base_url, slug),
+            &prerender::page(slug, markdown, &posts),
+        );
+        let out_file = args
+            .dist_dir
+            .join(sitemap::page_path(slug).trim_start_matches('/'))
+            .join("index.html");
+        if write_file(&out_file, &html) {
+            generated += 1;
         }
     }
 
     println!(
-        "\nmeta-gen: generated {} pages, skipped {} drafts.",
-        generated, skipped
+        "\nmeta-gen: generated {} pages, {} of them drafts.",
+        generated, drafts
     );
 
-    if generated == 0 && entries.is_empty() {
+    if posts.is_empty() {
         eprintln!(
             "Warning: no markdown files found in {}",
             posts_dir.display()
         );
     }
+
+    let page_slugs: Vec<String> = pages.iter().map(|(slug, _)| slug.clone()).collect();
+    write_file(
+        &args.dist_dir.join("sitemap.xml"),
+        sitemap::sitemap(&args.base_url, &posts, &page_slugs),
+    );
+    write_file(
+        &args.dist_dir.join("robots.txt"),
+        sitemap::robots(&args.base_url),
+    );
+
+    for feed in feeds::feeds(&posts) {
+        let dir = args.dist_dir.join(&feed.dir);
+        write_file(&dir.join("feed.xml"), feeds::atom(&args.base_url, &feed));
+        write_file(&dir.join("rss.xml"), feeds::rss(&args.base_url, &feed));
+        write_file(
+            &dir.join("feed.json"),
+            feeds::json_feed(&args.base_url, &feed),
+        );
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -377,61 +463,6 @@ fn main() {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_normalise_date_compact() {
-        assert_eq!(normalise_date("20240101"), "2024-01-01");
-    }
-
-    #[test]
-    fn test_normalise_date_iso_passthrough() {
-        assert_eq!(normalise_date("2024-01-01"), "2024-01-01");
-    }
-
-    #[test]
-    fn test_normalise_date_quoted() {
-        assert_eq!(normalise_date("\"2024-03-10\""), "2024-03-10");
-    }
-
-    #[test]
-    fn test_parse_yaml_frontmatter_basic() {
-        let yaml = r#"title: My Post
-date: "2024-01-01"
-summary: A great article
-author: Gertjan Assies
-tags: rust, wasm, featured
-image: /content/images/test.png
-published: true"#;
-
-        let fm = parse_yaml_frontmatter(yaml);
-        assert_eq!(fm.title, "My Post");
-        assert_eq!(fm.summary, "A great article");
-        assert_eq!(fm.author, "Gertjan Assies");
-        assert_eq!(fm.image, "/content/images/test.png");
-        assert!(fm.published);
-        assert_eq!(fm.tags, vec!["rust", "wasm", "featured"]);
-    }
-
-    #[test]
-    fn test_parse_yaml_frontmatter_bracket_tags() {
-        let yaml = "tags: [rust, wasm]";
-        let fm = parse_yaml_frontmatter(yaml);
-        assert_eq!(fm.tags, vec!["rust", "wasm"]);
-    }
-
-    #[test]
-    fn test_parse_post_no_frontmatter() {
-        let raw = "Just some content without frontmatter";
-        assert!(parse_post(raw).is_none());
-    }
-
-    #[test]
-    fn test_parse_post_with_frontmatter() {
-        let raw = "---\ntitle: Hello\npublished: true\n---\n\nContent here";
-        let fm = parse_post(raw).unwrap();
-        assert_eq!(fm.title, "Hello");
-        assert!(fm.published);
-    }
-
     #[test]
     fn test_escape_html() {
         assert_eq!(escape_html("a & b < c > d"), "a &amp; b &lt; c &gt; d");
@@ -439,17 +470,22 @@ published: true"#;
     }
 
     #[test]
-    fn test_generate_html_contains_meta_tags() {
+    fn test_post_head_contains_meta_tags() {
         let fm = PostFrontmatter {
             title: "Test Post".to_string(),
-            date: "20240101".to_string(),
+            date: content_model::parse_date("20240101"),
             summary: "A test summary".to_string(),
             author: "Gertjan Assies".to_string(),
             tags: vec!["rust".to_string()],
             image: "/content/images/test.png".to_string(),
-            published: true,
+            ..Default::default()
         };
-        let html = generate_html("test_slug", &fm);
+        let post = Post {
+            slug: "test_slug".to_string(),
+            frontmatter: fm,
+            body: "Three little words".to_string(),
+        };
+        let html = post_head(BASE_URL, &post);
 
         assert!(html.contains("og:title"));
         assert!(html.contains("og:description"));
@@ -459,19 +495,65 @@ published: true"#;
         assert!(html.contains("article:published_time"));
         assert!(html.contains("2024-01-01T00:00:00Z"));
         assert!(html.contains("article:tag"));
-        assert!(html.contains(r#"window.location.replace(window.location.pathname.replace(/\/index\.html?$/, ''))"#));
-        assert!(html.contains("Test Post - gertjanassies.dev"));
+        assert!(html.contains("<title>Test Post - gertjanassies.dev</title>"));
+        assert!(html.contains(r#"<meta property="og:type" content="article" />"#));
+        assert!(html.contains(r#"<script type="application/ld+json" id="structured-data">"#));
+        assert!(html.contains(r#""@type":"BlogPosting""#));
+        assert!(html.contains(r#""wordCount":3"#));
+        assert!(html.contains(r#""image":"https://gertjanassies.dev/content/images/test.png""#));
+        assert!(html.contains(r#""@type":"BreadcrumbList""#));
     }
 
     #[test]
-    fn test_generate_html_fallback_image() {
+    fn test_post_head_fallback_image() {
         let fm = PostFrontmatter {
             title: "No Image Post".to_string(),
             image: String::new(),
             published: true,
             ..Default::default()
         };
-        let html = generate_html("no_image", &fm);
-        assert!(html.contains(FALLBACK_IMAGE));
+        let post = Post {
+            slug: "no_image".to_string(),
+            frontmatter: fm,
+            body: String::new(),
+        };
+        let html = post_head(BASE_URL, &post);
+        assert!(html.contains(
+            r#"<meta property="og:image" content="https://gertjanassies.dev/og/no_image.png" />"#
+        ));
+        assert!(html.contains(r#"<meta property="og:image:width" content="1200" />"#));
+    }
+
+    #[test]
+    fn test_draft_head_is_noindex() {
+        let mut post = Post {
+            slug: "draft".to_string(),
+            frontmatter: PostFrontmatter {
+                title: "Draft".to_string(),
+                published: false,
+                ..Default::default()
+            },
+            body: String::new(),
+        };
+        assert!(post_head(BASE_URL, &post)
+            .contains(r#"<meta name="robots" content="noindex, nofollow" />"#));
+
+        post.frontmatter.published = true;
+        assert!(post_head(BASE_URL, &post)
+            .contains(r#"<meta name="robots" content="index, follow" />"#));
+    }
+
+    #[test]
+    fn test_page_head() {
+        let html = page_head(BASE_URL, "about");
+        assert!(html.contains("<title>About - gertjanassies.dev</title>"));
+        assert!(html.contains(r#"<link rel="canonical" href="https://gertjanassies.dev/about" />"#));
+        assert!(html.contains(r#"<meta property="og:type" content="website" />"#));
+        assert!(html.contains(r#""@type":"Person""#));
+
+        let html = page_head(BASE_URL, "home");
+        assert!(html.contains("<title>gertjanassies.dev</title>"));
+        assert!(html.contains(r#"<link rel="canonical" href="https://gertjanassies.dev/" />"#));
+        assert!(html.contains(r#""@type":"WebSite""#));
     }
 }
@@ -0,0 +1,374 @@
+//! Open Graph cards for posts without an `image`: a 1200×630 PNG with the title, date, tags and
+//! site logo, drawn with the fonts bundled with the frontend. `ab_glyph` reads the glyphs,
+//! `tiny-skia` fills them and the logo's shapes and encodes the PNG.
+
+use ab_glyph::{Font, FontRef, OutlineCurve, Point};
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+use crate::{Post, SITE_NAME};
+
+type Color = [u8; 3];
+
+pub const WIDTH: usize = 1200;
+pub const HEIGHT: usize = 630;
+
+const TITLE_FONT: &[u8] = include_bytes!("../../frontend/static/fonts/RedHatText-VF.ttf");
+const MONO_FONT: &[u8] = include_bytes!("../../frontend/static/fonts/FiraCode.ttf");
+const LOGO: &str = include_str!("../../frontend/static/logo_ga.svg");
+
+const BACKGROUND: Color = [17, 17, 17];
+const TEXT: Color = [238, 238, 238];
+const MUTED: Color = [136, 136, 136];
+/// `--accent` of the site's colour palette
+const ACCENT: Color = [1, 192, 240];
+/// Steelblue, the colour of the logo
+const LOGO_COLOR: Color = [70, 130, 180];
+
+const MARGIN: f32 = 72.0;
+const LOGO_HEIGHT: f32 = 110.0;
+/// Top of the first title line
+const TITLE_TOP: f32 = 220.0;
+/// Title sizes tried from large to small until the title fits
+const TITLE_SIZES: [f32; 3] = [72.0, 60.0, 52.0];
+const MAX_TITLE_LINES: usize = 3;
+const META_SIZE: f32 = 28.0;
+
+/// URL path of a post's card, relative to `dist` as well
+pub fn card_path(slug: &str) -> String {
+    format!("/og/{}.png", slug)
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(color[0], color[1], color[2], 255);
+    paint
+}
+
+/// Pixels per font unit at a font size
+fn font_scale(font: &FontRef, size: f32) -> f32 {
+    size / font
+        .units_per_em()
+        .expect("the bundled fonts have units per em")
+}
+
+/// Width of a line of text in pixels
+fn text_width(font: &FontRef, text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| font.h_advance_unscaled(font.glyph_id(c)))
+        .sum::<f32>()
+        * font_scale(font, size)
+}
+
+/// Draw a line of text starting at `x` on the `baseline`, returning where it ends
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontRef,
+    text: &str,
+    (x, baseline): (f32, f32),
+    size: f32,
+    color: Color,
+) -> f32 {
+    let scale = font_scale(font, size);
+    let mut pen = x;
+    let mut path = PathBuilder::new();
+    for c in text.chars() {
+        let glyph = font.glyph_id(c);
+        // Font units point up, pixels down
+        let at = |point: Point| (pen + point.x * scale, baseline - point.y * scale);
+        let mut end = None;
+        for curve in font
+            .outline(glyph)
+            .map(|outline| outline.curves)
+            .unwrap_or_default()
+        {
+            let (start, to) = match curve {
+                OutlineCurve::Line(from, to) => (from, to),
+                OutlineCurve::Quad(from, _, to) => (from, to),
+                OutlineCurve::Cubic(from, _, _, to) => (from, to),
+            };
+            // A curve not starting where the last one ended starts a new contour
+            if end != Some(start) {
+                let (x, y) = at(start);
+                path.move_to(x, y);
+            }
+            match curve {
+                OutlineCurve::Line(_, to) => {
+                    let (x, y) = at(to);
+                    path.line_to(x, y);
+                }
+                OutlineCurve::Quad(_, control, to) => {
+                    let ((x1, y1), (x, y)) = (at(control), at(to));
+                    path.quad_to(x1, y1, x, y);
+                }
+                OutlineCurve::Cubic(_, control1, control2, to) => {
+                    let ((x1, y1), (x2, y2), (x, y)) = (at(control1), at(control2), at(to));
+                    path.cubic_to(x1, y1, x2, y2, x, y);
+                }
+            }
+            end = Some(to);
+        }
+        pen += font.h_advance_unscaled(glyph) * scale;
+    }
+    if let Some(path) = path.finish() {
+        pixmap.fill_path(
+            &path,
+            &paint(color),
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+    pen
+}
+
+/// Split text into lines that fit `max_width`, a word wider than a line gets a line of its own
+fn wrap(font: &FontRef, text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if text_width(font, &format!("{} {}", line, word), size) <= max_width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// The largest size at which the title fits, or the smallest with the overflow cut off by an
+/// ellipsis
+fn fit_title(font: &FontRef, title: &str, max_width: f32) -> (f32, Vec<String>) {
+    for size in TITLE_SIZES {
+        let lines = wrap(font, title, size, max_width);
+        if lines.len() <= MAX_TITLE_LINES {
+            return (size, lines);
+        }
+    }
+
+    let size = TITLE_SIZES[TITLE_SIZES.len() - 1];
+    let mut lines = wrap(font, title, size, max_width);
+    lines.truncate(MAX_TITLE_LINES);
+    let last = lines
+        .last_mut()
+        .expect("a title that doesn't fit has lines");
+    while text_width(font, &format!("{}…", last), size) > max_width {
+        match last.rfind(' ') {
+            Some(space) => last.truncate(space),
+            None => break,
+        }
+    }
+    last.push('…');
+    (size, lines)
+}
+
+/// Value of an attribute of an SVG element, or of the same property in its `style`
+fn svg_number(element: &str, name: &str) -> Option<f32> {
+    let attribute = |name: &str| {
+        let start = element.find(&format!(" {}=\"", name))? + name.len() + 3;
+        let end = element[start..].find('"')?;
+        Some(&element[start..start + end])
+    };
+    attribute(name)
+        .or_else(|| {
+            attribute("style")?
+                .split(';')
+                .filter_map(|declaration| declaration.split_once(':'))
+                .find(|(property, _)| property.trim() == name)
+                .map(|(_, value)| value.trim().trim_end_matches("px"))
+        })?
+        .parse()
+        .ok()
+}
+
+/// Draw the logo from the circles, lines and text in its SVG, all in one colour, scaled to
+/// `height` with its top left corner at `origin`
+fn draw_logo(pixmap: &mut Pixmap, font: &FontRef, origin: (f32, f32), height: f32) {
+    let svg_height = LOGO
+        .split('<')
+        .find(|element| element.starts_with("svg "))
+        .and_then(|element| svg_number(element, "height"))
+        .unwrap_or(height);
+    let scale = height / svg_height;
+    let at = |x: f32, y: f32| (origin.0 + x * scale, origin.1 + y * scale);
+
+    for element in LOGO.split('<') {
+        let number = |name| svg_number(element, name).unwrap_or(0.0);
+        let stroke = Stroke {
+            width: number("stroke-width") * scale,
+            ..Stroke::default()
+        };
+        if element.starts_with("circle ") {
+            let (cx, cy) = at(number("cx"), number("cy"));
+            if let Some(circle) = PathBuilder::from_circle(cx, cy, number("r") * scale) {
+                pixmap.stroke_path(
+                    &circle,
+                    &paint(LOGO_COLOR),
+                    &stroke,
+                    Transform::identity(),
+                    None,
+                );
+            }
+        } else if element.starts_with("line ") {
+            let mut line = PathBuilder::new();
+            let ((x1, y1), (x2, y2)) = (
+                at(number("x1"), number("y1")),
+                at(number("x2"), number("y2")),
+            );
+            line.move_to(x1, y1);
+            line.line_to(x2, y2);
+            if let Some(line) = line.finish() {
+                pixmap.stroke_path(
+                    &line,
+                    &paint(LOGO_COLOR),
+                    &stroke,
+                    Transform::identity(),
+                    None,
+                );
+            }
+        } else if element.starts_with("text ") {
+            let text = element.split_once('>').map_or("", |(_, text)| text.trim());
+            let size = number("font-size") * scale;
+            draw_text(
+                pixmap,
+                font,
+                text,
+                at(number("x"), number("y")),
+                size,
+                LOGO_COLOR,
+            );
+        }
+    }
+}
+
+/// The card of a post as a PNG
+pub fn render(post: &Post) -> Vec<u8> {
+    let title_font = FontRef::try_from_slice(TITLE_FONT).expect("the bundled title font is valid");
+    let mono_font =
+        FontRef::try_from_slice(MONO_FONT).expect("the bundled monospace font is valid");
+    let (width, height) = (WIDTH as f32, HEIGHT as f32);
+    let text_width_max = width - 2.0 * MARGIN;
+
+    let mut pixmap = Pixmap::new(WIDTH as u32, HEIGHT as u32).expect("the card has a size");
+    let [r, g, b] = BACKGROUND;
+    pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, 255));
+    let accent_bar = Rect::from_xywh(0.0, 0.0, width, 8.0).expect("the bar has a size");
+    pixmap.fill_rect(accent_bar, &paint(ACCENT), Transform::identity(), None);
+
+    draw_logo(&mut pixmap, &title_font, (MARGIN, 56.0), LOGO_HEIGHT);
+    let site_width = text_width(&mono_font, SITE_NAME, META_SIZE);
+    draw_text(
+        &mut pixmap,
+        &mono_font,
+        SITE_NAME,
+        (
+            width - MARGIN - site_width,
+            56.0 + LOGO_HEIGHT / 2.0 + META_SIZE / 3.0,
+        ),
+        META_SIZE,
+        MUTED,
+    );
+
+    let (size, lines) = fit_title(&title_font, &post.frontmatter.title, text_width_max);
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = TITLE_TOP + size + i as f32 * size * 1.2;
+        draw_text(
+            &mut pixmap,
+            &title_font,
+            line,
+            (MARGIN, baseline),
+            size,
+            TEXT,
+        );
+    }
+
+    // The date, followed by as many tags as fit
+    let baseline = height - MARGIN;
+    let mut x = draw_text(
+        &mut pixmap,
+        &mono_font,
+        &post.frontmatter.date_string(),
+        (MARGIN, baseline),
+        META_SIZE,
+        ACCENT,
+    );
+    for tag in &post.frontmatter.tags {
+        let tag = format!("  #{}", tag);
+        if x + text_width(&mono_font, &tag, META_SIZE) > width - MARGIN {
+            break;
+        }
+        x = draw_text(
+            &mut pixmap,
+            &mono_font,
+            &tag,
+            (x, baseline),
+            META_SIZE,
+            MUTED,
+        );
+    }
+
+    pixmap
+        .encode_png()
+        .expect("encoding a PNG in memory can't fail")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use content_model::PostFrontmatter;
+
+    #[test]
+    fn test_fit_title() {
+        let font = FontRef::try_from_slice(TITLE_FONT).unwrap();
+
+        let (size, lines) = fit_title(&font, "Hello  World", 1056.0);
+        assert_eq!((size, lines), (72.0, vec!["Hello World".to_string()]));
+
+        let long = "word ".repeat(60);
+        let (size, lines) = fit_title(&font, &long, 1056.0);
+        assert_eq!(size, 52.0);
+        assert_eq!(lines.len(), MAX_TITLE_LINES);
+        assert!(lines[2].ends_with("word…"));
+        assert!(lines
+            .iter()
+            .all(|line| text_width(&font, line, size) <= 1056.0));
+    }
+
+    #[test]
+    fn test_render_card() {
+        let post = Post {
+            slug: "card".to_string(),
+            frontmatter: PostFrontmatter::from_yaml(
+                "title: A card for a post\ndate: 2024-01-01\ntags: [rust, wasm]",
+            )
+            .unwrap(),
+            body: String::new(),
+        };
+        let png = render(&post);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // Width and height in the header
+        assert_eq!(&png[16..24], &[0, 0, 4, 176, 0, 0, 2, 118]);
+
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        let rgb = |x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            [pixel.red(), pixel.green(), pixel.blue()]
+        };
+        assert_eq!(rgb(600, 2), ACCENT);
+        assert_eq!(rgb(600, 20), BACKGROUND);
+        // Some of the first title line is drawn in the text colour
+        assert!((MARGIN as u32..600)
+            .flat_map(|x| (TITLE_TOP as u32..TITLE_TOP as u32 + 72).map(move |y| (x, y)))
+            .any(|(x, y)| rgb(x, y) == TEXT));
+
+        assert_eq!(card_path("card"), "/og/card.png");
+        assert_eq!(svg_number(LOGO, "height"), Some(300.0));
+        assert_eq!(
+            svg_number(
+                r#"<line x1="1" style="stroke:red;stroke-width:8" />"#,
+                "stroke-width"
+            ),
+            Some(8.0)
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! Full HTML of the posts and pages, so crawlers and readers without JavaScript get the page
+//! itself rather than an empty SPA.
+//!
+//! Every page is Trunk's `index.html` with its own head and the header, content and footer the
+//! frontend would render, built with the same markdown pipeline and the same classes. The SPA
+//! loads as usual and replaces the pre-rendered element once it renders the page.
+//! The untouched `index.html` is kept as [`SHELL_FILE`], the SPA fallback of the other routes,
+//! as the home page takes its place.
+
+use content_model::markdown::{parse_markdown_with_components, render_markdown_to_html};
+use content_model::{PostFrontmatter, PRERENDERED_ID};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+use crate::sitemap::encode_query_value;
+use crate::{escape_html, write_file, Post, AUTHOR};
+
+/// Trunk's `index.html` as it was before the home page was pre-rendered into it
+pub const SHELL_FILE: &str = "app.html";
+
+/// Document used when there is no Trunk build in `dist`, readable but without the SPA
+const BARE_SHELL: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="UTF-8" />
+  <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+  <link rel="alternate" type="application/atom+xml" title="gertjanassies.dev" href="/feed.xml" />
+  <link rel="alternate" type="application/rss+xml" title="gertjanassies.dev" href="/rss.xml" />
+  <link rel="alternate" type="application/feed+json" title="gertjanassies.dev" href="/feed.json" />
+</head>
+<body>
+</body>
+</html>
+"#;
+
+/// Tags of the SPA's head that describe one page, replaced by those of the pre-rendered page
+const PAGE_HEAD_TAGS: &[&str] = &[
+    r"<title>.*?</title>",
+    r#"<meta\s+name="(?:description|robots|twitter:[^"]*)"[^>]*>"#,
+    r#"<meta\s+property="(?:og|article):[^"]*"[^>]*>"#,
+    r#"<link\s+rel="canonical"[^>]*>"#,
+    r#"<script\s+type="application/ld\+json"[^>]*>.*?</script>"#,
+];
+
+/// The SPA's `index.html` split around the head and body content of a page
+pub struct Template {
+    /// Up to the end of the head, without the tags describing a page
+    head: String,
+    /// From the end of the head to the start of the body content
+    body_start: String,
+    /// From the end of the body content, the scripts Trunk may have put in the body
+    end: String,
+}
+
+impl Template {
+    /// Trunk's `index.html` in `dist`, kept as [`SHELL_FILE`] before the home page replaces it.
+    /// Later runs read the shell.
+    pub fn load(dist_dir: &Path) -> Template {
+        let shell = dist_dir.join(SHELL_FILE);
+        let html = match fs::read_to_string(dist_dir.join("index.html")) {
+            Ok(html) if !html.contains(&format!("id=\"{}\"", PRERENDERED_ID)) => {
+                write_file(&shell, &html);
+                Some(html)
+            }
+            _ => fs::read_to_string(&shell).ok(),
+        };
+
+        Template::parse(&html.unwrap_or_else(|| {
+            eprintln!(
+                "  [WARN] No index.html from Trunk in {}, pre-rendered pages won't start the SPA",
+                dist_dir.display()
+            );
+            BARE_SHELL.to_string()
+        }))
+    }
+
+    fn parse(html: &str) -> Template {
+        let mut html = html.to_string();
+        for pattern in PAGE_HEAD_TAGS {
+            let tag = Regex::new(&format!(r"(?s)[ \t]*{}[ \t]*\r?\n?", pattern))
+                .expect("head tag patterns are valid");
+            html = tag.replace_all(&html, "").into_owned();
+        }
+
+        // The head tags go on their own lines, before the indentation of `</head>`
+        let head_end = html
+            .find("</head>")
+            .map_or(0, |end| html[..end].trim_end_matches([' ', '\t']).len());
+        let body_start = html[head_end..]
+            .find("<body")
+            .and_then(|start| {
+                html[head_end + start..]
+                    .find('>')
+                    .map(|end| start + end + 1)
+            })
+            .map_or(head_end, |offset| head_end + offset);
+        // Whatever is in the body before the first script is placeholder content
+        let body_end = ["<script", "</body>"]
+            .iter()
+            .filter_map(|tag| html[body_start..].find(tag))
+            .min()
+            .map_or(html.len(), |offset| body_start + offset);
+
+        Template {
+            head: html[..head_end].to_string(),
+            body_start: html[head_end..body_start].to_string(),
+            end: html[body_end..].to_string(),
+        }
+    }
+
+    /// A page with its head tags and body content
+    pub fn render(&self, head: &str, body: &str) -> String {
+        format!(
+            "{}{}{}\n<div id=\"{}\">\n{}</div>\n{}",
+            self.head, head, self.body_start, PRERENDERED_ID, body, self.end
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+/// The `Header` component, with plain links and a search form that works without JavaScript
+const HEADER: &str = r#"<header>
+  <div class="header">
+    <div class="header-left">
+      <a href="/"><img src="/static/logo_ga.svg" alt="Logo Gertjan Assies" style="height: 50px; position: absolute; top: 10px; left: 10px;" /></a>
+      <h2>gertjanassies.dev</h2><br/>
+      <sub>ramblings of a chaotic mind</sub>
+    </div>
+    <div class="header-right">
+      <nav>
+        <a href="/">home</a>
+        <a href="/blog">blog</a>
+        <a href="/about">about</a>
+        <span>categories: </span>
+        <a href="/blog?category=code">code</a>
+        <a href="/blog?category=make">make</a>
+        <a href="/blog?category=tooling">tooling</a>
+        <a href="/blog?category=life">life</a>
+        <form class="header-search" role="search" action="/search">
+          <input type="search" name="q" placeholder="search" aria-label="Search posts" />
+        </form>
+      </nav>
+    </div>
+  </div>
+</header>
+"#;
+
+/// The `Footer` component, without the stats opt-out which needs the SPA
+const FOOTER: &str = r#"<footer>
+  <div class="footer">
+    Opinions expressed here are my own and not the views of my employer or anyone else, (re)use is free, but quoting the source is appreciated.
+    <br/>
+    This blog is licensed under a <a rel="license" href="http://creativecommons.org/licenses/by/4.0/">Creative Commons Attribution 4.0 International License.</a> © 2023-2025 by Gertjan Assies
+    <br/>
+    <span class="footer-privacy"><a href="/privacy">privacy</a></span>
+  </div>
+</footer>
+"#;
+
+fn layout(main: &str) -> String {
+    format!("{}<main>\n{}</main>\n{}", HEADER, main, FOOTER)
+}
+
+// ---------------------------------------------------------------------------
+// Content
+// ---------------------------------------------------------------------------
+
+/// Categories and tags of a post as links to their listing on the blog page
+fn taxonomy(frontmatter: &PostFrontmatter, class: &str) -> String {
+    let categories = frontmatter
+        .categories()
+        .map(|category| ("category", category));
+    let tags = frontmatter
+        .tags
+        .iter()
+        .filter(|tag| *tag != "featured")
+        .map(|tag| ("tag", tag.as_str()));
+
+    let links: String = categories
+        .chain(tags)
+        .map(|(key, value)| {
+            format!(
+                "<a class=\"{key} clickable\" href=\"/blog?{key}={}\">{}</a>",
+                escape_html(&encode_query_value(value)),
+                escape_html(value)
+            )
+        })
+        .collect();
+    format!("<div class=\"{}\">{}</div>\n", class, links)
+}
+
+fn author(frontmatter: &PostFrontmatter) -> String {
+    let author = match frontmatter.author.as_str() {
+        "" => AUTHOR,
+        author => author,
+    };
+    format!(
+        "<span class=\"post-author\">By {}</span>",
+        escape_html(author)
+    )
+}
+
+/// The `Posts` component: the published posts, or the featured ones, newest first
+fn post_list(posts: &[Post], featured_only: bool) -> String {
+    let items: String = posts
+        .iter()
+        .filter(|post| post.frontmatter.published)
+        .filter(|post| !featured_only || post.frontmatter.tags.iter().any(|t| t == "featured"))
+        .map(|post| {
+            let fm = &post.frontmatter;
+            let image = if fm.image.is_empty() {
+                "<div class=\"placeholder-image\"></div>".to_string()
+            } else {
+                format!(
+                    "<img src=\"{}\" alt=\"{}\" />",
+                    escape_html(&fm.image),
+                    escape_html(&fm.title)
+                )
+            };
+            let summary = if fm.summary.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "<p class=\"post-summary\">{}</p>\n",
+                    escape_html(&fm.summary)
+                )
+            };
+
+            format!(
+                r#"<article class="post-list-item">
+<div class="post-image-section">
+<div class="post-image">{image}</div>
+<div class="post-meta"><time class="post-date">{date}</time>{author}</div>
+</div>
+<div class="post-content">
+<div class="post-header">
+<h2 class="post-title"><a href="/post/{slug}">{title}</a></h2>
+{taxonomy}</div>
+{summary}</div>
+</article>
+"#,
+                date = fm.date_string(),
+                author = author(fm),
+                slug = escape_html(&post.slug),
+                title = escape_html(&fm.title),
+                taxonomy = taxonomy(fm, "post-taxonomy"),
+            )
+        })
+        .collect();
+
+    if items.is_empty() {
+        return "<div class=\"posts-container\">\n<div class=\"no-posts\"><p>No posts found matching the current filters.</p></div>\n</div>\n".to_string();
+    }
+    format!(
+        "<div class=\"posts-container\">\n<div class=\"posts-list\">\n{}</div>\n</div>\n",
+        items
+    )
+}
+
+/// Markdown with its components, those that show content rendered as static HTML
+fn markdown_html(markdown: &str, posts: &[Post]) -> String {
+    let mut html = String::new();
+    for part in parse_markdown_with_components(markdown) {
+        if !part.is_component {
+            html.push_str(&render_markdown_to_html(&part.content));
+            continue;
+        }
+
+        let attribute = |name: &str| part.attributes.get(name).map_or("", String::as_str);
+        match part.component_name.as_deref() {
+            Some("Posts") => html.push_str(&post_list(posts, attribute("featured_only") == "true")),
+            Some("Image") => html.push_str(&format!(
+                "<p><img class=\"image-thumbnail\" src=\"{}\" alt=\"{}\" /></p>\n",
+                escape_html(attribute("path")),
+                escape_html(attribute("alt"))
+            )),
+            _ => html.push_str("<p><em>This part of the page needs JavaScript.</em></p>\n"),
+        }
+    }
+    html
+}
+
+/// Body of a post, as the `PostView` component renders it
+pub fn post(post: &Post, posts: &[Post]) -> String {
+    let fm = &post.frontmatter;
+    let featured = if fm.tags.iter().any(|tag| tag == "featured") {
+        "<span class=\"featured-badge\">Featured</span>"
+    } else {
+        ""
+    };
+    let summary = if fm.summary.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<p class=\"post-view-summary\">{}</p>\n",
+            escape_html(&fm.summary)
+        )
+    };
+    let image = if fm.image.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<img class=\"post-view-featured-image\" src=\"{}\" alt=\"{}\" />\n",
+            escape_html(&fm.image),
+            escape_html(&fm.title)
+        )
+    };
+
+    layout(&format!(
+        r#"<div class="posts-container">
+<div class="post-view-container">
+<h1 class="post-view-title">{title}</h1>
+<div class="post-view-meta"><time class="post-date">{date}</time>{author}{featured}</div>
+{taxonomy}{summary}<div class="post-view-content">
+{image}<div class="post-markdown-content">
+{content}</div>
+</div>
+</div>
+</div>
+"#,
+        title = escape_html(&fm.title),
+        date = fm.date_string(),
+        author = author(fm),
+        taxonomy = taxonomy(fm, "post-view-taxonomy"),
+        content = markdown_html(&post.body, posts),
+    ))
+}
+
+/// Body of a page in `content/pages`, as the `Page` component renders it in the page's route
+pub fn page(slug: &str, markdown: &str, posts: &[Post]) -> String {
+    layout(&format!(
+        "<div class=\"{}-page\">\n<div class=\"page\">\n<div class=\"markdown-content\">\n{}</div>\n</div>\n</div>\n",
+        escape_html(slug),
+        markdown_html(markdown, posts)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(slug: &str, yaml: &str, body: &str) -> Post {
+        Post {
+            slug: slug.to_string(),
+            frontmatter: PostFrontmatter::from_yaml(yaml).unwrap(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_template() {
+        let template = Template::parse(
+            r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Blog</title>
+    <meta charset="utf-8" />
+    <meta name="description" content="Site" />
+    <meta property="og:title" content="Site" />
+    <link rel="canonical" href="https://example.com" />
+    <link rel="alternate" type="application/rss+xml" href="/rss.xml" />
+    <script type="application/ld+json" id="structured-data">{"@graph":[]}</script>
+    <script type="module">import init from '/app.js'; init();</script>
+  </head>
+  <body>
+    body
+  </body>
+</html>
+"#,
+        );
+        let html = template.render("<title>Post</title>\n", "<main>Hi</main>\n");
+
+        assert!(html.contains("<meta charset=\"utf-8\" />"));
+        assert!(html.contains("href=\"/rss.xml\""));
+        assert!(html.contains("import init"));
+        assert!(!html.contains("<title>Blog</title>"));
+        assert!(!html.contains("content=\"Site\""));
+        assert!(!html.contains("https://example.com"));
+        assert!(!html.contains("@graph"));
+        assert!(!html.contains("    body"));
+        assert!(html.contains("    <script type=\"module\">import init from '/app.js'; init();</script>\n<title>Post</title>\n  </head>"));
+        assert!(html.contains("<body>\n<div id=\"prerendered\">\n<main>Hi</main>\n</div>\n</body>"));
+    }
+
+    #[test]
+    fn test_post() {
+        let posts = vec![post(
+            "hello",
+            "title: Hello <World>\ndate: 2024-01-01\nsummary: Hi\ntags: [featured, C++]\ncategory: code",
+            "Some **bold** text\n\n<Image path=\"/content/images/a.png\" alt=\"A\" />\n\n<Certifications />",
+        )];
+        let html = super::post(&posts[0], &posts);
+
+        assert!(html.starts_with("<header>"));
+        assert!(html.contains("<a href=\"/about\">about</a>"));
+        assert!(html.contains("<h1 class=\"post-view-title\">Hello &lt;World&gt;</h1>"));
+        assert!(html.contains("<time class=\"post-date\">2024-01-01</time>"));
+        assert!(html.contains(&format!("By {}", AUTHOR)));
+        assert!(html.contains("featured-badge"));
+        assert!(
+            html.contains(r#"<a class="category clickable" href="/blog?category=code">code</a>"#)
+        );
+        assert!(html.contains(r#"<a class="tag clickable" href="/blog?tag=C%2B%2B">C++</a>"#));
+        assert!(!html.contains("tag=featured"));
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(
+            html.contains(r#"<img class="image-thumbnail" src="/content/images/a.png" alt="A" />"#)
+        );
+        assert!(html.contains("needs JavaScript"));
+        assert!(html.ends_with("</footer>\n"));
+    }
+
+    #[test]
+    fn test_page_with_posts() {
+        let posts = vec![
+            post("new", "title: New\ndate: 2024-02-01\ntags: featured", ""),
+            post("old", "title: Old\ndate: 2021-06-09\nsummary: Before", ""),
+            post(
+                "draft",
+                "title: Draft\ndate: 2025-01-01\npublished: false",
+                "",
+            ),
+        ];
+
+        let home = page("home", "Hello\n\n<Posts featured_only=\"true\" />", &posts);
+        assert!(home.contains("<div class=\"home-page\">"));
+        assert!(home.contains("<p>Hello</p>"));
+        assert!(home.contains("<a href=\"/post/new\">New</a>"));
+        assert!(!home.contains("/post/old"));
+
+        let blog = page("blog", "<Posts featured_only=\"false\" />", &posts);
+        assert!(blog.contains("<a href=\"/post/new\">New</a>"));
+        assert!(blog.contains("<p class=\"post-summary\">Before</p>"));
+        assert!(blog.find("/post/new") < blog.find("/post/old"));
+        assert!(!blog.contains("/post/draft"));
+
+        let empty = page("blog", "<Posts />", &[]);
+        assert!(empty.contains("no-posts"));
+    }
+}
@@ -0,0 +1,174 @@
+//! `sitemap.xml` and `robots.txt`, so search engines find every post without crawling the SPA.
+
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use crate::{escape_html, Post};
+
+/// Route of a page in `content/pages`, the home page is served at the root
+pub fn page_path(slug: &str) -> String {
+    match slug {
+        "home" => "/".to_string(),
+        slug => format!("/{}", slug),
+    }
+}
+
+/// Percent-encode a query string value, keeping the unreserved characters
+pub fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Newest date per tag or category, over the posts listed under it
+fn listings<'a>(
+    posts: &[&'a Post],
+    keys: impl Fn(&'a Post) -> Vec<&'a str>,
+) -> BTreeMap<&'a str, Option<NaiveDate>> {
+    let mut listings: BTreeMap<&str, Option<NaiveDate>> = BTreeMap::new();
+    for post in posts {
+        for key in keys(post) {
+            let lastmod = listings.entry(key).or_default();
+            *lastmod = (*lastmod).max(post.frontmatter.date);
+        }
+    }
+    listings
+}
+
+fn url_entry(base_url: &str, path: &str, lastmod: Option<NaiveDate>) -> String {
+    let lastmod = lastmod
+        .map(|date| format!("\n    <lastmod>{}</lastmod>", date))
+        .unwrap_or_default();
+    format!(
+        "  <url>\n    <loc>{}</loc>{}\n  </url>\n",
+        escape_html(&format!("{}{}", base_url, path)),
+        lastmod
+    )
+}
+
+/// Every published post, every page, and the blog listing of every tag and category. Posts
+/// carry their frontmatter date as `lastmod`; listings, the home page and the blog page the
+/// date of their newest post.
+pub fn sitemap(base_url: &str, posts: &[Post], pages: &[String]) -> String {
+    let published: Vec<&Post> = posts
+        .iter()
+        .filter(|post| post.frontmatter.published)
+        .collect();
+    let newest = published
+        .iter()
+        .filter_map(|post| post.frontmatter.date)
+        .max();
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for slug in pages {
+        let lastmod = matches!(slug.as_str(), "home" | "blog")
+            .then_some(newest)
+            .flatten();
+        xml.push_str(&url_entry(base_url, &page_path(slug), lastmod));
+    }
+
+    for post in &published {
+        let path = format!("/post/{}", post.slug);
+        xml.push_str(&url_entry(base_url, &path, post.frontmatter.date));
+    }
+
+    let categories = listings(&published, |post| post.frontmatter.categories().collect());
+    for (category, lastmod) in categories {
+        let path = format!("/blog?category={}", encode_query_value(category));
+        xml.push_str(&url_entry(base_url, &path, lastmod));
+    }
+
+    let tags = listings(&published, |post| {
+        post.frontmatter.tags.iter().map(String::as_str).collect()
+    });
+    for (tag, lastmod) in tags {
+        let path = format!("/blog?tag={}", encode_query_value(tag));
+        xml.push_str(&url_entry(base_url, &path, lastmod));
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Allow everything but the API and the dashboard, and point crawlers to the sitemap. Drafts
+/// aren't listed, which would give their slugs away; their pages are marked `noindex` instead.
+pub fn robots(base_url: &str) -> String {
+    format!(
+        "User-agent: AdsBot-Google\nDisallow: /\n\nUser-agent: *\nAllow: /\nDisallow: /api/\nDisallow: /stats\n\nSitemap: {}/sitemap.xml\n",
+        base_url
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use content_model::PostFrontmatter;
+
+    fn post(slug: &str, yaml: &str) -> Post {
+        Post {
+            slug: slug.to_string(),
+            frontmatter: PostFrontmatter::from_yaml(yaml).unwrap(),
+            body: String::new(),
+        }
+    }
+
+    fn posts() -> Vec<Post> {
+        vec![
+            post(
+                "new",
+                "title: New\ndate: 2024-02-01\ntags: [rust, C++]\ncategory: code, make",
+            ),
+            post(
+                "old",
+                "title: Old\ndate: 2021-06-09\ntags: rust\ncategory: code",
+            ),
+            post(
+                "draft",
+                "title: Draft\ndate: 2025-01-01\ntags: secret\npublished: false",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_sitemap() {
+        let pages = ["about".to_string(), "home".to_string()];
+        let xml = sitemap("https://example.com", &posts(), &pages);
+
+        assert!(xml.contains("<loc>https://example.com/</loc>\n    <lastmod>2024-02-01</lastmod>"));
+        assert!(xml.contains("<loc>https://example.com/about</loc>\n  </url>"));
+        assert!(xml.contains(
+            "<loc>https://example.com/post/old</loc>\n    <lastmod>2021-06-09</lastmod>"
+        ));
+        assert!(xml.contains(
+            "<loc>https://example.com/blog?category=code</loc>\n    <lastmod>2024-02-01</lastmod>"
+        ));
+        assert!(xml.contains("<loc>https://example.com/blog?tag=C%2B%2B</loc>"));
+        assert!(!xml.contains("draft"));
+        assert!(!xml.contains("secret"));
+        assert!(xml.ends_with("</urlset>\n"));
+    }
+
+    #[test]
+    fn test_query_values_are_percent_encoded() {
+        let posts = [post("amp", "title: Amp\ndate: 2024-01-01\ntags: [\"a&b\"]")];
+        let xml = sitemap("https://example.com", &posts, &[]);
+        assert!(xml.contains("<loc>https://example.com/blog?tag=a%26b</loc>"));
+    }
+
+    #[test]
+    fn test_robots() {
+        let robots = robots("https://example.com");
+        assert!(robots.contains("Disallow: /stats\n"));
+        assert!(!robots.contains("/post/"));
+        assert!(robots.ends_with("Sitemap: https://example.com/sitemap.xml\n"));
+    }
+}
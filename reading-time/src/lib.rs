@@ -0,0 +1,79 @@
+//! Reading time and word count of a markdown text, without dependencies so it builds for wasm as well.
+
+/// Words read per minute
+const WPM: u16 = 200;
+
+/// Seconds it takes to read the markdown at [`WPM`] words per minute
+pub fn calculate_reading_time(content: &str) -> u64 {
+    let word_count = count_words(content);
+    if word_count == 0 {
+        return 0;
+    }
+
+    let minutes = word_count as f64 / WPM as f64;
+    (minutes * 60.0) as u64
+}
+
+/// Words in the markdown, leaving out headers, code fences and formatting characters
+pub fn count_words(content: &str) -> usize {
+    content
+        // Remove markdown headers
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            // Skip lines that are purely markdown syntax
+            if line.starts_with('#')
+                || line.starts_with("```")
+                || line.starts_with("---")
+                || line.is_empty()
+            {
+                String::new()
+            } else {
+                // Remove inline markdown formatting
+                line.replace("**", "") // Bold
+                    .replace("*", "") // Italic
+                    .replace("`", "") // Code
+                    .replace("_", "") // Underscore formatting
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        // Split by whitespace and filter empty strings
+        .split_whitespace()
+        .filter(|word| !word.is_empty())
+        .count()
+}
+
+/// Hash of the markdown a reading time was calculated from (64-bit FNV-1a as hex), stable
+/// across builds and platforms so a stored hash can be compared with a fresh one
+pub fn content_hash(content: &str) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = content.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_time_skips_markdown_syntax() {
+        let words = vec!["word"; 200].join(" ");
+        let markdown = format!("# Title\n\n```rust\n{}\n```\n---\n**{}**", words, words);
+
+        // The heading, fences and rule don't count, the code and bold text do
+        assert_eq!(calculate_reading_time(&markdown), 120);
+        assert_eq!(calculate_reading_time("# Only a heading"), 0);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash("post"), content_hash("post edited"));
+    }
+}
@@ -0,0 +1,139 @@
+#!/usr/bin/env python3
+"""
+Writes a tiny MaxMind DB (GeoIP2-Country layout) used by the backend's GeoIP tests:
+  81.2.69.0/24    -> GB
+  89.160.20.0/24  -> SE
+  2001:db8::/32   -> NL
+
+Only the parts of the format (https://maxmind.github.io/MaxMind-DB/) the fixture needs are
+implemented: 24-bit records, strings, unsigned integers, maps and arrays.
+
+Usage: python3 tools/make_geoip_fixture.py [output]
+"""
+
+import ipaddress
+import pathlib
+import sys
+
+
+OUTPUT = pathlib.Path(sys.argv[1]) if len(sys.argv) > 1 else pathlib.Path(
+    "backend/fixtures/geoip-country.mmdb"
+)
+
+NETWORKS = {
+    "81.2.69.0/24": "GB",
+    "89.160.20.0/24": "SE",
+    "2001:db8::/32": "NL",
+}
+
+METADATA_MARKER = b"\xab\xcd\xefMaxMind.com"
+RECORD_SIZE = 24
+# Fixed so regenerating the fixture gives the same file
+BUILD_EPOCH = 1_700_000_000
+
+
+def control(type_, size):
+    """Control byte(s) of a field, types above 7 use the extended type byte"""
+    assert size < 29
+    if type_ <= 7:
+        return bytes([(type_ << 5) | size])
+    return bytes([size, type_ - 7])
+
+
+def encode(value):
+    if isinstance(value, str):
+        data = value.encode()
+        return control(2, len(data)) + data
+    if isinstance(value, int):
+        data = value.to_bytes((value.bit_length() + 7) // 8, "big")
+        type_ = 6 if value < 2**32 else 9  # uint32 or uint64
+        return control(type_, len(data)) + data
+    if isinstance(value, dict):
+        return control(7, len(value)) + b"".join(
+            encode(key) + encode(item) for key, item in value.items()
+        )
+    if isinstance(value, list):
+        return control(11, len(value)) + b"".join(encode(item) for item in value)
+    raise TypeError(value)
+
+
+def uint(type_, value):
+    """Unsigned integer of a given type, the metadata fields have fixed types"""
+    data = value.to_bytes((value.bit_length() + 7) // 8, "big")
+    return control(type_, len(data)) + data
+
+
+def uint16(value):
+    return uint(5, value)
+
+
+def bits(network):
+    """Bits of the network's prefix in the IPv6 tree, IPv4 networks live below ::/96"""
+    address = network.network_address
+    if network.version == 4:
+        address = ipaddress.IPv6Address(int(address))
+        prefix = 96 + network.prefixlen
+    else:
+        prefix = network.prefixlen
+    value = int(address)
+    return [(value >> (127 - i)) & 1 for i in range(prefix)]
+
+
+def main():
+    data = b""
+    root = [None, None]
+    for cidr, iso_code in NETWORKS.items():
+        offset = len(data)
+        data += encode({"country": {"iso_code": iso_code}})
+
+        node = root
+        path = bits(ipaddress.ip_network(cidr))
+        for bit in path[:-1]:
+            if node[bit] is None:
+                node[bit] = [None, None]
+            node = node[bit]
+        node[path[-1]] = ("data", offset)
+
+    # Number the nodes breadth first, the root is node 0
+    nodes, queue = [], [root]
+    while queue:
+        node = queue.pop(0)
+        nodes.append(node)
+        queue.extend(child for child in node if isinstance(child, list))
+    numbers = {id(node): number for number, node in enumerate(nodes)}
+    node_count = len(nodes)
+
+    def record(child):
+        if child is None:
+            return node_count
+        if isinstance(child, list):
+            return numbers[id(child)]
+        return node_count + 16 + child[1]
+
+    tree = b"".join(
+        record(left).to_bytes(3, "big") + record(right).to_bytes(3, "big")
+        for left, right in nodes
+    )
+
+    metadata = control(7, 9) + b"".join(
+        encode(key) + value
+        for key, value in [
+            ("binary_format_major_version", uint16(2)),
+            ("binary_format_minor_version", uint16(0)),
+            ("build_epoch", uint(9, BUILD_EPOCH)),
+            ("database_type", encode("GeoIP2-Country")),
+            ("description", encode({"en": "GeoIP test fixture"})),
+            ("ip_version", uint16(6)),
+            ("languages", encode(["en"])),
+            ("node_count", encode(node_count)),
+            ("record_size", uint16(RECORD_SIZE)),
+        ]
+    )
+
+    OUTPUT.parent.mkdir(parents=True, exist_ok=True)
+    OUTPUT.write_bytes(tree + bytes(16) + data + METADATA_MARKER + metadata)
+    print(f"Wrote {OUTPUT} ({node_count} nodes)")
+
+
+if __name__ == "__main__":
+    main()
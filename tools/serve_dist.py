@@ -1,10 +1,12 @@
 #!/usr/bin/env python3
 """
 Minimal static file server that replicates Nginx's try_files behaviour:
-  try_files $uri $uri/index.html /index.html
+  try_files $uri $uri/index.html /app.html /index.html
 
 Used by `make serve-dist` to test the generated dist/ locally, including
-the meta-page sidecars at dist/post/{slug}/index.html.
+the pages meta-gen pre-renders at dist/post/{slug}/index.html and
+dist/{page}/index.html. meta-gen keeps Trunk's index.html as app.html,
+the SPA fallback, before pre-rendering the home page into index.html.
 
 Usage: python3 tools/serve_dist.py [port] [dist_dir]
 """
@@ -26,10 +28,11 @@ class TryFilesHandler(http.server.SimpleHTTPRequestHandler):
         # Strip query string, remove leading slash
         rel = self.path.split("?")[0].lstrip("/")
 
-        # try_files: $uri, $uri/index.html, /index.html (SPA fallback)
+        # try_files: $uri, $uri/index.html, /app.html, /index.html (SPA fallback)
         candidates = [
             DIST_DIR / rel,
             DIST_DIR / rel / "index.html",
+            DIST_DIR / "app.html",
             DIST_DIR / "index.html",
         ]
 
@@ -52,7 +55,7 @@ if __name__ == "__main__":
         sys.exit(1)
 
     print(f"Serving {DIST_DIR.resolve()} on http://localhost:{PORT}")
-    print("Real files are served directly; unknown paths fall back to index.html.")
+    print("Real files are served directly; unknown paths fall back to the SPA.")
     print("Press Ctrl+C to stop.\n")
 
     server = http.server.HTTPServer(("", PORT), TryFilesHandler)